@@ -1,4 +1,5 @@
-use crate::euclidian::modinv;
+use crate::euclidian::mod_inverse;
+use crate::math::{mod_add, mod_mul, mod_sub};
 use bigint::uint::U512;
 use crate::constants::*;
 
@@ -9,6 +10,7 @@ pub struct MontgomeryPoint {
     pub infinity: bool,
 }
 
+#[allow(clippy::clone_on_copy)]
 impl MontgomeryPoint {
     pub fn add(&self, other: &MontgomeryPoint) -> MontgomeryPoint {
         let f = U512::from_big_endian(&FIELD);
@@ -16,24 +18,17 @@ impl MontgomeryPoint {
         if other.infinity { return self.clone(); }
         if (self.x == other.x) && (self.y == f - other.y) { return MontgomeryPoint::default(); }
         let a = U512::from(486662u32);
-        let lambda = self.slope(*other, f);
-        let lambda_s = (lambda * lambda) % f;
-        let lambda_c = (lambda_s * lambda) % f;
-        let x3;
-        if lambda_s > (a + self.x + other.x) % f {
-            x3 = lambda_s - ((a + self.x + other.x) % f);
-        }
-        else {
-            x3 = f - (((a + self.x + other.x) % f) - lambda_s);
-        }
-        let o = (((U512::from(2u32) * self.x) % f) + other.x + a) % f;
-        let y3;
-        if (o * lambda) % f > (lambda_c + self.y) % f {
-            y3 = ((o * lambda) % f) - ((lambda_c + self.y) % f);
-        }
-        else {
-            y3 = f - (((lambda_c + self.y) % f) - ((o * lambda) % f));
-        }
+        // No slope means a vertical line (doubling a point with y = 0), which only meets the curve
+        // again at infinity.
+        let lambda = match self.slope(*other, f) {
+            Some(lambda) => lambda,
+            None => return MontgomeryPoint::default(),
+        };
+        let lambda_s = mod_mul(lambda, lambda, f);
+        let lambda_c = mod_mul(lambda_s, lambda, f);
+        let x3 = mod_sub(lambda_s, mod_add(mod_add(a, self.x, f), other.x, f), f);
+        let o = mod_add(mod_add(mod_add(self.x, self.x, f), other.x, f), a, f);
+        let y3 = mod_sub(mod_mul(o, lambda, f), mod_add(lambda_c, self.y, f), f);
         MontgomeryPoint::new(x3, y3)
     }
 
@@ -80,35 +75,24 @@ impl MontgomeryPoint {
     pub fn on_curve(&self) -> bool {
         let a = U512::from(486662u32);
         let f = U512::from_big_endian(&FIELD);
-        (self.y * self.y) % f == (((((((self.x * self.x) % f) * self.x) % f) + ((((self.x * self.x) % f) * a) % f)) % f) + self.x) % f
+        let x2 = mod_mul(self.x, self.x, f);
+        let rhs = mod_add(mod_add(mod_mul(x2, self.x, f), mod_mul(x2, a, f), f), self.x, f);
+        mod_mul(self.y, self.y, f) == rhs
     }
 
-    pub fn slope(&self, other: MontgomeryPoint, f: U512) -> U512 {
+    pub fn slope(&self, other: MontgomeryPoint, f: U512) -> Option<U512> {
         if self.eq(&other) { return self.implicit(f); }
-        let n;
-        if other.y > self.y {
-            n = other.y - self.y;
-        }
-        else {
-            n = f - (self.y - other.y);
-        }
-        let d;
-        if other.x > self.x {
-            d = other.x - self.x;
-        }
-        else {
-            d = f - (self.x - other.x);
-        }
-        let d = modinv(d, f);
-        (d * n) % f
+        let n = mod_sub(other.y, self.y, f);
+        let d = mod_inverse(mod_sub(other.x, self.x, f), f)?;
+        Some(mod_mul(d, n, f))
     }
 
-    pub fn implicit(&self, f: U512) -> U512 {
+    pub fn implicit(&self, f: U512) -> Option<U512> {
         let a = U512::from(486662u32);
-        let n = (((U512::from(3u32) * ((self.x * self.x) % f)) % f) + ((U512::from(2u32) * ((a * self.x) % f)) % f) + (U512::one())) % f;
-        let d = (self.y * U512::from(2u32)) % f;
-        let d = modinv(d, f);
-        (n * d) % f
+        let ax = mod_mul(a, self.x, f);
+        let n = mod_add(mod_add(mod_mul(U512::from(3u32), mod_mul(self.x, self.x, f), f), mod_add(ax, ax, f), f), U512::one(), f);
+        let d = mod_inverse(mod_add(self.y, self.y, f), f)?;
+        Some(mod_mul(n, d, f))
     }
 
     pub fn new(x: U512, y: U512) -> Self {
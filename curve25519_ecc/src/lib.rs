@@ -4,6 +4,7 @@ use bigint::uint::U512;
 use rand::*;
 
 mod ecmult;
+mod math;
 mod euclidian;
 mod constants;
 
@@ -90,7 +91,7 @@ pub fn curve25519_runthrough() -> bool {
     let f = U512::from_big_endian(&FIELD);
     let privkey = generate_curve25519_privkey(&mut rand::thread_rng());
     let pubkey = derive_curve25519_pubkey(privkey);
-    let message = derive_curve25519_pubkey((privkey * privkey) % f);
+    let message = derive_curve25519_pubkey(math::mod_mul(privkey, privkey, f));
     let (c1, c2) = encrypt_curve25519(&mut rand::thread_rng(), pubkey, message);
     let res = decrypt_curve25519(privkey, c1, c2);
     res == message
@@ -101,7 +102,7 @@ pub fn curve25519_runthrough_f() -> bool {
     let f = U512::from_big_endian(&FIELD);
     let privkey = generate_curve25519_privkey(&mut rand::thread_rng());
     let pubkey = derive_curve25519_pubkey(privkey);
-    let message = derive_curve25519_pubkey((privkey * privkey) % f);
+    let message = derive_curve25519_pubkey(math::mod_mul(privkey, privkey, f));
     let (c1, c2) = encrypt_curve25519(&mut rand::thread_rng(), pubkey, message);
     let res = decrypt_curve25519(privkey + U512::one(), c1, c2);
     res != message
@@ -114,7 +115,7 @@ pub fn curve25519_runthrough_constant_time(i: usize) -> bool {
     let _privkey = generate_curve25519_privkey(&mut rand::thread_rng());
     let privkey = U512::from(10 + i as u32);
     let pubkey = derive_curve25519_pubkey(privkey);
-    let message = derive_curve25519_pubkey((privkey * privkey) % f);
+    let message = derive_curve25519_pubkey(math::mod_mul(privkey, privkey, f));
     let (c1, c2) = encrypt_curve25519(&mut rand::thread_rng(), pubkey, message);
     let res = decrypt_curve25519(privkey, c1, c2);
     res == message
@@ -131,7 +132,7 @@ mod tests {
         let f = U512::from_big_endian(&FIELD);
         let privkey = generate_curve25519_privkey(&mut rand::thread_rng());
         let pubkey = derive_curve25519_pubkey(privkey);
-        let message = derive_curve25519_pubkey((privkey * privkey) % f);
+        let message = derive_curve25519_pubkey(math::mod_mul(privkey, privkey, f));
         let (c1, c2) = encrypt_curve25519(&mut rand::thread_rng(), pubkey, message);
         let res = decrypt_curve25519(privkey, c1, c2);
         assert_eq!(res, message);
@@ -164,6 +165,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::clone_on_copy)]
     fn curve25519_constant_time() {
         let mut v = Vec::new();
         for i in 0..10 {
@@ -175,4 +177,20 @@ mod tests {
             thread.join().unwrap();
         }
     }
+
+    #[test]
+    fn field_arithmetic() {
+        let f = U512::from_big_endian(&FIELD);
+        let a = U512::from_dec_str("33181666278292049635378873366025400822382871298992342469807652648728459836780").unwrap();
+        let b = U512::from_dec_str("43944504835141045868087342835229961628335242640059027412329015532553086831168").unwrap();
+        let ab = U512::from_dec_str("1119233016813478935049982533489268774852738866792905639378395678122614955862").unwrap();
+        assert_eq!(math::mod_mul(a, b, f), ab);
+        assert_eq!(math::mod_mul(f - U512::one(), f - U512::one(), f), U512::one());
+        assert_eq!(math::mod_add(f - U512::one(), U512::one(), f), U512::zero());
+        assert_eq!(math::mod_sub(U512::zero(), U512::one(), f), f - U512::one());
+        assert_eq!(math::mod_sub(a, a, f), U512::zero());
+        assert_eq!(math::mod_mul(euclidian::mod_inverse(a, f).unwrap(), a, f), U512::one());
+        assert_eq!(euclidian::mod_inverse(U512::zero(), f), None);
+        assert_eq!(euclidian::mod_inverse(f, f), None);
+    }
 }
@@ -2,6 +2,7 @@
 
 use bigint::uint::U512;
 use crate::constants::*;
+use crate::euclidian::mod_inverse;
 use crate::utils::*;
 use crate::montgomery::MontgomeryPoint;
 use crate::projective::ProjectiveEdwardsPoint;
@@ -31,8 +32,18 @@ impl EdwardsPoint {
     pub fn into_montgomery(&self) -> MontgomeryPoint {
         let f = U512::from_big_endian(&FIELD);
         let sf = U512::from_big_endian(&INV_FACTOR);
-        let u = ((self.y + U512::one()) * modinv(f - (self.y - U512::one()), f)) % f;
-        let v = (((u * modinv(self.x, f)) % f) * sf) % f;
+        // y = 1 is the identity, which goes to the point at infinity.
+        let d = match mod_inverse((f + U512::one() - self.y) % f, f) {
+            Some(d) => d,
+            None => return MontgomeryPoint::default(),
+        };
+        let u = ((self.y + U512::one()) * d) % f;
+        // x = 0 otherwise only happens for (0, -1), the point of order two, which is (0, 0) on the
+        // Montgomery curve (u came out as 0 above already).
+        let v = match mod_inverse(self.x, f) {
+            Some(x_inv) => (((u * x_inv) % f) * sf) % f,
+            None => U512::zero(),
+        };
         MontgomeryPoint::new(u, v)
     }
 
@@ -51,9 +62,13 @@ impl EdwardsPoint {
         let b = (((((((((d * self.x) % f) * other.x) % f) * self.y) % f) * other.y) % f) + U512::one()) % f;
         let c = (((self.y * other.y) % f) + ((self.x) * other.x) % f) % f;
         let e = difference(U512::one(), (((((((d * self.x) % f) * other.x) % f) * self.y) % f) * other.y) % f);
-        let x3 = (modinv(b, f) * a) % f;
-        let y3 = (modinv(e, f) * c) % f;
-        EdwardsPoint::new(x3, y3)
+        // d isn't a square, so 1 ± d x1 x2 y1 y2 is never zero for points on the curve (the
+        // formulas are complete). Anything else isn't a point we can add; give back the identity
+        // the way the Montgomery code gives back the point at infinity.
+        match (mod_inverse(b, f), mod_inverse(e, f)) {
+            (Some(b), Some(e)) => EdwardsPoint::new((b * a) % f, (e * c) % f),
+            _ => EdwardsPoint::default(),
+        }
     }
 
     pub fn double(&self) -> EdwardsPoint {
@@ -72,9 +87,11 @@ impl EdwardsPoint {
         let b = difference(ys, xs);
         let c = (xs + ys) % f;
         let d = difference((U512::from(2u32) + xs) % f, ys);
-        let x3 = (modinv(b, f) * a) % f;
-        let y3 = (modinv(d, f) * c) % f;
-        EdwardsPoint::new(x3, y3)
+        // Same as add: neither denominator vanishes on the curve.
+        match (mod_inverse(b, f), mod_inverse(d, f)) {
+            (Some(b), Some(d)) => EdwardsPoint::new((b * a) % f, (d * c) % f),
+            _ => EdwardsPoint::default(),
+        }
     }
 
     pub fn naf_ecmult(&self, s: &U512) -> EdwardsPoint {
@@ -211,5 +228,44 @@ mod edwards_tests {
         }
     }
 
+
+    #[test]
+    pub fn exceptional_points() {
+        use crate::euclidian::mod_inverse;
+
+        let f = U512::from_big_endian(&FIELD);
+        let g = EdwardsPoint::generator();
+        assert_eq!((mod_inverse(g.x, f).unwrap() * g.x) % f, U512::one());
+        assert_eq!(mod_inverse(U512::zero(), f), None);
+        assert_eq!(mod_inverse(f, f), None);
+
+        // The identity and the point at infinity map to each other, and so do the two points of
+        // order two, (0, -1) and (0, 0). Those are exactly where the maps divide by zero.
+        let identity = EdwardsPoint::default();
+        let order_two = EdwardsPoint::new(U512::zero(), f - U512::one());
+        assert!(identity.valid() && order_two.valid());
+        assert_eq!(identity.into_montgomery(), MontgomeryPoint::default());
+        assert_eq!(MontgomeryPoint::default().into_edwards(), identity);
+        assert_eq!(order_two.into_montgomery(), MontgomeryPoint::new(U512::zero(), U512::zero()));
+        assert_eq!(MontgomeryPoint::new(U512::zero(), U512::zero()).into_edwards(), order_two);
+
+        // Doubling (0, 0) is a vertical tangent, so it lands on infinity, and (0, -1) doubles to
+        // the identity.
+        let m = MontgomeryPoint::new(U512::zero(), U512::zero());
+        assert_eq!(m.add(&m), MontgomeryPoint::default());
+        assert_eq!(order_two.double(), identity);
+        assert_eq!(order_two.add(&order_two), identity);
+        assert_eq!(g.add(&identity), g);
+
+        // (sqrt(-1), 0) has order four and y = 0, which made 1 - y underflow.
+        let i = U512::from_dec_str("19681161376707505956807079304988542015446066515923890162744021073123829784752").unwrap();
+        let order_four = EdwardsPoint::new(i, U512::zero());
+        assert!(order_four.valid());
+        assert_eq!(order_four.into_montgomery().into_edwards(), order_four);
+        assert_eq!(order_four.double(), order_two);
+
+        // Z = 0 in projective coordinates isn't a point.
+        assert_eq!(ProjectiveEdwardsPoint::new(g.x, g.y, U512::zero(), U512::zero()).into_edwards(), identity);
+    }
 }
 
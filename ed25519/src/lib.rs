@@ -1,5 +1,6 @@
 mod montgomery;
 mod utils;
+mod euclidian;
 mod constants;
 mod edwards;
 mod window;
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use crate::euclidian::mod_inverse;
 use crate::utils::*;
 use bigint::uint::U512;
 use crate::constants::*;
@@ -28,7 +29,12 @@ impl MontgomeryPoint {
         if other.infinity { return self.clone(); }
         if (self.x == other.x) && (self.y == f - other.y) { return MontgomeryPoint::default(); }
         let a = U512::from(486662u32);
-        let lambda = self.slope(other, f);
+        // No slope means a vertical line (doubling a point with y = 0), which only meets the curve
+        // again at infinity.
+        let lambda = match self.slope(other, f) {
+            Some(lambda) => lambda,
+            None => return MontgomeryPoint::default(),
+        };
         let lambda_s = (lambda * lambda) % f;
         let lambda_c = (lambda_s * lambda) % f;
         let x3;
@@ -96,7 +102,7 @@ impl MontgomeryPoint {
         res
     }
 
-    pub fn slope(&self, other: &MontgomeryPoint, f: U512) -> U512 {
+    pub fn slope(&self, other: &MontgomeryPoint, f: U512) -> Option<U512> {
         if self.eq(&other) { return self.implicit(f); }
         let n;
         if other.y > self.y {
@@ -112,16 +118,16 @@ impl MontgomeryPoint {
         else {
             d = f - (self.x - other.x);
         }
-        let d = modinv(d, f);
-        (d * n) % f
+        let d = mod_inverse(d, f)?;
+        Some((d * n) % f)
     }
 
-    pub fn implicit(&self, f: U512) -> U512 {
+    pub fn implicit(&self, f: U512) -> Option<U512> {
         let a = U512::from(486662u32);
         let n = (((U512::from(3u32) * ((self.x * self.x) % f)) % f) + ((U512::from(2u32) * ((a * self.x) % f)) % f) + (U512::one())) % f;
         let d = (self.y * U512::from(2u32)) % f;
-        let d = modinv(d, f);
-        (n * d) % f
+        let d = mod_inverse(d, f)?;
+        Some((n * d) % f)
     }
 
     pub fn on_curve(&self) -> bool {
@@ -145,8 +151,18 @@ impl MontgomeryPoint {
     pub fn into_edwards(&self) -> EdwardsPoint {
         let f = U512::from_big_endian(&FIELD);
         let sf = U512::from_big_endian(&INV_FACTOR);
-        let y = ((self.x - U512::one()) * modinv(self.x + U512::one(), f)) % f;
-        let x = (sf * ((self.x * modinv(self.y, f)) % f)) % f;
+        if self.infinity { return EdwardsPoint::default(); }
+        // u = -1 isn't on the curve (A - 2 isn't a square), so for points on it this only fails
+        // for garbage, which gets the identity just like a failed add.
+        let y = match mod_inverse(self.x + U512::one(), f) {
+            Some(d) => (((self.x + f - U512::one()) % f) * d) % f,
+            None => return EdwardsPoint::default(),
+        };
+        // v = 0 is (0, 0), the point of order two, which is (0, -1) on the Edwards curve.
+        let x = match mod_inverse(self.y, f) {
+            Some(v_inv) => (sf * ((self.x * v_inv) % f)) % f,
+            None => U512::zero(),
+        };
         EdwardsPoint::new(x, y)
     }
 }
@@ -2,6 +2,7 @@
 
 use bigint::uint::U512;
 use crate::constants::*;
+use crate::euclidian::mod_inverse;
 use crate::utils::*;
 use crate::edwards::EdwardsPoint;
 
@@ -26,7 +27,11 @@ impl ProjectiveEdwardsPoint {
 
     pub fn into_edwards(&self) -> EdwardsPoint {
         let f = U512::from_big_endian(&FIELD);
-        let z = modinv(self.z, f);
+        // Z = 0 isn't a point at all (extended coordinates always have Z != 0).
+        let z = match mod_inverse(self.z, f) {
+            Some(z) => z,
+            None => return EdwardsPoint::default(),
+        };
         let x = (self.x * z) % f;
         let y = (self.y * z) % f;
         EdwardsPoint::new(x, y)
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use crate::edwards::*;
 use bigint::uint::U512;
-use crate::utils::*;
+use crate::euclidian::mod_inverse;
 use crate::constants::*;
 
 #[derive(PartialEq, Clone, Copy)]
@@ -38,9 +38,11 @@ impl ProjectiveEdwardsPoint {
 
     pub fn into_edwards(&self) -> EdwardsPoint {
         let field = U512::from_big_endian(&FIELD);
-        let x = modinv(self.z, field) * self.x;
-        let y = modinv(self.z, field) * self.y;
-        EdwardsPoint::new(x % field, y % field)
+        let z = match mod_inverse(self.z, field) {
+            Some(z) => z,
+            None => return EdwardsPoint::default(),
+        };
+        EdwardsPoint::new((z * self.x) % field, (z * self.y) % field)
     }
 
     pub fn add_no_assumptions(&self, other: &ProjectiveEdwardsPoint) -> ProjectiveEdwardsPoint {
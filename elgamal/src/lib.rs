@@ -1,159 +1,273 @@
-use rand::RngCore;
-use bigint::U512;
+use rand::{CryptoRng, RngCore};
+use num_bigint::BigUint;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
 mod math;
-mod euclidian;
-
-const BASEPOINT_ORDER_ARRAY: [u8; 32] = 
-    [
-        0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58,
-        0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
-        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
-    ];
-
-const GENERATOR_ARRAY: [u8; 32] = 
-    [
-        0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
-        0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
-        0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
-        0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66
-    ];
-
-/// For the sake of simplicity, we are going to use the ed25519 generator and prime field for the
-/// key calculations.
+mod group;
 
+pub use crate::group::DhGroup;
 
 /// derive_public_key will find a user's public key from an existing private key. Users can simply
-/// generate a public key by picking a random number ranging from 
-/// 0 to (252^2 + 27742317777372353535851937790883648493).
+/// generate a private key by picking a random number from 1 to q - 1, where q is the order of the
+/// group (DhGroup::q).
 ///
+/// Input: group (group) -> the Diffie-Hellman group both sides agreed on.
 /// Input: private key (privkey) -> the private key of the user.
 ///
-/// Output: user's corresponding public key.
-#[allow(non_snake_case)]
-pub fn derive_public_key(privkey: U512) -> U512 {
-    let GENERATOR: U512 = U512::from_little_endian(&GENERATOR_ARRAY);
-    let BASEPOINT_ORDER: U512 = U512::from_little_endian(&BASEPOINT_ORDER_ARRAY);
-
-    math::mod_exp(GENERATOR, privkey, BASEPOINT_ORDER)
+/// Output: user's corresponding public key, g^privkey (mod p).
+pub fn derive_public_key(group: &DhGroup, privkey: &BigUint) -> BigUint {
+    math::mod_exp_ct(group.g(), privkey, group.p())
 }
 
-/// encrypt_elgamal will encrypt an ascii message using an existing public key. The public key must
+/// encrypt_elgamal will encrypt a message using an existing public key. The public key must
 /// have a user owning the private key or the message will be lost forever! (That being until the
-/// DHP is solved). 
+/// DHP is solved).
 ///
+/// The message has to live in the subgroup too, or c2 would give away which coset it came from.
+/// Exactly one of m and p - m is a quadratic residue (p is 3 mod 4, so -1 isn't one), and that's
+/// the one that gets encrypted; decrypt_elgamal maps it back.
+///
+/// Input: group (group) -> the group the public key lives in.
 /// Input: public key (pubkey) -> public key of the message recipient.
-/// Input: ascii encoded message (ascii_message) -> message to send to the owner of the respective
+/// Input: message (message) -> a number from 1 to q to send to the owner of the respective
 /// secret key.
+/// Input: random number generator (rng) -> source of the throwaway key, which is drawn uniformly
+/// from 1 to q - 1. Anybody who can guess it can read the message, so it has to be as big as a
+/// private key.
 ///
-/// Output: tuple containing two ciphertexts, as appropriate with elgamal encryption.
-#[allow(non_snake_case)]
-pub fn encrypt_elgamal(pubkey: U512, ascii_message: U512) -> (U512, U512) {
-    let GENERATOR: U512 = U512::from_little_endian(&GENERATOR_ARRAY);
-    let BASEPOINT_ORDER: U512 = U512::from_little_endian(&BASEPOINT_ORDER_ARRAY);
-    let throwaway_key: U512  = U512::from(rand::thread_rng().next_u64());
-
-    let c1 = math::mod_exp(GENERATOR, throwaway_key, BASEPOINT_ORDER);
-    let c2 = (ascii_message * math::mod_exp(pubkey, throwaway_key, BASEPOINT_ORDER)) % BASEPOINT_ORDER;
-
-    (c1, c2)
+/// Output: Some tuple containing two ciphertexts, as appropriate with elgamal encryption, or None
+/// if the public key isn't in the group or the message is out of range.
+pub fn encrypt_elgamal<R: CryptoRng + RngCore>(group: &DhGroup, pubkey: &BigUint, message: &BigUint, rng: &mut R) -> Option<(BigUint, BigUint)> {
+    if !group.contains(pubkey) || *message == BigUint::ZERO || message > group.q() { return None; }
+    let throwaway_key = math::random_range(rng, &BigUint::from(1u64), &(group.q() - 1u64));
 
+    let p = group.p();
+    let c1 = math::mod_exp_ct(group.g(), &throwaway_key, p);
+    let c2 = encode_message(group, message) * math::mod_exp_ct(pubkey, &throwaway_key, p) % p;
+    Some((c1, c2))
 }
 
 /// decrypt_elgamal takes in the two ciphertexts and, using the user's appropriate private key,
-/// decrypt the ascii message.
+/// decrypt the message. c1 has order q, so c1^(q - privkey) is the inverse of the shared secret and
+/// no modular inversion is needed.
 ///
-/// Input: ciphertext1 (c1) -> first value of tuple returned from "elgamal_encrypt."
-/// Input: ciptertext2 (c2) -> second value of tuple returned from "elgamal_encrypt."
+/// Input: group (group) -> the group the ciphertexts live in.
+/// Input: ciphertext1 (c1) -> first value of tuple returned from "encrypt_elgamal."
+/// Input: ciptertext2 (c2) -> second value of tuple returned from "encrypt_elgamal."
 /// Input: private key (privkey) -> user's appropriate private key. If the wrong private key is
-/// used, the message will (obviously) not be encrypted.
+/// used, the message will (obviously) not be decrypted.
 ///
-/// Output: message encoded in ascii
-#[allow(non_snake_case)]
-pub fn decrypt_elgamal(c1: U512, c2: U512, privkey: U512) -> U512 {
-    let BASEPOINT_ORDER: U512 = U512::from_little_endian(&BASEPOINT_ORDER_ARRAY);
-
-    let y = BASEPOINT_ORDER - U512::one() - privkey;
-    let x = math::mod_exp(c1, y, BASEPOINT_ORDER);
-    let res = (x * c2) % BASEPOINT_ORDER;
-    res
-
+/// Output: Some(message), or None if either ciphertext isn't an element of the group.
+pub fn decrypt_elgamal(group: &DhGroup, c1: &BigUint, c2: &BigUint, privkey: &BigUint) -> Option<BigUint> {
+    if !group.contains(c1) || !group.contains(c2) { return None; }
+    let (p, q) = (group.p(), group.q());
+    let y = q - privkey % q;
+    let x = math::mod_exp_ct(c1, &y, p) * c2 % p;
+    Some(decode_message(group, &x))
 }
 
-#[allow(non_snake_case)]
-pub fn elgamal_test(s: U512, m: U512) -> bool {
+pub fn elgamal_test<R: CryptoRng + RngCore>(group: &DhGroup, s: &BigUint, m: &BigUint, rng: &mut R) -> bool {
 
     // Derive pub key
-    
-    let GENERATOR: U512 = U512::from_little_endian(&GENERATOR_ARRAY);
-    let BASEPOINT_ORDER: U512 = U512::from_little_endian(&BASEPOINT_ORDER_ARRAY);
-    let pub_key = math::mod_exp(GENERATOR, s, BASEPOINT_ORDER);
 
-    // This could have more entropy to be safer
-    
-    let throwaway_key: U512  = U512::from(rand::thread_rng().next_u64());
+    let pub_key = derive_public_key(group, s);
 
     // Encrypt
 
-    let c1 = math::mod_exp(GENERATOR, throwaway_key, BASEPOINT_ORDER);
-    let c2 = (m * math::mod_exp(pub_key, throwaway_key, BASEPOINT_ORDER)) % BASEPOINT_ORDER;
+    let (c1, c2) = encrypt_elgamal(group, &pub_key, m, rng).expect("public key and message are in range");
 
-    // Decrypt    
+    // Decrypt
 
-    let y = BASEPOINT_ORDER - U512::one() - s;
-    let x = math::mod_exp(c1, y, BASEPOINT_ORDER);
-    let res = (x * c2) % BASEPOINT_ORDER;
+    let res = decrypt_elgamal(group, &c1, &c2, s).expect("ciphertexts are in the group");
 
     // Verify m == m
 
-    assert_eq!(res, m);
+    assert_eq!(res, *m);
     true
 }
 
+/// m if it's a quadratic residue mod p, p - m otherwise. Euler's criterion (m^q = 1 exactly for the
+/// residues) is computed in constant time and the pick is made with a mask.
+fn encode_message(group: &DhGroup, m: &BigUint) -> BigUint {
+    let p = group.p();
+    let n = p.to_u64_digits().len();
+    let euler = math::to_limbs(&math::mod_exp_ct(m, group.q(), p), n);
+    select(&(p - m), m, euler.ct_eq(&math::to_limbs(&BigUint::from(1u64), n)), n)
+}
+
+/// Undoes encode_message: messages are at most q, so anything above q must be p - m.
+fn decode_message(group: &DhGroup, x: &BigUint) -> BigUint {
+    let p = group.p();
+    let n = p.to_u64_digits().len();
+    // q - x borrows past the top limb exactly when x > q.
+    let (q, x_limbs) = (math::to_limbs(group.q(), n), math::to_limbs(x, n));
+    let mut borrow = 0u64;
+    for (qi, xi) in q.iter().zip(&x_limbs) {
+        let (d, b1) = qi.overflowing_sub(*xi);
+        borrow = (b1 | d.overflowing_sub(borrow).1) as u64;
+    }
+    select(x, &(p - x), Choice::from(borrow as u8), n)
+}
+
+/// b if choice is set and a otherwise, limb by limb with a mask.
+fn select(a: &BigUint, b: &BigUint, choice: Choice, n: usize) -> BigUint {
+    let (mut out, b) = (math::to_limbs(a, n), math::to_limbs(b, n));
+    for (o, bi) in out.iter_mut().zip(&b) {
+        o.conditional_assign(bi, choice);
+    }
+    math::from_limbs(&out)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::math::mod_exp;
-    use crate::euclidian::gcd;
+    use crate::math::mod_exp_ct;
     use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
     #[test]
-    fn mod_exponentiation() {
-        assert_eq!(mod_exp(U512::from(2), U512::from(127), U512::from(71)), U512::from(50));
-        assert_eq!(mod_exp(U512::from(2), U512::from(511), U512::from(47298)), U512::from(1778));
-        assert_eq!(mod_exp(U512::from(2), U512::from(127), U512::from(3473)), U512::from(1789));
-        assert_eq!(mod_exp(U512::from(46), U512::from(3), U512::from(71)), U512::from(66));
-        assert_eq!(mod_exp(U512::from(5762), U512::from(7), U512::from(33)), U512::from(26));
-        assert_eq!(mod_exp(U512::from(4), U512::from(56), U512::from(941)), U512::from(469));
-        assert_eq!(mod_exp(U512::from(3), U512::from(100), U512::from(77)), U512::from(67));
-        assert_eq!(mod_exp(U512::from(8), U512::from(55), U512::from(8193)), U512::from(512));
-        assert_eq!(mod_exp(U512::from(92), U512::from(27), U512::from(19083)), U512::from(7769));
+    fn constant_time_exponentiation() {
+        let cases = [(2u64, 127u64, 71u64, 50u64), (2, 127, 3473, 1789), (46, 3, 71, 66), (5762, 7, 33, 26), (4, 56, 941, 469), (3, 100, 77, 67), (8, 55, 8193, 512), (92, 27, 19083, 7769)];
+        for (g, a, f, expected) in cases {
+            assert_eq!(mod_exp_ct(&BigUint::from(g), &BigUint::from(a), &BigUint::from(f)), BigUint::from(expected));
+        }
+        assert_eq!(mod_exp_ct(&BigUint::from(92u64), &BigUint::from(0u64), &BigUint::from(19083u64)), BigUint::from(1u64));
+
+        // 3^e (mod 2^512 - 569) with a 512-bit exponent, checked against python's pow.
+        let m: BigUint = "13407807929942597099574024998205846127479365820592393377723561443721764030073546976801874298166903427690031858186486050853753882811946569946433649006083527".parse().unwrap();
+        let e: BigUint = "9144161185990377371972742767573012328303244350985954542017076076342954857633666300252959704019074743035461536631850120794406783451351917500864708259601100".parse().unwrap();
+        let expected: BigUint = "12174092818644331363135362522523148667958352920489959573708815954102318284711112003071967354418358465727265243916204560980020421010442968067771347591240959".parse().unwrap();
+        assert_eq!(mod_exp_ct(&BigUint::from(3u64), &e, &m), expected);
+        // Fermat's little theorem on a prime that's right at the edge.
+        assert_eq!(mod_exp_ct(&BigUint::from(12345u64), &(&m - 1u64), &m), BigUint::from(1u64));
+
+        // On a group the rest of the crate works in.
+        let group = DhGroup::modp2048();
+        assert_eq!(mod_exp_ct(group.g(), &e, group.p()), group.g().modpow(&e, group.p()));
+        assert_eq!(mod_exp_ct(group.g(), group.q(), group.p()), BigUint::from(1u64));
     }
     #[test]
-    fn gcds() {
-        assert_eq!(gcd(U512::from(2024), U512::from(748)), U512::from(44));
-        assert_eq!(gcd(U512::from(7834), U512::from(48)), U512::from(2));
-        assert_eq!(gcd(U512::from(4235), U512::from(15241245)), U512::from(5));
-        assert_eq!(gcd(U512::from(32764), U512::from(747)), U512::from(1));
-        assert_eq!(gcd(U512::from(72941), U512::from(582717)), U512::from(1));
-        assert_eq!(gcd(U512::from(40183), U512::from(45791821)), U512::from(1));
-        assert_eq!(gcd(U512::from_dec_str("127098475608364526582164396391421").unwrap(), U512::from_dec_str("457912374852730972352435230953821").unwrap()), U512::from(1)); // very large
-        assert_eq!(gcd(U512::from_dec_str("127098475602348752983645965238765498616528749758364526582164396391421").unwrap(), U512::from_dec_str("45791237485218247592876548236498276439730972352435230953821").unwrap()), U512::from(3)); // very large
+    fn dh_groups() {
+        let groups = [
+            (DhGroup::modp1536(), 1536), (DhGroup::modp2048(), 2048), (DhGroup::modp3072(), 3072),
+            (DhGroup::modp4096(), 4096), (DhGroup::modp6144(), 6144), (DhGroup::modp8192(), 8192),
+            (DhGroup::ffdhe2048(), 2048), (DhGroup::ffdhe3072(), 3072), (DhGroup::ffdhe4096(), 4096),
+        ];
+        let one = BigUint::from(1u64);
+        let all_ones = BigUint::from(u64::MAX);
+        for (group, bits) in &groups {
+            let (p, q, g) = (group.p(), group.q(), group.g());
+            assert_eq!(group.bits(), *bits);
+            assert_eq!(*p, q * 2u64 + 1u64);
+            // Both RFCs fix the top and bottom 64 bits to 1.
+            assert_eq!(p >> (bits - 64), all_ones);
+            assert_eq!(p & &all_ones, all_ones);
+            // g = 2 has order q, not 2q, and q passes a Fermat test.
+            assert_eq!(*g, BigUint::from(2u64));
+            assert_eq!(g.modpow(q, p), one);
+            assert_eq!(BigUint::from(3u64).modpow(&(q - 1u64), q), one);
+        }
+        assert_ne!(DhGroup::modp2048(), DhGroup::ffdhe2048());
+
+        let group = DhGroup::ffdhe2048();
+        let mut rng = rand::thread_rng();
+        let (p, q) = (group.p(), group.q());
+        let s = BigUint::parse_bytes(b"1f3b6a0c9d2e8457b1c0aa91e4d27f3685c1b0e9f4a2d7c3b8e16f05a9d4c27e", 16).unwrap();
+        let pub_key = derive_public_key(&group, &s);
+        assert!(group.contains(&pub_key));
+        assert_eq!(pub_key, group.g().modpow(&s, p));
+
+        // Messages at both ends of the range. The small ones are residues mod this p, q and q - 1
+        // aren't, so both branches of the encoding get used.
+        for m in [one.clone(), BigUint::from(2u64), BigUint::from(3u64), q.clone(), q - 1u64] {
+            assert!(elgamal_test(&group, &s, &m, &mut rng));
+            let (c1, c2) = encrypt_elgamal(&group, &pub_key, &m, &mut rng).unwrap();
+            assert!(group.contains(&c1) && group.contains(&c2));
+            assert_ne!(decrypt_elgamal(&group, &c1, &c2, &(&s + 1u64)), Some(m));
+        }
+        assert_eq!(encrypt_elgamal(&group, &pub_key, &BigUint::from(0u64), &mut rng), None);
+        assert_eq!(encrypt_elgamal(&group, &pub_key, &(q + 1u64), &mut rng), None);
+
+        // Public keys outside the subgroup: 1, -1 (order 2), p itself and -2, a non-residue.
+        for bad in [one.clone(), p - 1u64, p.clone(), p - 2u64] {
+            assert!(!group.contains(&bad));
+            assert_eq!(encrypt_elgamal(&group, &bad, &one, &mut rng), None);
+        }
+        let (c1, c2) = encrypt_elgamal(&group, &pub_key, &one, &mut rng).unwrap();
+        assert_eq!(decrypt_elgamal(&group, &(p - &c1), &c2, &s), None);
+        assert_eq!(decrypt_elgamal(&group, &c1, &(p - &c2), &s), None);
+        assert_eq!(decrypt_elgamal(&group, &c1, &c2, &s), Some(one));
+    }
+    /// Hands out the given bytes first and zeros after that.
+    struct ScriptedRng(Vec<u8>);
+    impl RngCore for ScriptedRng {
+        fn next_u32(&mut self) -> u32 { let mut b = [0u8; 4]; self.fill_bytes(&mut b); u32::from_le_bytes(b) }
+        fn next_u64(&mut self) -> u64 { let mut b = [0u8; 8]; self.fill_bytes(&mut b); u64::from_le_bytes(b) }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for d in dest.iter_mut() { *d = if self.0.is_empty() { 0 } else { self.0.remove(0) }; }
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> { self.fill_bytes(dest); Ok(()) }
+    }
+    impl rand::CryptoRng for ScriptedRng {}
+    #[test]
+    fn ephemeral_keys() {
+        let group = DhGroup::modp2048();
+        let s = BigUint::parse_bytes(b"1f3b6a0c9d2e8457b1c0aa91e4d27f3685c1b0e9f4a2d7c3b8e16f05a9d4c27e", 16).unwrap();
+        let pub_key = derive_public_key(&group, &s);
+        let m = BigUint::from_bytes_be(b"attack at dawn");
+
+        // Known answers with ChaCha20 seeded with 32 bytes of 0x2a, checked against an independent
+        // implementation. Only the low 256 bits of each ciphertext are compared to keep this short.
+        let low = |x: &BigUint| format!("{:064x}", x & ((BigUint::from(1u64) << 256) - 1u64));
+        let mut rng = ChaCha20Rng::from_seed([0x2a; 32]);
+        let (c1, c2) = encrypt_elgamal(&group, &pub_key, &m, &mut rng).unwrap();
+        assert_eq!(low(&c1), "a5b051fdf72fc74d72e516cbdd0bac22f5d66aafd2d01163a16807e2b1056dc4");
+        assert_eq!(low(&c2), "91adda23a0adf45e07b672c09edd43eca46487de621e5481ccd789e535320e77");
+        assert_eq!(decrypt_elgamal(&group, &c1, &c2, &s), Some(m.clone()));
+        // The next message is q, which isn't a residue and gets encrypted as p - q.
+        let (c1, c2) = encrypt_elgamal(&group, &pub_key, group.q(), &mut rng).unwrap();
+        assert_eq!(low(&c1), "c476695df3d78b1c77b6f24b98ce4a3e584bf3a7cef748cb136fcdc6e69561d7");
+        assert_eq!(low(&c2), "379f4e618b47a6214340cb59fc45f3a19e35655397cd789114d259a2a5bd0d33");
+        assert_eq!(decrypt_elgamal(&group, &c1, &c2, &s).as_ref(), Some(group.q()));
+
+        // The same seed gives the same ciphertext, and a fresh seed a different one.
+        let again = encrypt_elgamal(&group, &pub_key, &m, &mut ChaCha20Rng::from_seed([0x2a; 32]));
+        assert_eq!(again.as_ref().map(|c| low(&c.0)).as_deref(), Some("a5b051fdf72fc74d72e516cbdd0bac22f5d66aafd2d01163a16807e2b1056dc4"));
+        assert_ne!(encrypt_elgamal(&group, &pub_key, &m, &mut ChaCha20Rng::from_seed([0x2b; 32])), again);
+
+        // A draw of all ones (2^2047 - 1) is bigger than q - 2 and has to be thrown out, not reduced.
+        // The zeros after it give x = 0, the throwaway key 1 and so c1 = g.
+        let mut rng = ScriptedRng(vec![0xff; 256]);
+        let (c1, c2) = encrypt_elgamal(&group, &pub_key, &m, &mut rng).unwrap();
+        assert_eq!(c1, *group.g());
+        assert_eq!(c2, &m * &pub_key % group.p());
+        assert!(rng.0.is_empty());
+
+        // Over many draws the throwaway keys are as big as q, not 64 bits.
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let k = math::random_range(&mut rng, &BigUint::from(1u64), &(group.q() - 1u64));
+            assert!(k >= BigUint::from(1u64) && k < *group.q());
+            assert!(k.bits() > 2000);
+        }
     }
     #[test]
     fn encrypt() {
-        assert!(elgamal_test(U512::from(312847592u64), U512::from(1342u64)));
-        assert!(elgamal_test(U512::from(124512454u64), U512::from(5123542u64)));
-        assert!(elgamal_test(U512::from(624523u64), U512::from(4123451u64)));
-        assert!(elgamal_test(U512::from(72345u64), U512::from(312341234u64)));
-        assert!(elgamal_test(U512::from(2232454u64), U512::from(123412356u64)));
-        assert!(elgamal_test(U512::from(2234u64), U512::from(152312453u64)));
-        assert!(elgamal_test(U512::from(22345234u64), U512::from(1235421454u64)));
-        assert!(elgamal_test(U512::from(2343345234u64), U512::from(123345421454u64)));
+        let group = DhGroup::modp2048();
+        let mut rng = rand::thread_rng();
+        assert!(elgamal_test(&group, &BigUint::from(312847592u64), &BigUint::from(1342u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(124512454u64), &BigUint::from(5123542u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(624523u64), &BigUint::from(4123451u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(72345u64), &BigUint::from(312341234u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(2232454u64), &BigUint::from(123412356u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(2234u64), &BigUint::from(152312453u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(22345234u64), &BigUint::from(1235421454u64), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::from(2343345234u64), &BigUint::from(123345421454u64), &mut rng));
 
         // very large 
-        assert!(elgamal_test(U512::from_dec_str("2234523431927162487312376421834").unwrap(), U512::from_dec_str("17963872153742134761124512235421454").unwrap()));
-        assert!(elgamal_test(U512::from_dec_str("22312124523452345243524345452341425").unwrap(), U512::from_dec_str("1252452411235421454213752452345235").unwrap()));
-        assert!(elgamal_test(U512::from_dec_str("221234524521531243241132341345234").unwrap(), U512::from_dec_str("1234213542134123411245231235125421454").unwrap()));
-        assert!(elgamal_test(U512::from_dec_str("12341252251245341245512212534").unwrap(), U512::from_dec_str("1237456115245235123524355642345214541251").unwrap()));
+        assert!(elgamal_test(&group, &BigUint::parse_bytes(b"2234523431927162487312376421834", 10).unwrap(), &BigUint::parse_bytes(b"17963872153742134761124512235421454", 10).unwrap(), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::parse_bytes(b"22312124523452345243524345452341425", 10).unwrap(), &BigUint::parse_bytes(b"1252452411235421454213752452345235", 10).unwrap(), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::parse_bytes(b"221234524521531243241132341345234", 10).unwrap(), &BigUint::parse_bytes(b"1234213542134123411245231235125421454", 10).unwrap(), &mut rng));
+        assert!(elgamal_test(&group, &BigUint::parse_bytes(b"12341252251245341245512212534", 10).unwrap(), &BigUint::parse_bytes(b"1237456115245235123524355642345214541251", 10).unwrap(), &mut rng));
     }
 }
@@ -1,7 +1,7 @@
 use math::mod_exp;
 use rand::RngCore;
 use bigint::U512;
-use euclidian::gcd;
+use euclidian::mod_inverse;
 
 mod math;
 mod euclidian;
@@ -32,12 +32,12 @@ pub const GENERATOR_ARRAY: [u8; 32] =
 /// Input: private key (privkey) -> the private key of the user.
 ///
 /// Output: user's corresponding public key.
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::empty_line_after_doc_comments)]
 pub fn derive_public_key(privkey: U512) -> U512 {
     let GENERATOR: U512 = U512::from_little_endian(&GENERATOR_ARRAY);
     let BASEPOINT_ORDER: U512 = U512::from_little_endian(&PRIME_ORDER);
 
-    math::mod_exp(GENERATOR, privkey, BASEPOINT_ORDER)
+    math::mod_exp_ct(GENERATOR, privkey, BASEPOINT_ORDER)
 }
 
 /// Sign will, well, provide a digital signature for the hash of a digital file. We choose to sign
@@ -48,25 +48,23 @@ pub fn derive_public_key(privkey: U512) -> U512 {
 /// Input: hash (document_hash) -> hash of the document (any hash should do as long as it is less than 512 bits. 
 ///
 /// Output: Tuple of two signatures. (S1, S2)
-#[allow(non_snake_case, unused_assignments)]
+#[allow(non_snake_case)]
 pub fn sign(privkey: U512, document_hash: U512) -> (U512, U512) {
     let GENERATOR: U512 = U512::from_little_endian(&GENERATOR_ARRAY);
     let BASEPOINT_ORDER: U512 = U512::from_little_endian(&PRIME_ORDER);
 
-    let mut k: U512 = U512::from(rand::thread_rng().next_u64());
-    while gcd(k, BASEPOINT_ORDER - U512::one()) != U512::one() {
-        k = U512::from(rand::thread_rng().next_u64());
-    }
+    // k has to be invertible mod p - 1, so keep drawing until it is.
+    let (k, inv_k) = loop {
+        let k = U512::from(rand::thread_rng().next_u64());
+        if let Some(inv_k) = mod_inverse(k, BASEPOINT_ORDER - U512::one()) { break (k, inv_k); }
+    };
 
-    let s1 = math::mod_exp(GENERATOR, k, BASEPOINT_ORDER);
-    let inv_k = euclidian::modinv(k, BASEPOINT_ORDER - U512::one());
-    let mut s2 = U512::zero();
-    if (privkey * s1) % (BASEPOINT_ORDER - U512::one()) > document_hash {
-        s2 = (((BASEPOINT_ORDER - U512::one()) - (((privkey * s1) % (BASEPOINT_ORDER - U512::one())) - document_hash)) * inv_k) % (BASEPOINT_ORDER - U512::one());
-    }
-    else {
-        s2 = ((document_hash - (privkey * s1)) * inv_k) % (BASEPOINT_ORDER - U512::one());
-    }
+    let s1 = math::mod_exp_ct(GENERATOR, k, BASEPOINT_ORDER);
+
+    // s2 = (H(m) - x * s1) * k^-1 (mod p - 1)
+    let order = BASEPOINT_ORDER - U512::one();
+    let xr = math::mod_mul(privkey, s1, order);
+    let s2 = math::mod_mul(math::mod_sub(document_hash % order, xr, order), inv_k, order);
     (s1, s2)
 }
 
@@ -87,7 +85,7 @@ pub fn verify(s1: U512, s2: U512, pubkey: U512, document_hash: U512) -> bool {
 
     let p1 = mod_exp(pubkey, s1, BASEPOINT_ORDER);
     let p2 = mod_exp(s1, s2, BASEPOINT_ORDER);
-    let verif = (p1 * p2) % BASEPOINT_ORDER;
+    let verif = math::mod_mul(p1, p2, BASEPOINT_ORDER);
     
     check == verif
 }
@@ -126,8 +124,8 @@ pub fn elgamal_test_fail(s: U512, d: U512) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::math::mod_exp;
-    use crate::euclidian::gcd;
+    use crate::math::{mod_exp, mod_exp_ct, mod_mul, wide_mul};
+    use crate::euclidian::{binary_gcd, gcd, mod_inverse, xgcd};
     use super::*;
     #[test]
     fn mod_exponentiation() {
@@ -142,6 +140,68 @@ mod tests {
         assert_eq!(mod_exp(U512::from(92), U512::from(27), U512::from(19083)), U512::from(7769));
     }
     #[test]
+    fn mod_multiplication() {
+        // Right at the top of the U512 range: 2^512 - 569 is prime, and (-1) * (-2) = 2.
+        let m = U512::MAX - U512::from(568u64);
+        assert_eq!(mod_mul(m - U512::one(), m - U512::from(2u64), m), U512::from(2u64));
+        assert_eq!(mod_mul(U512::MAX, U512::MAX, m), U512::from(568u64 * 568u64));
+        assert_eq!(mod_mul(m - U512::one(), m - U512::one(), m), U512::one());
+        let a = U512::from_dec_str("2878632006852039861398374444252833604607928908011031527239529357080103665536524539155621949196490440913140525952976749477520271611552307027187511041897528").unwrap();
+        let b = U512::from_dec_str("8455311166716118866295620264038631771900680627001938551326291936174047472550769313668870816295314510562862578581919818722046768088907134297290291069669592").unwrap();
+        let ab = U512::from_dec_str("12804185821318715464701081150433104765880773075222121739380454615955414654550618130102440377314949585115162379797760237669656131884093813620844408346172310").unwrap();
+        assert_eq!(mod_mul(a, b, m), ab);
+        assert_eq!(mod_mul(b, a, m), ab);
+
+        // 2^256 + 297 is the first prime above 2^256, which is where squaring a residue can first
+        // spill past 512 bits: (m - 1)^2 is a little over 2^512.
+        let m = (U512::one() << 256) + U512::from(297u64);
+        let x = U512::from_dec_str("63638094743243607701259098635895508316175423375552970043721904176140436600501").unwrap();
+        let xx = U512::from_dec_str("35700457150511478900217443468585340736720137888645276177418122631482246052026").unwrap();
+        assert_eq!(mod_mul(x, x, m), xx);
+        assert_eq!(mod_mul(m - U512::one(), m - U512::one(), m), U512::one());
+
+        // Small values still take the fast path and agree with plain arithmetic.
+        assert_eq!(mod_mul(U512::from(5762u64), U512::from(5762u64), U512::from(33u64)), U512::from(5762u64 * 5762 % 33));
+        assert_eq!(mod_mul(U512::zero(), U512::MAX, m), U512::zero());
+
+        // The full product splits into the right halves.
+        assert_eq!(wide_mul(U512::MAX, U512::MAX), (U512::MAX - U512::one(), U512::one()));
+        assert_eq!(wide_mul(U512::one() << 300, U512::one() << 300), (U512::one() << 88, U512::zero()));
+    }
+    #[test]
+    fn large_mod_exponentiation() {
+        // 3^e (mod 2^512 - 569) with a 512-bit exponent, checked against python's pow.
+        let m = U512::MAX - U512::from(568u64);
+        let e = U512::from_dec_str("9144161185990377371972742767573012328303244350985954542017076076342954857633666300252959704019074743035461536631850120794406783451351917500864708259601100").unwrap();
+        let expected = U512::from_dec_str("12174092818644331363135362522523148667958352920489959573708815954102318284711112003071967354418358465727265243916204560980020421010442968067771347591240959").unwrap();
+        assert_eq!(mod_exp(U512::from(3u64), e, m), expected);
+        // Fermat's little theorem on a prime that's right at the edge.
+        assert_eq!(mod_exp(U512::from(12345u64), m - U512::one(), m), U512::one());
+        let m = (U512::one() << 256) + U512::from(297u64);
+        assert_eq!(mod_exp(U512::from(7u64), m - U512::one(), m), U512::one());
+    }
+    #[test]
+    fn constant_time_exponentiation() {
+        let cases = [(2u64, 127u64, 71u64), (2, 127, 3473), (46, 3, 71), (5762, 7, 33), (4, 56, 941), (3, 100, 77), (8, 55, 8193), (92, 27, 19083)];
+        for (g, a, f) in cases {
+            assert_eq!(mod_exp_ct(U512::from(g), U512::from(a), U512::from(f)), mod_exp(U512::from(g), U512::from(a), U512::from(f)));
+        }
+        assert_eq!(mod_exp_ct(U512::from(92u64), U512::zero(), U512::from(19083u64)), U512::one());
+
+        // All eight limbs in use.
+        let m = U512::MAX - U512::from(568u64);
+        let e = U512::from_dec_str("9144161185990377371972742767573012328303244350985954542017076076342954857633666300252959704019074743035461536631850120794406783451351917500864708259601100").unwrap();
+        let expected = U512::from_dec_str("12174092818644331363135362522523148667958352920489959573708815954102318284711112003071967354418358465727265243916204560980020421010442968067771347591240959").unwrap();
+        assert_eq!(mod_exp_ct(U512::from(3u64), e, m), expected);
+        assert_eq!(mod_exp_ct(U512::MAX, U512::from(2u64), m), U512::from(568u64 * 568u64));
+
+        // The group the rest of the crate works in.
+        let order = U512::from_little_endian(&PRIME_ORDER);
+        let g = U512::from_little_endian(&GENERATOR_ARRAY);
+        assert_eq!(mod_exp_ct(g, order - U512::one(), order), U512::one());
+        assert_eq!(mod_exp_ct(g, e, order), mod_exp(g, e, order));
+    }
+    #[test]
     fn gcds() {
         assert_eq!(gcd(U512::from(2024), U512::from(748)), U512::from(44));
         assert_eq!(gcd(U512::from(7834), U512::from(48)), U512::from(2));
@@ -153,6 +213,43 @@ mod tests {
         assert_eq!(gcd(U512::from_dec_str("127098475602348752983645965238765498616528749758364526582164396391421").unwrap(), U512::from_dec_str("45791237485218247592876548236498276439730972352435230953821").unwrap()), U512::from(3)); // very large
     }
     #[test]
+    fn extended_gcds() {
+        // Zero on either side used to divide by zero.
+        assert_eq!(gcd(U512::from(2024), U512::zero()), U512::from(2024));
+        assert_eq!(gcd(U512::zero(), U512::from(748)), U512::from(748));
+        assert_eq!(binary_gcd(U512::zero(), U512::from(748)), U512::from(748));
+
+        let a = U512::from_dec_str("836924423851314686957903970262947033592661064025187511582267500056692617427").unwrap();
+        let b = U512::from_dec_str("60772039568148124987496788008570400867100447637981291630550967949578841").unwrap();
+        let pairs = [(U512::from(2024), U512::from(748)), (U512::from(748), U512::from(2024)), (U512::from(7834), U512::from(48)), (U512::from(40183), U512::from(45791821)), (U512::from(12), U512::zero()), (U512::zero(), U512::from(12)), (a, b)];
+        for (a, b) in pairs {
+            let (g, x, y) = xgcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(g, binary_gcd(a, b));
+            // a * x + b * y = g, with the negative term moved to the other side.
+            let (ax, by) = (a * x.magnitude, b * y.magnitude);
+            match (x.negative, y.negative) {
+                (true, false) => assert_eq!(by - ax, g),
+                (false, true) => assert_eq!(ax - by, g),
+                _ => assert_eq!(ax + by, g),
+            }
+        }
+        assert_eq!(xgcd(a, b).0, U512::from(3));
+
+        let order = U512::from_little_endian(&PRIME_ORDER);
+        let x = U512::from_dec_str("21582102299826525449504797940759260576379968499998982132066757867315193878103").unwrap();
+        let inv = U512::from_dec_str("2821670252408495415641284638864936532766101186781183807149613816555617576584").unwrap();
+        assert_eq!(mod_inverse(x, order), Some(inv));
+        assert_eq!(mod_inverse(x + order, order), Some(inv));
+        assert_eq!(mod_inverse(U512::from(3), U512::from(7)), Some(U512::from(5)));
+        assert_eq!(mod_inverse(U512::from(5), U512::one()), Some(U512::zero()));
+        // No inverse exists for these, which the old modinv would happily "compute" anyway.
+        assert_eq!(mod_inverse(U512::zero(), order), None);
+        assert_eq!(mod_inverse(order, order), None);
+        assert_eq!(mod_inverse(U512::from(6), U512::from(15)), None);
+        assert_eq!(mod_inverse(U512::from(6), U512::zero()), None);
+    }
+    #[test]
     fn digital_signature() {
         assert!(elgamal_test(U512::from(312847592u64), U512::from(1342u64)));
         assert!(elgamal_test(U512::from(124512454u64), U512::from(5123542u64)));
@@ -1,5 +1,12 @@
 use bigint::uint::U512;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
+/// Bits of the exponent handled per table lookup in mod_exp_ct.
+const WINDOW: usize = 4;
+
+/// Square-and-multiply modular exponentiation. Every product goes through mod_mul, so this works
+/// for any modulus that fits in a U512 and not just the ones below 2^256, where x * x still fits.
+/// This one is NOT constant time, so only use it when the exponent is public.
 #[allow(dead_code)]
 pub fn mod_exp(g: U512, a: U512, f: U512) -> U512 {
     if g == U512::zero() { return U512::zero(); }
@@ -10,15 +17,189 @@ pub fn mod_exp(g: U512, a: U512, f: U512) -> U512 {
     let mut x = g;
     while exp > U512::one() {
         if is_even(&exp) {
-            x = (x * x) % f;
+            x = mod_mul(x, x, f);
             exp = exp >> 1;
         }
         else {
-            ret = (ret * x) % f;
-            x = (x * x) % f;
+            ret = mod_mul(ret, x, f);
+            x = mod_mul(x, x, f);
             exp = exp >> 1;
         }
     }
-    (x * ret) % f
+    mod_mul(x, ret, f)
+}
+
+/// g^a (mod f) for secret exponents (private keys and the throwaway keys). mod_exp above only
+/// multiplies when it hits a 1 bit and mod_mul branches on the bits of the product, so the running
+/// time of both spells out the exponent. Here the exponent is eaten four bits at a time from the
+/// top: every window costs exactly four squarings and one multiplication by table[window], and the
+/// table entry is fetched by touching every entry and keeping the right one with a mask. The only
+/// thing left to leak is the bit length of a.
+///
+/// Input: base (g) -> any U512. It's reduced mod f first.
+/// Input: exponent (a) -> the secret exponent.
+/// Input: modulus (f) -> must be odd (Montgomery multiplication needs it).
+///
+/// Output: g^a (mod f).
+#[allow(dead_code)]
+pub fn mod_exp_ct(g: U512, a: U512, f: U512) -> U512 {
+    assert!(f.bit(0), "Montgomery multiplication needs an odd modulus");
+    let n = f.bits().div_ceil(64);
+    let m = &f.0[..n];
+    let m_inv = mont_inverse(m[0]);
+
+    // R = 2^(64n). Multiplying by R^2 and reducing once moves a number into Montgomery form.
+    let r = if n == 8 { mod_add(U512::MAX % f, U512::one(), f) } else { (U512::one() << (64 * n)) % f };
+    let r2 = mod_mul(r, r, f);
+    let one = &U512::one().0[..n];
+    let base = mont_mul(&(g % f).0[..n], &r2.0[..n], m, m_inv);
+
+    // table[i] = g^i * R (mod f)
+    let mut table = vec![r.0[..n].to_vec(), base.clone()];
+    for i in 2..1 << WINDOW {
+        table.push(mont_mul(&table[i - 1], &base, m, m_inv));
+    }
+
+    let mut acc = table[0].clone();
+    let windows = a.bits().div_ceil(WINDOW);
+    for w in (0..windows).rev() {
+        for _ in 0..WINDOW {
+            acc = mont_mul(&acc, &acc, m, m_inv);
+        }
+        let digit = (0..WINDOW).fold(0u64, |d, j| d | (a.bit(w * WINDOW + j) as u64) << j);
+        acc = mont_mul(&acc, &ct_lookup(&table, digit), m, m_inv);
+    }
+
+    let mut out = [0u64; 8];
+    out[..n].copy_from_slice(&mont_mul(&acc, one, m, m_inv));
+    U512(out)
+}
+
+/// a * b / R (mod m) for a, b < m, using the CIOS method (Koc, Acar and Kaliski, 1996). The loops
+/// only depend on the number of limbs and the final subtraction is done with a mask, so the
+/// running time doesn't depend on the values.
+fn mont_mul(a: &[u64], b: &[u64], m: &[u64], m_inv: u64) -> Vec<u64> {
+    let n = m.len();
+    let mut t = vec![0u64; n + 2];
+    for &ai in a {
+        // t += ai * b
+        let mut carry = 0u128;
+        for j in 0..n {
+            let s = t[j] as u128 + ai as u128 * b[j] as u128 + carry;
+            t[j] = s as u64;
+            carry = s >> 64;
+        }
+        let s = t[n] as u128 + carry;
+        t[n] = s as u64;
+        t[n + 1] = (s >> 64) as u64;
+
+        // t = (t + u * m) / 2^64, where u is picked so that the low limb cancels out.
+        let u = t[0].wrapping_mul(m_inv);
+        let mut carry = (t[0] as u128 + u as u128 * m[0] as u128) >> 64;
+        for j in 1..n {
+            let s = t[j] as u128 + u as u128 * m[j] as u128 + carry;
+            t[j - 1] = s as u64;
+            carry = s >> 64;
+        }
+        let s = t[n] as u128 + carry;
+        t[n - 1] = s as u64;
+        t[n] = t[n + 1] + (s >> 64) as u64;
+    }
+
+    // t < 2m here. Compute t - m and keep it unless that borrowed past the top limb.
+    let mut out = vec![0u64; n];
+    let mut borrow = 0u64;
+    for j in 0..n {
+        let (d, b1) = t[j].overflowing_sub(m[j]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        out[j] = d;
+        borrow = (b1 | b2) as u64;
+    }
+    let t_smaller = Choice::from(t[n].overflowing_sub(borrow).1 as u8);
+    for j in 0..n {
+        out[j].conditional_assign(&t[j], t_smaller);
+    }
+    out
+}
+
+/// -m^-1 (mod 2^64) by Newton's iteration. Every step doubles the number of correct low bits, and
+/// x = 1 is already right mod 2 because m is odd.
+fn mont_inverse(m: u64) -> u64 {
+    let mut x = 1u64;
+    for _ in 0..6 {
+        x = x.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(x)));
+    }
+    x.wrapping_neg()
+}
+
+/// table[index] without the memory access pattern depending on index.
+fn ct_lookup(table: &[Vec<u64>], index: u64) -> Vec<u64> {
+    let mut out = vec![0u64; table[0].len()];
+    for (i, entry) in table.iter().enumerate() {
+        let hit = (i as u64).ct_eq(&index);
+        for (o, e) in out.iter_mut().zip(entry) {
+            o.conditional_assign(e, hit);
+        }
+    }
+    out
+}
+
+/// a * b (mod m) for any m < 2^512. The product is computed at full width (1024 bits) and then
+/// reduced, so unlike (a * b) % m it can't wrap around no matter how big the operands are.
+///
+/// Input: first factor (a) -> any U512.
+/// Input: second factor (b) -> any U512.
+/// Input: modulus (m) -> nonzero modulus.
+///
+/// Output: product -> a * b reduced into [0, m).
+#[allow(dead_code)]
+pub fn mod_mul(a: U512, b: U512, m: U512) -> U512 {
+    let (hi, lo) = wide_mul(a % m, b % m);
+    // Both factors are below m, so if m is at most 256 bits the product always fits in the low
+    // half and a single division does the job.
+    if hi == U512::zero() { return lo % m; }
+
+    // Otherwise reduce hi * 2^512 + lo one bit at a time: start from hi (mod m) and shift the
+    // bits of lo in from the top, doubling and reducing as we go.
+    let mut r = hi % m;
+    for i in (0..512).rev() {
+        r = mod_add(r, r, m);
+        if lo.bit(i) { r = mod_add(r, U512::one(), m); }
+    }
+    r
 }
 
+/// a + b (mod m) for a, b < m. The sum can spill past 2^512 when m is close to it, in which case
+/// the wrapped result plus the lost 2^512 is still less than 2m, so one subtraction fixes it.
+#[allow(dead_code)]
+pub fn mod_add(a: U512, b: U512, m: U512) -> U512 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= m { sum.overflowing_sub(m).0 } else { sum }
+}
+
+/// a - b (mod m) for a, b < m.
+#[allow(dead_code)]
+pub fn mod_sub(a: U512, b: U512, m: U512) -> U512 {
+    if a >= b { a - b } else { m - (b - a) }
+}
+
+/// The full 1024-bit product of a and b as (high half, low half), by schoolbook multiplication on
+/// the 64-bit limbs.
+#[allow(dead_code)]
+pub fn wide_mul(a: U512, b: U512) -> (U512, U512) {
+    let (U512(x), U512(y)) = (a, b);
+    let mut limbs = [0u64; 16];
+    for i in 0..8 {
+        let mut carry = 0u128;
+        for j in 0..8 {
+            let t = x[i] as u128 * y[j] as u128 + limbs[i + j] as u128 + carry;
+            limbs[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        limbs[i + 8] = carry as u64;
+    }
+    let (mut hi, mut lo) = ([0u64; 8], [0u64; 8]);
+    lo.copy_from_slice(&limbs[..8]);
+    hi.copy_from_slice(&limbs[8..]);
+    (U512(hi), U512(lo))
+}
@@ -0,0 +1,152 @@
+use crate::math::random_range;
+use crate::prime::{generate_prime, jacobi, legendre};
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::One;
+use rand::{CryptoRng, RngCore};
+
+/// A Goldwasser-Micali public key: the modulus N = pq and a pseudo-square y, a number with Jacobi
+/// symbol (y/N) = 1 that is nevertheless not a square mod N. Telling y apart from a real square
+/// without p and q is the quadratic residuosity problem, the same problem that convince is about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GmPublicKey {
+    n: BigUint,
+    y: BigUint,
+}
+
+/// A Goldwasser-Micali private key. Knowing p is all it takes to decrypt: a ciphertext is a square
+/// mod N exactly when it is a square mod p.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GmPrivateKey {
+    public: GmPublicKey,
+    p: BigUint,
+    q: BigUint,
+}
+
+impl GmPublicKey {
+
+    /// The public modulus N = pq.
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// The pseudo-square y.
+    pub fn y(&self) -> &BigUint {
+        &self.y
+    }
+
+    /// Encrypts one bit: c = y^b r^2 (mod N) for a fresh random r. A 0 becomes a random square and
+    /// a 1 a random pseudo-square, and both have Jacobi symbol 1, so without p and q the two look
+    /// the same. Since r is new every time, encrypting the same bit twice gives two unrelated
+    /// ciphertexts; that's the semantic security part.
+    ///
+    /// Input: bit (bit) -> the bit to encrypt.
+    /// Input: random number generator (rng) -> source of r.
+    ///
+    /// Output: ciphertext (c) -> a number mod N.
+    pub fn encrypt_bit<R: CryptoRng + RngCore>(&self, bit: bool, rng: &mut R) -> BigUint {
+        let r = loop {
+            let r = random_range(rng, &BigUint::from(2u64), &(&self.n - 1u64));
+            if r.gcd(&self.n).is_one() { break r; }
+        };
+        let c = &r * &r % &self.n;
+        if bit { c * &self.y % &self.n } else { c }
+    }
+
+    /// Encrypts a byte string one bit at a time, most significant bit of every byte first. Every
+    /// single bit costs a number the size of N, so this is for teaching, not for bulk data.
+    ///
+    /// Input: message (msg) -> bytes to encrypt.
+    /// Input: random number generator (rng) -> source of randomness.
+    ///
+    /// Output: ciphertexts -> 8 per byte of the message.
+    pub fn encrypt<R: CryptoRng + RngCore>(&self, msg: &[u8], rng: &mut R) -> Vec<BigUint> {
+        msg.iter().flat_map(|&byte| (0..8).rev().map(move |i| byte >> i & 1 == 1)).map(|bit| self.encrypt_bit(bit, rng)).collect()
+    }
+
+    /// E(a) E(b) = E(a XOR b): y^a r^2 y^b s^2 = y^(a + b) (rs)^2, and y^2 is a square.
+    pub fn xor(&self, c1: &BigUint, c2: &BigUint) -> BigUint {
+        c1 * c2 % &self.n
+    }
+
+    /// Bitwise xor of two encrypted byte strings of the same length, or None if they differ in
+    /// length.
+    pub fn xor_bytes(&self, c1: &[BigUint], c2: &[BigUint]) -> Option<Vec<BigUint>> {
+        if c1.len() != c2.len() { return None; }
+        Some(c1.iter().zip(c2).map(|(a, b)| self.xor(a, b)).collect())
+    }
+}
+
+impl GmPrivateKey {
+
+    /// Generates two random primes of half the size each and picks a pseudo-square for them.
+    ///
+    /// Input: random number generator (rng) -> source of randomness.
+    /// Input: modulus size (bits) -> size of N in bits.
+    ///
+    /// Output: GmPrivateKey.
+    pub fn generate<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> Self {
+        loop {
+            let p = generate_prime(rng, bits / 2);
+            let q = generate_prime(rng, bits - bits / 2);
+            if p != q { return Self::from_primes(p, q, rng); }
+        }
+    }
+
+    /// Builds a key out of two distinct odd primes. The pseudo-square is found by drawing random
+    /// numbers until one is a non-square mod p and mod q at once, which takes four tries on
+    /// average.
+    ///
+    /// Input: secret prime #1 (p) -> first prime factor of N.
+    /// Input: secret prime #2 (q) -> second prime factor of N.
+    /// Input: random number generator (rng) -> source of candidates for y.
+    ///
+    /// Output: GmPrivateKey.
+    pub fn from_primes<R: CryptoRng + RngCore>(p: BigUint, q: BigUint, rng: &mut R) -> Self {
+        assert!(p != q && p.bit(0) && q.bit(0), "p and q must be distinct odd primes");
+        let n = &p * &q;
+        let y = loop {
+            let y = random_range(rng, &BigUint::from(2u64), &(&n - 1u64));
+            if legendre(&y, &p) == -1 && legendre(&y, &q) == -1 { break y; }
+        };
+        Self { public: GmPublicKey { n, y }, p, q }
+    }
+
+    /// The public half of the key.
+    pub fn public_key(&self) -> GmPublicKey {
+        self.public.clone()
+    }
+
+    /// The first secret prime.
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    /// The second secret prime.
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    /// Decrypts one bit with the Legendre symbol mod p: a square means 0 and a non-square means 1.
+    ///
+    /// Input: ciphertext (c) -> one encrypted bit.
+    ///
+    /// Output: Some(bit), or None if c can't be a ciphertext (it is too big or its Jacobi symbol
+    /// isn't 1).
+    pub fn decrypt_bit(&self, c: &BigUint) -> Option<bool> {
+        if *c >= self.public.n || jacobi(c, &self.public.n) != 1 { return None; }
+        Some(legendre(c, &self.p) == -1)
+    }
+
+    /// Decrypts the output of encrypt back into bytes.
+    ///
+    /// Input: ciphertexts (c) -> 8 per byte.
+    ///
+    /// Output: Some(message), or None if the number of ciphertexts isn't a multiple of 8 or any
+    /// of them is invalid.
+    pub fn decrypt(&self, c: &[BigUint]) -> Option<Vec<u8>> {
+        if !c.len().is_multiple_of(8) { return None; }
+        c.chunks(8).map(|byte| byte.iter().try_fold(0u8, |acc, c| Some(acc << 1 | self.decrypt_bit(c)? as u8))).collect()
+    }
+}
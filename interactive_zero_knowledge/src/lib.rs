@@ -1,6 +1,13 @@
 use bigint::uint::U512;
 use rand::RngCore;
 
+pub use crate::goldwasser_micali::{GmPrivateKey, GmPublicKey};
+pub use crate::prime::{jacobi, legendre};
+
+mod goldwasser_micali;
+mod math;
+mod prime;
+
 /// This is an example of an interactive zero-knowledge proof. There is the prover who has the goal
 /// to sufficiently convince the verifier that they have knowledge of something without revealing
 /// any information that would help the verifier prove to other people that he himself has
@@ -17,7 +24,7 @@ use rand::RngCore;
 /// Output: bool -> This proof is executed with 100 rounds. If any of the rounds fail, then the
 /// proof is invalid, and the function returns false. If the 100 rounds are all verified, then the
 /// proof is valid and "convince" returns true.
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::needless_late_init)]
 pub fn convince(p: U512, q: U512, x: U512) -> bool {
     let N = p * q;
     let y = (x * x) % N;
@@ -93,6 +100,7 @@ mod tests {
 345731
 */
     use super::*;
+    use num_bigint::BigUint;
     #[test]
     fn zero_knowledge_completeness() {
         assert!(convince(U512::from(4598933u64), U512::from(4598939u64), U512::from(rand::thread_rng().next_u64())));
@@ -104,4 +112,71 @@ mod tests {
         assert!(convince(U512::from(345701u64), U512::from(345707u64), U512::from(rand::thread_rng().next_u64())));
         assert!(convince(U512::from(345727u64), U512::from(345731u64), U512::from(rand::thread_rng().next_u64())));
     }
+    #[test]
+    fn jacobi_symbols() {
+        // (a/n) from the table on wikipedia, n = 3, 5, 7, 9, 15.
+        let table: [(u64, [i32; 10]); 5] = [
+            (3, [0, 1, -1, 0, 1, -1, 0, 1, -1, 0]),
+            (5, [0, 1, -1, -1, 1, 0, 1, -1, -1, 1]),
+            (7, [0, 1, 1, -1, 1, -1, -1, 0, 1, 1]),
+            (9, [0, 1, 1, 0, 1, 1, 0, 1, 1, 0]),
+            (15, [0, 1, 1, 0, 1, 0, 0, -1, 1, 0]),
+        ];
+        for (n, row) in table {
+            for (a, &expected) in row.iter().enumerate() {
+                assert_eq!(jacobi(&BigUint::from(a as u64), &BigUint::from(n)), expected, "({}/{})", a, n);
+            }
+        }
+        // For primes Euler's criterion and reciprocity agree.
+        for p in [3u64, 5, 7, 1223, 1987, 345679, 4598933] {
+            let p = BigUint::from(p);
+            for a in 0u64..100 {
+                let a = BigUint::from(a);
+                assert_eq!(legendre(&a, &p), jacobi(&a, &p));
+            }
+        }
+    }
+    #[test]
+    fn goldwasser_micali_encryption() {
+        let mut rng = rand::thread_rng();
+        let key = GmPrivateKey::generate(&mut rng, 512);
+        let public = key.public_key();
+        let n = public.n();
+        assert_eq!(key.p() * key.q(), *n);
+        // y looks like a square to anybody without p and q, but it isn't one.
+        assert_eq!(jacobi(public.y(), n), 1);
+        assert_eq!(legendre(public.y(), key.p()), -1);
+        assert_eq!(legendre(public.y(), key.q()), -1);
+
+        for bit in [false, true] {
+            let c = public.encrypt_bit(bit, &mut rng);
+            assert_eq!(jacobi(&c, n), 1);
+            assert_eq!(key.decrypt_bit(&c), Some(bit));
+            assert_ne!(public.encrypt_bit(bit, &mut rng), c);
+        }
+        assert_eq!(key.decrypt_bit(n), None);
+
+        let msg = b"semantic security";
+        let c = public.encrypt(msg, &mut rng);
+        assert_eq!(c.len(), 8 * msg.len());
+        assert_eq!(key.decrypt(&c).unwrap(), msg);
+        assert_eq!(key.decrypt(&c[1..]), None);
+        assert_eq!(key.decrypt(&public.encrypt(b"", &mut rng)).unwrap(), b"");
+
+        // XOR under encryption, for single bits and for whole strings.
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            let c = public.xor(&public.encrypt_bit(a, &mut rng), &public.encrypt_bit(b, &mut rng));
+            assert_eq!(key.decrypt_bit(&c), Some(a ^ b));
+        }
+        let pad = b"0123456789abcdefg";
+        let c = public.xor_bytes(&c, &public.encrypt(pad, &mut rng)).unwrap();
+        let expected: Vec<u8> = msg.iter().zip(pad).map(|(a, b)| a ^ b).collect();
+        assert_eq!(key.decrypt(&c).unwrap(), expected);
+        assert!(public.xor_bytes(&c, &c[8..]).is_none());
+
+        // Small primes work the same way.
+        let key = GmPrivateKey::from_primes(BigUint::from(4598933u64), BigUint::from(4598939u64), &mut rng);
+        let c = key.public_key().encrypt(b"hi", &mut rng);
+        assert_eq!(key.decrypt(&c).unwrap(), b"hi");
+    }
 }
@@ -0,0 +1,22 @@
+use num_bigint::BigUint;
+use rand::{CryptoRng, RngCore};
+
+/// Uniformly random number of at most "bits" bits.
+pub fn random_bits<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> BigUint {
+    let len = bits.div_ceil(8);
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    if len > 0 { bytes[0] &= 0xff >> (len * 8 - bits); }
+    BigUint::from_bytes_be(&bytes)
+}
+
+/// Uniformly random number in [low, high]. We draw numbers with as many bits as the width of the
+/// range and throw out anything too big, which takes fewer than two draws on average and (unlike
+/// reducing mod the width) doesn't favor small values.
+pub fn random_range<R: CryptoRng + RngCore>(rng: &mut R, low: &BigUint, high: &BigUint) -> BigUint {
+    let width = high - low;
+    loop {
+        let x = random_bits(rng, width.bits() as usize);
+        if x <= width { return low + x; }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::math::{random_bits, random_range};
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore};
+
+/// Primes below 2^8, used for trial division before we bother with Miller-Rabin.
+pub const SMALL_PRIMES: [u64; 54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// Number of Miller-Rabin rounds. Random candidates of the sizes we generate almost never fool
+/// even one round, so 40 leaves the error probability far below anything that matters.
+const MR_ROUNDS: usize = 40;
+
+/// The Jacobi symbol (a/n) for odd n, computed with quadratic reciprocity so we never have to
+/// factor n. For a prime n this is the Legendre symbol: 1 if a is a square mod n, -1 if it isn't
+/// and 0 if n divides a. For a composite n, (a/n) = 1 does NOT mean that a is a square, which is
+/// exactly the gap Goldwasser-Micali hides its bits in.
+///
+/// Input: number (a) -> any BigUint.
+/// Input: modulus (n) -> odd modulus.
+///
+/// Output: 1, -1 or 0.
+pub fn jacobi(a: &BigUint, n: &BigUint) -> i32 {
+    assert!(n.bit(0), "jacobi symbol is only defined for odd n");
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut res = 1;
+    while !a.is_zero() {
+        let tz = a.trailing_zeros().expect("a is not zero");
+        a >>= tz;
+        let n_mod_8 = low_u64(&n) & 7;
+        if tz & 1 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) { res = -res; }
+        if low_u64(&a) & 3 == 3 && n_mod_8 & 3 == 3 { res = -res; }
+        std::mem::swap(&mut a, &mut n);
+        a %= &n;
+    }
+    if n.is_one() { res } else { 0 }
+}
+
+/// The Legendre symbol (a/p) for an odd prime p by Euler's criterion: a^((p-1)/2) is 1 for a
+/// square, p - 1 (that is, -1) for a non-square and 0 if p divides a. Slower than jacobi, but it
+/// is the definition, and it only works because p is prime.
+///
+/// Input: number (a) -> any BigUint.
+/// Input: prime (p) -> odd prime.
+///
+/// Output: 1, -1 or 0.
+pub fn legendre(a: &BigUint, p: &BigUint) -> i32 {
+    let e = a.modpow(&((p - 1u64) >> 1), p);
+    if e.is_zero() { 0 } else if e.is_one() { 1 } else { -1 }
+}
+
+/// Draws random odd numbers with the top two bits set until one of them passes Miller-Rabin.
+///
+/// Input: random number generator (rng) -> source of randomness.
+/// Input: prime size (bits) -> exact bit length of the returned prime.
+///
+/// Output: prime -> a probable prime of exactly "bits" bits.
+pub fn generate_prime<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> BigUint {
+    assert!(bits >= 16, "primes must be at least 16 bits");
+    loop {
+        let mut candidate = random_bits(rng, bits);
+        candidate.set_bit(bits as u64 - 1, true);
+        candidate.set_bit(bits as u64 - 2, true);
+        candidate.set_bit(0, true);
+        if is_probable_prime(&candidate, rng) { return candidate; }
+    }
+}
+
+/// Trial division by the small primes, then Miller-Rabin with random bases.
+pub fn is_probable_prime<R: CryptoRng + RngCore>(n: &BigUint, rng: &mut R) -> bool {
+    if *n < BigUint::from(2u64) { return false; }
+    for &p in SMALL_PRIMES.iter() {
+        if *n == BigUint::from(p) { return true; }
+        if (n % p).is_zero() { return false; }
+    }
+    let n1 = n - 1u64;
+    let s = n1.trailing_zeros().expect("n is odd and bigger than 2");
+    let d = &n1 >> s;
+    let (two, high) = (BigUint::from(2u64), n - 2u64);
+    'rounds: for _ in 0..MR_ROUNDS {
+        let mut x = random_range(rng, &two, &high).modpow(&d, n);
+        if x.is_one() || x == n1 { continue; }
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n1 { continue 'rounds; }
+        }
+        return false;
+    }
+    true
+}
+
+/// The lowest 64 bits of n.
+fn low_u64(n: &BigUint) -> u64 {
+    n.iter_u64_digits().next().unwrap_or(0)
+}
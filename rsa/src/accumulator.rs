@@ -0,0 +1,333 @@
+use crate::error::RsaError;
+use crate::euclidian::{gcd, mod_inverse};
+use crate::hash::HashAlg;
+use crate::key::RsaPrivateKey;
+use crate::math::{mod_exp, os2ip, random_range};
+use crate::prime::baillie_psw;
+
+use num_bigint::BigUint;
+use num_traits::One;
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeSet;
+
+/// Size of the primes that hash_to_prime produces.
+pub const ACCUMULATOR_PRIME_BITS: usize = 256;
+
+/// The published part of an RSA accumulator: the modulus N, the base g and the current value
+/// c = g^(x_1 x_2 ... x_n) (mod N). That's all anybody needs to check a witness, and it stays the
+/// same size no matter how many elements went into it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    n: BigUint,
+    g: BigUint,
+    value: BigUint,
+}
+
+/// Proof that y is not in the set: a and d with c^a = d^y g (mod N) and 0 <= a < y. Such a pair
+/// exists exactly when gcd(y, x_1 ... x_n) = 1, i.e. when y isn't one of the accumulated primes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonMembershipWitness {
+    pub a: BigUint,
+    pub d: BigUint,
+}
+
+/// What changed between two accumulator values, for everybody holding witnesses to catch up
+/// with. Insertions and deletions can't go into the same update: the holders would need the
+/// value in between, and that value is never published.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccumulatorUpdate {
+    /// The primes were added and the accumulator moved to "value" = c^(product of primes).
+    Insert { primes: Vec<BigUint>, value: BigUint },
+    /// The primes were removed and the accumulator moved to "value", with value^(product of
+    /// primes) = c.
+    Delete { primes: Vec<BigUint>, value: BigUint },
+}
+
+/// The set behind an accumulator, kept by whoever manages it (the issuer of a revocation list,
+/// say). Without the factors of N, a deletion means recomputing c from the remaining elements,
+/// so this has to remember all of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccumulatorSet {
+    accumulator: Accumulator,
+    elements: BTreeSet<BigUint>,
+}
+
+impl Accumulator {
+
+    /// Rebuilds an empty accumulator from published parameters.
+    ///
+    /// Input: public modulus (n) -> an RSA modulus whose factors nobody knows.
+    /// Input: base (g) -> a unit mod N other than ±1, ideally a square.
+    ///
+    /// Output: Accumulator with value g, or RsaError::InvalidKey.
+    pub fn new(n: BigUint, g: BigUint) -> Result<Self, RsaError> {
+        if !n.bit(0) || n < BigUint::from(15u64) { return Err(RsaError::InvalidKey("modulus must be an odd composite")); }
+        if g < BigUint::from(2u64) || g >= &n - 1u64 || !gcd(&g, &n).is_one() { return Err(RsaError::InvalidKey("base must be a unit other than 1 and -1")); }
+        Ok(Self { value: g.clone(), n, g })
+    }
+
+    /// The public modulus N.
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// The base g, which is also the value of the empty set.
+    pub fn g(&self) -> &BigUint {
+        &self.g
+    }
+
+    /// The current value c.
+    pub fn value(&self) -> &BigUint {
+        &self.value
+    }
+
+    /// Checks that w^x = c (mod N). x has to be prime: w = c is a "witness" for x = 1, and a
+    /// witness for x_1 x_2 would work for their product as well.
+    ///
+    /// Input: element (x) -> a prime, usually from hash_to_prime.
+    /// Input: witness (w) -> its membership witness.
+    ///
+    /// Output: bool -> true if x is in the set.
+    pub fn verify_membership(&self, x: &BigUint, w: &BigUint) -> bool {
+        *w < self.n && baillie_psw(x) && mod_exp(w, x, &self.n) == self.value
+    }
+
+    /// Checks that c^a = d^y g (mod N) with a < y.
+    ///
+    /// Input: element (y) -> a prime, usually from hash_to_prime.
+    /// Input: witness (witness) -> its non-membership witness.
+    ///
+    /// Output: bool -> true if y is not in the set.
+    pub fn verify_non_membership(&self, y: &BigUint, witness: &NonMembershipWitness) -> bool {
+        if witness.a >= *y || witness.d >= self.n || !baillie_psw(y) { return false; }
+        mod_exp(&self.value, &witness.a, &self.n) == mod_exp(&witness.d, y, &self.n) * &self.g % &self.n
+    }
+
+    /// Moves to the value after an update, after checking that it really follows from the
+    /// current one. Update the witnesses first: the witness updates work from the old value.
+    ///
+    /// Output: () or RsaError::InvalidKey if the update doesn't fit this accumulator.
+    pub fn apply(&mut self, update: &AccumulatorUpdate) -> Result<(), RsaError> {
+        let consistent = match update {
+            AccumulatorUpdate::Insert { primes, value } => mod_exp(&self.value, &product(primes), &self.n) == *value,
+            AccumulatorUpdate::Delete { primes, value } => mod_exp(value, &product(primes), &self.n) == self.value,
+        };
+        if !consistent { return Err(RsaError::InvalidKey("update doesn't match the accumulator")); }
+        self.value = update.value().clone();
+        Ok(())
+    }
+
+    /// Brings a membership witness for x up to date without knowing the rest of the set.
+    ///
+    /// Insert of P = x_1 ... x_k: w' = w^P.
+    /// Delete of P (x not among them): with a = x^-1 (mod P), a x - b P = 1 and w' = c'^a w^-b,
+    /// since (c'^a w^-b)^x = c'^(ax) c'^(-bP) = c'.
+    ///
+    /// Input: element (x) -> the prime the witness is for.
+    /// Input: witness (w) -> its witness for the current value.
+    /// Input: update (update) -> the update that is about to be applied.
+    ///
+    /// Output: Some(new witness), or None if x was deleted (or the update makes no sense).
+    pub fn update_membership(&self, x: &BigUint, w: &BigUint, update: &AccumulatorUpdate) -> Option<BigUint> {
+        match update {
+            AccumulatorUpdate::Insert { primes, .. } => Some(mod_exp(w, &product(primes), &self.n)),
+            AccumulatorUpdate::Delete { primes, value } => {
+                if primes.contains(x) { return None; }
+                let p = product(primes);
+                let a = mod_inverse(x, &p)?;
+                let b = (&a * x - 1u64) / &p;
+                Some(mod_exp(value, &a, &self.n) * mod_exp(&mod_inverse(w, &self.n)?, &b, &self.n) % &self.n)
+            }
+        }
+    }
+
+    /// Brings a non-membership witness for y up to date without knowing the rest of the set.
+    ///
+    /// Insert of P: with α = P^-1 (mod y), c'^(aα) = c^(aαP) = c^a c^(a(αP - 1)), and αP - 1 is a
+    /// multiple of y. Reducing aα mod y gives a' = aα - ky and d' = d c^(a(αP - 1)/y) c'^-k.
+    /// Delete of P: c'^(aP) = c^a, so a' = aP - ky and d' = d c'^-k.
+    ///
+    /// Input: element (y) -> the prime the witness is for.
+    /// Input: witness (witness) -> its witness for the current value.
+    /// Input: update (update) -> the update that is about to be applied.
+    ///
+    /// Output: Some(new witness), or None if y was inserted (or the update makes no sense).
+    pub fn update_non_membership(&self, y: &BigUint, witness: &NonMembershipWitness, update: &AccumulatorUpdate) -> Option<NonMembershipWitness> {
+        let NonMembershipWitness { a, d } = witness;
+        let (exponent, d) = match update {
+            AccumulatorUpdate::Insert { primes, .. } => {
+                if primes.contains(y) { return None; }
+                let p = product(primes);
+                let alpha = mod_inverse(&p, y)?;
+                let shift = a * (&alpha * &p - 1u64) / y;
+                (a * alpha, d * mod_exp(&self.value, &shift, &self.n) % &self.n)
+            }
+            AccumulatorUpdate::Delete { primes, .. } => (a * product(primes), d.clone()),
+        };
+        let value = update.value();
+        let (k, a) = (&exponent / y, exponent % y);
+        let d = d * mod_inverse(&mod_exp(value, &k, &self.n), &self.n)? % &self.n;
+        Some(NonMembershipWitness { a, d })
+    }
+}
+
+impl AccumulatorUpdate {
+
+    /// The accumulator value after the update.
+    pub fn value(&self) -> &BigUint {
+        match self {
+            AccumulatorUpdate::Insert { value, .. } | AccumulatorUpdate::Delete { value, .. } => value,
+        }
+    }
+}
+
+impl AccumulatorSet {
+
+    /// Trusted setup: generates an RSA modulus the usual way, picks g as a random square mod N
+    /// and forgets the primes. Whoever knows p and q can take x-th roots, and with those forge a
+    /// membership witness for anything, so the key never leaves this function. If one party
+    /// running this isn't trustworthy enough, use a modulus nobody ever knew the factors of (the
+    /// RSA-2048 challenge number, for instance) with AccumulatorSet::new.
+    ///
+    /// Input: random number generator (rng) -> source of randomness for the primes and g.
+    /// Input: modulus size (bits) -> size of N in bits (2048 or more for real use).
+    ///
+    /// Output: AccumulatorSet holding the empty set.
+    pub fn setup<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> Self {
+        let n = RsaPrivateKey::generate(rng, bits).n().clone();
+        let r = random_range(rng, &BigUint::from(2u64), &(&n - 2u64));
+        let accumulator = Accumulator::new(n.clone(), &r * &r % &n).expect("a square of a random unit");
+        Self { accumulator, elements: BTreeSet::new() }
+    }
+
+    /// Starts an empty set under existing parameters.
+    pub fn new(accumulator: Accumulator) -> Self {
+        let accumulator = Accumulator { value: accumulator.g.clone(), ..accumulator };
+        Self { accumulator, elements: BTreeSet::new() }
+    }
+
+    /// The accumulator to publish.
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+
+    /// Every accumulated prime, in increasing order.
+    pub fn elements(&self) -> impl Iterator<Item = &BigUint> {
+        self.elements.iter()
+    }
+
+    /// Whether x is in the set.
+    pub fn contains(&self, x: &BigUint) -> bool {
+        self.elements.contains(x)
+    }
+
+    /// Adds a batch of primes: c' = c^(x_1 ... x_k), one exponentiation for the whole batch.
+    ///
+    /// Input: primes (primes) -> distinct primes that aren't in the set yet.
+    ///
+    /// Output: the update to hand to witness holders, or RsaError::InvalidElement (in which case
+    /// nothing was added).
+    pub fn insert(&mut self, primes: &[BigUint]) -> Result<AccumulatorUpdate, RsaError> {
+        self.check(primes, false)?;
+        let acc = &mut self.accumulator;
+        acc.value = mod_exp(&acc.value, &product(primes), &acc.n);
+        self.elements.extend(primes.iter().cloned());
+        Ok(AccumulatorUpdate::Insert { primes: primes.to_vec(), value: acc.value.clone() })
+    }
+
+    /// Removes a batch of primes. Without the factors of N there is no way to take a root of c,
+    /// so c is recomputed as g raised to the product of everything that's left.
+    ///
+    /// Input: primes (primes) -> distinct primes that are in the set.
+    ///
+    /// Output: the update to hand to witness holders, or RsaError::InvalidElement (in which case
+    /// nothing was removed).
+    pub fn delete(&mut self, primes: &[BigUint]) -> Result<AccumulatorUpdate, RsaError> {
+        self.check(primes, true)?;
+        for x in primes { self.elements.remove(x); }
+        let acc = &mut self.accumulator;
+        acc.value = mod_exp(&acc.g, &product(&self.elements), &acc.n);
+        Ok(AccumulatorUpdate::Delete { primes: primes.to_vec(), value: acc.value.clone() })
+    }
+
+    /// w = g^(product of every other element), so that w^x = c.
+    ///
+    /// Output: Some(witness), or None if x isn't in the set.
+    pub fn membership_witness(&self, x: &BigUint) -> Option<BigUint> {
+        if !self.contains(x) { return None; }
+        let acc = &self.accumulator;
+        Some(mod_exp(&acc.g, &product(self.elements.iter().filter(|&e| e != x)), &acc.n))
+    }
+
+    /// Witnesses for every element at once, in the order of elements(). Computing them one by
+    /// one takes n exponentiations by products of n - 1 primes; splitting the set in half and
+    /// raising g to each half's product before recursing into the other (Sander, Ta-Shma and
+    /// Yung's RootFactor) gets that down to O(n log n) prime-sized exponentiations.
+    pub fn membership_witnesses(&self) -> Vec<BigUint> {
+        let elements: Vec<&BigUint> = self.elements.iter().collect();
+        let mut witnesses = Vec::with_capacity(elements.len());
+        root_factor(&self.accumulator.g, &elements, &self.accumulator.n, &mut witnesses);
+        witnesses
+    }
+
+    /// Finds a and d with c^a = d^y g: with u the product of the set, a = u^-1 (mod y) and
+    /// d = g^((au - 1) / y), since c^a = g^(au) = g^(au - 1) g.
+    ///
+    /// Output: Some(witness), or None if y isn't prime or is in the set.
+    pub fn non_membership_witness(&self, y: &BigUint) -> Option<NonMembershipWitness> {
+        if self.contains(y) || !baillie_psw(y) { return None; }
+        let u = product(&self.elements);
+        let a = mod_inverse(&u, y)?;
+        let d = mod_exp(&self.accumulator.g, &((&a * u - 1u64) / y), &self.accumulator.n);
+        Some(NonMembershipWitness { a, d })
+    }
+
+    /// Every element of a batch has to be prime, appear once, and be in the set (present) or not.
+    fn check(&self, primes: &[BigUint], present: bool) -> Result<(), RsaError> {
+        let mut seen = BTreeSet::new();
+        for x in primes {
+            if !baillie_psw(x) { return Err(RsaError::InvalidElement("not a prime")); }
+            if !seen.insert(x) { return Err(RsaError::InvalidElement("listed twice")); }
+            if self.contains(x) != present {
+                return Err(RsaError::InvalidElement(if present { "not in the set" } else { "already in the set" }));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maps arbitrary data to a 256-bit prime: SHA-256(data || counter) with the top and bottom bits
+/// set, for counter = 0, 1, 2, ... until Baillie-PSW says prime. Each candidate is a fresh hash,
+/// so unlike searching upwards from one hash, no prime is more likely to come out than another.
+///
+/// Input: data (data) -> the element to accumulate, e.g. a serial number.
+///
+/// Output: prime -> a 256-bit prime that only this data hashes to (as far as SHA-256 goes).
+pub fn hash_to_prime(data: &[u8]) -> BigUint {
+    (0u32..)
+        .map(|counter| {
+            let mut x = os2ip(&HashAlg::Sha256.digest(&[data, &counter.to_be_bytes()]));
+            x.set_bit(ACCUMULATOR_PRIME_BITS as u64 - 1, true);
+            x.set_bit(0, true);
+            x
+        })
+        .find(baillie_psw)
+        .expect("a prime turns up within a few hundred tries")
+}
+
+fn product<'a>(xs: impl IntoIterator<Item = &'a BigUint>) -> BigUint {
+    xs.into_iter().fold(BigUint::one(), |acc, x| acc * x)
+}
+
+/// RootFactor: pushes g^(product of everything except x) for every x, in order.
+fn root_factor(g: &BigUint, xs: &[&BigUint], n: &BigUint, out: &mut Vec<BigUint>) {
+    match xs.len() {
+        0 => {}
+        1 => out.push(g.clone()),
+        len => {
+            let (left, right) = xs.split_at(len / 2);
+            root_factor(&mod_exp(g, &product(right.iter().copied()), n), left, n, out);
+            root_factor(&mod_exp(g, &product(left.iter().copied()), n), right, n, out);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::cryptanalysis::wiener;
+use crate::factor::p_minus_1;
+use crate::euclidian::{gcd, mod_inverse};
+use crate::key::carmichael_lambda;
+use crate::prime::{is_square, isqrt, sieve};
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// How hard audit looks for trouble.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// Smoothness bound B. A prime r counts as weak if every prime factor of r - 1 is at most B,
+    /// since Pollard's p - 1 method then pulls r straight out of N.
+    pub smoothness_bound: u64,
+    /// How many steps of Fermat's method to try, starting from ceil(sqrt(N)).
+    pub fermat_steps: u64,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { smoothness_bound: 1 << 16, fermat_steps: 1 << 16 }
+    }
+}
+
+/// Something audit found wrong with a modulus or its exponents. Every variant is an actual attack
+/// that works (we found the factors or d), except SmallPublicExponent, which is only a warning.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Weakness {
+    /// p and q are so close to sqrt(N) that Fermat's method found them after "steps" steps.
+    CloseFactors { p: BigUint, q: BigUint, steps: u64 },
+    /// factor - 1 has no prime factors bigger than "bound", so Pollard's p - 1 recovers it.
+    SmoothFactor { factor: BigUint, bound: u64 },
+    /// d is small enough (below Wiener's bound of N^(1/4) / 3) to fall out of the continued
+    /// fraction expansion of e / N.
+    SmallPrivateExponent { d: BigUint },
+    /// e is below 65537. Harmless with OAEP or PSS, but textbook RSA with a small e falls to
+    /// Håstad's broadcast attack and Coppersmith's stereotyped message attack.
+    SmallPublicExponent,
+    /// N shares "factor" with the modulus at position "other" of the batch, so both are broken.
+    SharedFactor { other: usize, factor: BigUint },
+}
+
+/// Checks a public key (and, if you have them, its primes) for the classic ways an RSA modulus
+/// goes wrong, using the default AuditConfig.
+///
+/// Input: modulus (n) -> the public modulus N.
+/// Input: public exponent (e) -> the public exponent.
+/// Input: secret primes (factors) -> Some((p, q)) if you have them. With the primes the checks are
+/// exact; without them we actually have to run the attacks, which only catches the weaknesses that
+/// the attacks can find within the configured bounds.
+///
+/// Output: every weakness that was found. An empty list means the key passed.
+pub fn audit(n: &BigUint, e: &BigUint, factors: Option<(&BigUint, &BigUint)>) -> Vec<Weakness> {
+    audit_with(n, e, factors, AuditConfig::default())
+}
+
+/// Same as audit, but with the bounds chosen by the caller.
+pub fn audit_with(n: &BigUint, e: &BigUint, factors: Option<(&BigUint, &BigUint)>, config: AuditConfig) -> Vec<Weakness> {
+    let mut weaknesses = Vec::new();
+    if *e < BigUint::from(65537u64) { weaknesses.push(Weakness::SmallPublicExponent); }
+
+    // Fermat. If we know p and q we don't have to walk there: the walk ends at a = (p + q) / 2.
+    let fermat = match factors {
+        Some((p, q)) => {
+            let start = ceil_sqrt(n);
+            let end: BigUint = (p + q) >> 1;
+            match u64::try_from(end - start) {
+                Ok(steps) if steps <= config.fermat_steps => Some((p.min(q).clone(), p.max(q).clone(), steps)),
+                _ => None,
+            }
+        }
+        None => fermat(n, config.fermat_steps),
+    };
+    if let Some((p, q, steps)) = fermat { weaknesses.push(Weakness::CloseFactors { p, q, steps }); }
+
+    // Smooth r - 1. With the primes in hand we can simply factor r - 1 over the primes up to B.
+    let primes = sieve(config.smoothness_bound);
+    let bound = config.smoothness_bound;
+    match factors {
+        Some((p, q)) => {
+            for r in [p.min(q), p.max(q)] {
+                if is_smooth(&(r - 1u64), &primes) { weaknesses.push(Weakness::SmoothFactor { factor: r.clone(), bound }); }
+            }
+        }
+        None => {
+            if let Some(factor) = p_minus_1(n, &primes, bound) { weaknesses.push(Weakness::SmoothFactor { factor, bound }); }
+        }
+    }
+
+    // Wiener. The continued fraction attack needs nothing but (N, e); with the primes we also
+    // check d against the bound directly.
+    let d = wiener(n, e).map(|(d, _, _)| d).or_else(|| {
+        let (p, q) = factors?;
+        let d = mod_inverse(e, &carmichael_lambda(&[p.clone(), q.clone()]))?;
+        let d4 = d.pow(4u32);
+        (d4 * 81u64 < *n).then_some(d)
+    });
+    if let Some(d) = d { weaknesses.push(Weakness::SmallPrivateExponent { d }); }
+
+    weaknesses
+}
+
+/// Audits a whole batch of moduli for shared primes. Two keys generated with a bad rng (the
+/// classic example is a router generating its key at first boot, before it has any entropy) can
+/// end up with one prime in common, and then gcd(N1, N2) breaks both of them. Comparing every
+/// pair takes n^2 gcds; batch_gcd does the whole batch in roughly the time of a few products.
+///
+/// Input: moduli (moduli) -> the public moduli to compare against each other.
+///
+/// Output: one list per modulus with a SharedFactor for every other modulus it shares a prime
+/// with. Empty lists mean that modulus is fine.
+pub fn audit_batch(moduli: &[BigUint]) -> Vec<Vec<Weakness>> {
+    let shared = batch_gcd(moduli);
+    // Only the moduli that batch_gcd flagged need to be compared pairwise to find out who they
+    // share with, and there are hopefully very few of those.
+    let flagged: Vec<usize> = (0..moduli.len()).filter(|&i| !shared[i].is_one()).collect();
+    let mut weaknesses = vec![Vec::new(); moduli.len()];
+    for &i in flagged.iter() {
+        for &j in flagged.iter().filter(|&&j| j != i) {
+            let factor = gcd(&moduli[i], &moduli[j]);
+            if !factor.is_one() { weaknesses[i].push(Weakness::SharedFactor { other: j, factor }); }
+        }
+    }
+    weaknesses
+}
+
+/// Bernstein's batch GCD: gcd(N_i, product of all the other N_j) for every i. We build a product
+/// tree of the moduli, push the product at the root back down as a remainder tree (reducing mod
+/// N_i^2 at every node), and at the leaves gcd(R_i / N_i, N_i) is the answer.
+///
+/// Input: moduli (moduli) -> the moduli to compare.
+///
+/// Output: for every modulus, the product of the primes it shares with the rest of the batch (1 if
+/// none). A modulus that appears twice in the batch comes back as itself.
+pub fn batch_gcd(moduli: &[BigUint]) -> Vec<BigUint> {
+    if moduli.len() < 2 { return vec![BigUint::one(); moduli.len()]; }
+    let mut tree = vec![moduli.to_vec()];
+    while tree.last().unwrap().len() > 1 {
+        let level = tree.last().unwrap();
+        let next = level.chunks(2).map(|pair| pair.iter().product()).collect();
+        tree.push(next);
+    }
+
+    let mut remainders = tree.pop().unwrap();
+    while let Some(level) = tree.pop() {
+        remainders = level.iter().enumerate().map(|(i, x)| &remainders[i / 2] % (x * x)).collect();
+    }
+    remainders.iter().zip(moduli).map(|(r, n)| gcd(&(r / n), n)).collect()
+}
+
+/// Fermat's method: walk a up from ceil(sqrt(N)) until a^2 - N = b^2, and then N = (a - b)(a + b).
+/// It takes (p + q) / 2 - sqrt(N) steps, which is tiny when p and q are close together.
+fn fermat(n: &BigUint, steps: u64) -> Option<(BigUint, BigUint, u64)> {
+    if n.is_even() { return None; }
+    let start = ceil_sqrt(n);
+    // a^2 - N, updated as a goes up: (a + 1)^2 - a^2 = 2a + 1.
+    let mut b2 = &start * &start - n;
+    for step in 0..=steps {
+        let a = &start + step;
+        if is_square(&b2) {
+            let b = isqrt(&b2);
+            let p = &a - &b;
+            if !p.is_one() { return Some((p, a + b, step)); }
+            return None;
+        }
+        b2 += (a << 1) + 1u64;
+    }
+    None
+}
+
+/// True if every prime factor of x is in "primes".
+fn is_smooth(x: &BigUint, primes: &[u64]) -> bool {
+    let mut x = x.clone();
+    for &l in primes {
+        let l = BigUint::from(l);
+        while (&x % &l).is_zero() { x /= &l; }
+        if x.is_one() { return true; }
+    }
+    x.is_one()
+}
+
+/// Ceiling of the square root of n.
+fn ceil_sqrt(n: &BigUint) -> BigUint {
+    let r = isqrt(n);
+    if &r * &r == *n { r } else { r + 1u64 }
+}
@@ -0,0 +1,143 @@
+use crate::euclidian::{mod_inverse, xgcd};
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::mod_exp;
+use crate::prime::{is_square, isqrt};
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// The most unknown bits stereotyped_message_attack will search through. Every bit doubles the
+/// running time, and 2^32 steps is already hours.
+pub const MAX_UNKNOWN_BITS: usize = 32;
+
+/// Wiener's attack: if d < N^(1/4) / 3, the whole private key falls out of the continued fraction
+/// of e / N. Keys like that come from picking a small d on purpose (to make decryption fast) and
+/// working out e from it, which is why e that is about as big as N is a red flag.
+///
+/// Input: public key (key) -> the key to attack.
+///
+/// Output: Some(private key) rebuilt from the recovered p and q, or None if d is too big for the
+/// attack to work.
+pub fn wiener_attack(key: &RsaPublicKey) -> Option<RsaPrivateKey> {
+    let (_, p, q) = wiener(key.n(), key.e())?;
+    RsaPrivateKey::from_primes(p, q, key.e().clone()).ok()
+}
+
+/// Håstad's broadcast attack: the same message m sent with textbook RSA to e recipients that all
+/// use the same small e. The CRT turns the e ciphertexts into m^e mod N_1 N_2 ... N_e, and since
+/// m is smaller than every N_i, m^e is smaller than their product. So that number is m^e itself,
+/// no modular reduction involved, and an ordinary integer e-th root gives back m. Padding with
+/// fresh randomness (OAEP) for every recipient is what stops this.
+///
+/// Input: public keys (keys) -> at least e keys with the same e and different moduli.
+/// Input: ciphertexts (ciphertexts) -> the ciphertext sent to each key, in the same order.
+///
+/// Output: Some(m), or None if the keys don't fit the attack (different e, too few of them,
+/// moduli with a factor in common) or no e-th root comes out.
+pub fn hastad_broadcast(keys: &[RsaPublicKey], ciphertexts: &[BigUint]) -> Option<BigUint> {
+    let e = u32::try_from(keys.first()?.e()).ok()?;
+    if keys.len() != ciphertexts.len() || keys.len() < e as usize || keys.iter().any(|key| key.e() != keys[0].e()) { return None; }
+    let (mut x, mut modulus) = (BigUint::zero(), BigUint::one());
+    for (key, c) in keys.iter().zip(ciphertexts).take(e as usize) {
+        // x = x mod M and c mod N_i at the same time: x + M ((c - x) M^-1 mod N_i).
+        let n = key.n();
+        let step = (c % n + n - &x % n) % n * mod_inverse(&modulus, n)? % n;
+        x += &modulus * step;
+        modulus *= n;
+    }
+    let m = x.nth_root(e);
+    (m.pow(e) == x).then_some(m)
+}
+
+/// The common modulus attack: the same message encrypted under one N with two exponents e1 and
+/// e2 that are coprime. With a e1 + b e2 = 1 (one of a and b is negative),
+/// c1^a c2^b = m^(a e1 + b e2) = m, no private key needed. That's why one N must never be handed
+/// to two people, even with different exponents.
+///
+/// Input: public key #1 (key1) and ciphertext #1 (c1) -> m^e1 mod N.
+/// Input: public key #2 (key2) and ciphertext #2 (c2) -> m^e2 mod N.
+///
+/// Output: Some(m), or None if the moduli differ, gcd(e1, e2) isn't 1, or a ciphertext isn't
+/// invertible (in which case it shares a factor with N, and that's a worse problem).
+pub fn common_modulus_attack(key1: &RsaPublicKey, c1: &BigUint, key2: &RsaPublicKey, c2: &BigUint) -> Option<BigUint> {
+    let n = key1.n();
+    if key2.n() != n { return None; }
+    let (g, a, b) = xgcd(key1.e(), key2.e());
+    if !g.is_one() { return None; }
+    Some(signed_pow(c1, &a, n)? * signed_pow(c2, &b, n)? % n)
+}
+
+/// Stereotyped messages with a tiny e: most of m is known ("your PIN is ____"), and only the last
+/// few bits x are secret. Coppersmith's method finds any x below N^(1/e) with lattice reduction;
+/// without it we can still search every x, and f(x) = (known + x)^e mod N is a polynomial of
+/// degree e, so its values can be walked with the method of finite differences: keep
+/// f(x), Δf(x), ..., Δ^e f, and every step is e additions mod N instead of an exponentiation.
+///
+/// Input: public key (key) -> the key the message was encrypted to, with a small e.
+/// Input: ciphertext (c) -> m^e mod N.
+/// Input: known part (known) -> m with the unknown bits set to zero.
+/// Input: unknown bits (unknown_bits) -> how many of m's lowest bits are unknown (at most
+/// MAX_UNKNOWN_BITS).
+///
+/// Output: Some(m), or None if no x of that many bits fits.
+pub fn stereotyped_message_attack(key: &RsaPublicKey, c: &BigUint, known: &BigUint, unknown_bits: usize) -> Option<BigUint> {
+    let (n, e) = (key.n(), usize::try_from(key.e()).ok()?);
+    if unknown_bits > MAX_UNKNOWN_BITS || e > 17 { return None; }
+    // Δ^j f(0) for j = 0..e, from f(0), ..., f(e) by repeated differences.
+    let mut diffs: Vec<BigUint> = (0..=e as u64).map(|x| mod_exp(&(known + x), key.e(), n)).collect();
+    for j in 1..=e {
+        for i in (j..=e).rev() {
+            diffs[i] = (&diffs[i] + n - &diffs[i - 1]) % n;
+        }
+    }
+    for x in 0..1u64 << unknown_bits {
+        if diffs[0] == *c { return Some(known + x); }
+        for j in 0..e {
+            let next = &diffs[j] + &diffs[j + 1];
+            diffs[j] = if next >= *n { next - n } else { next };
+        }
+    }
+    None
+}
+
+/// The continued fraction part of Wiener's attack. If d < N^(1/4) / 3 then k / d is one of the
+/// convergents of e / N (where ed = 1 + k * φ(N)). For every convergent we work out the φ(N) it
+/// would imply and check whether x^2 - (N - φ + 1) x + N = 0 has p and q as roots.
+///
+/// Output: Some((d, p, q)), or None.
+pub(crate) fn wiener(n: &BigUint, e: &BigUint) -> Option<(BigUint, BigUint, BigUint)> {
+    let (mut num, mut den) = (e.clone(), n.clone());
+    // Convergents h / k of e / N, where h plays the part of k and k the part of d above.
+    let (mut h0, mut h1) = (BigUint::zero(), BigUint::one());
+    let (mut k0, mut k1) = (BigUint::one(), BigUint::zero());
+    while !den.is_zero() {
+        let (a, r) = num.div_rem(&den);
+        (num, den) = (den, r);
+        (h0, h1) = (h1.clone(), a.clone() * &h1 + h0);
+        (k0, k1) = (k1.clone(), a * &k1 + k0);
+        let (k, d) = (&h1, &k1);
+        if k.is_zero() || d.is_even() { continue; }
+        let ed1 = e * d - 1u64;
+        if !(&ed1 % k).is_zero() { continue; }
+        let phi = ed1 / k;
+        if phi >= *n { continue; }
+        let s = n - phi + 1u64;
+        let s2 = &s * &s;
+        let four_n = n * 4u64;
+        if s2 < four_n { continue; }
+        let disc = s2 - four_n;
+        if !is_square(&disc) || (&s + isqrt(&disc)).is_odd() { continue; }
+        // p and q are (s ± sqrt(disc)) / 2.
+        let root = isqrt(&disc);
+        return Some((d.clone(), (&s + &root) >> 1, (s - root) >> 1));
+    }
+    None
+}
+
+/// x^k (mod n) for a k that may be negative, in which case x has to be invertible.
+fn signed_pow(x: &BigUint, k: &BigInt, n: &BigUint) -> Option<BigUint> {
+    let (sign, k) = k.clone().into_parts();
+    let x = if sign == Sign::Minus { mod_inverse(x, n)? } else { x.clone() };
+    Some(mod_exp(&x, &k, n))
+}
@@ -0,0 +1,107 @@
+use crate::error::RsaError;
+
+use num_bigint::BigUint;
+
+const INTEGER: u8 = 0x02;
+const SEQUENCE: u8 = 0x30;
+
+/// Just enough DER (X.690) to read and write PKCS#1 keys: non-negative INTEGERs and SEQUENCEs.
+/// Anything else, or anything that isn't in the one canonical DER form, is rejected.
+///
+/// Input: value (x) -> non-negative integer.
+///
+/// Output: the INTEGER encoding of x.
+pub fn integer(x: &BigUint) -> Vec<u8> {
+    let mut bytes = x.to_bytes_be();
+    // DER integers are two's complement, so a set top bit needs a zero in front of it.
+    if bytes[0] & 0x80 != 0 { bytes.insert(0, 0); }
+    tlv(INTEGER, &bytes)
+}
+
+/// Wraps already encoded elements in a SEQUENCE.
+pub fn sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+    tlv(SEQUENCE, &elements.concat())
+}
+
+/// Tag, length, then the value. Lengths below 128 fit in a single byte; anything longer is 0x80
+/// plus the number of length bytes that follow.
+fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if value.len() < 0x80 {
+        out.push(value.len() as u8);
+    }
+    else {
+        let len = value.len().to_be_bytes();
+        let skip = len.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (len.len() - skip) as u8);
+        out.extend_from_slice(&len[skip..]);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+/// Walks through the elements of a DER encoding one at a time.
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Reads a SEQUENCE and returns a reader over its contents.
+    pub fn sequence(&mut self) -> Result<Reader<'a>, RsaError> {
+        Ok(Reader::new(self.element(SEQUENCE)?))
+    }
+
+    /// Reads a non-negative INTEGER.
+    pub fn integer(&mut self) -> Result<BigUint, RsaError> {
+        let value = self.element(INTEGER)?;
+        match value {
+            [] => Err(RsaError::InvalidKey("empty DER integer")),
+            [b, ..] if b & 0x80 != 0 => Err(RsaError::InvalidKey("negative DER integer")),
+            [0, b, ..] if b & 0x80 == 0 => Err(RsaError::InvalidKey("DER integer has extra leading zeros")),
+            _ => Ok(BigUint::from_bytes_be(value)),
+        }
+    }
+
+    /// True once everything has been read.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Makes sure nothing is left over.
+    pub fn finish(&self) -> Result<(), RsaError> {
+        if self.is_empty() { Ok(()) } else { Err(RsaError::InvalidKey("trailing data after DER element")) }
+    }
+
+    fn element(&mut self, tag: u8) -> Result<&'a [u8], RsaError> {
+        let malformed = RsaError::InvalidKey("malformed DER");
+        let (&first, rest) = self.data.split_first().ok_or(malformed)?;
+        if first != tag { return Err(RsaError::InvalidKey("unexpected DER tag")); }
+        let (&len_byte, mut rest) = rest.split_first().ok_or(malformed)?;
+
+        let len = if len_byte < 0x80 {
+            len_byte as usize
+        }
+        else {
+            let count = (len_byte & 0x7f) as usize;
+            if count == 0 || count > std::mem::size_of::<usize>() || rest.len() < count { return Err(malformed); }
+            let (len_bytes, after) = rest.split_at(count);
+            rest = after;
+            // DER wants the shortest length encoding: no leading zero bytes, and no long form for
+            // anything that fits in the short one.
+            if len_bytes[0] == 0 { return Err(malformed); }
+            let len = len_bytes.iter().fold(0usize, |l, &b| (l << 8) | b as usize);
+            if len < 0x80 { return Err(malformed); }
+            len
+        };
+
+        if rest.len() < len { return Err(malformed); }
+        let (value, rest) = rest.split_at(len);
+        self.data = rest;
+        Ok(value)
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt;
+
+/// Everything that can go wrong when encrypting or decrypting padded messages. Decryption failures
+/// are deliberately lumped into one variant: telling an attacker *why* a ciphertext was rejected
+/// is exactly the kind of oracle that padding attacks feed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RsaError {
+    /// The message doesn't fit in the modulus once the padding is added.
+    MessageTooLong,
+    /// The modulus is too small to hold the padding for the chosen hash at all.
+    KeyTooSmall,
+    /// The key material doesn't describe a usable RSA key.
+    InvalidKey(&'static str),
+    /// The ciphertext was the wrong size, out of range, or decrypted to invalid padding.
+    Decryption,
+    /// An accumulator element isn't prime, or is (or isn't) in the set already.
+    InvalidElement(&'static str),
+}
+
+impl fmt::Display for RsaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsaError::MessageTooLong => write!(f, "message too long"),
+            RsaError::KeyTooSmall => write!(f, "modulus too small for the chosen padding"),
+            RsaError::InvalidKey(reason) => write!(f, "invalid key: {}", reason),
+            RsaError::Decryption => write!(f, "decryption error"),
+            RsaError::InvalidElement(reason) => write!(f, "invalid accumulator element: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RsaError {}
@@ -0,0 +1,536 @@
+use crate::euclidian::{gcd, mod_inverse};
+use crate::math::mod_exp;
+use crate::prime::{baillie_psw, sieve};
+use crate::rabin::sqrt_mod_prime;
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive, Zero};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The largest number quadratic_sieve takes on. All of its sieving arithmetic is done in 128-bit
+/// integers, which holds everything up to this size.
+pub const QS_MAX_BITS: u64 = 120;
+
+/// factor divides out every prime up to this bound before trying anything clever.
+const TRIAL_DIVISION_BOUND: u64 = 1000;
+
+/// How many steps Brent's rho takes between gcds.
+const RHO_BATCH: u64 = 128;
+
+/// Factors n completely. Small primes are divided out by trial division, and whatever is left is
+/// split by the first method that works, cheapest first: Pollard's rho for factors up to about 2^30
+/// (and for everything up to 50 bits), Pollard's p - 1 for factors p with a smooth p - 1, the
+/// quadratic sieve for anything up to QS_MAX_BITS, and ECM with growing bounds after that. Past
+/// about 200 bits with no small factor, don't hold your breath: that's RSA doing its job.
+///
+/// Input: number (n) -> the number to factor.
+///
+/// Output: every prime factor of n with its exponent, smallest first. 0 and 1 have none.
+pub fn factor(n: &BigUint) -> Vec<(BigUint, u32)> {
+    let mut factors = BTreeMap::new();
+    if n.is_zero() { return Vec::new(); }
+    let mut n = n.clone();
+    for p in sieve(TRIAL_DIVISION_BOUND) {
+        let p = BigUint::from(p);
+        while (&n % &p).is_zero() {
+            n /= &p;
+            *factors.entry(p.clone()).or_insert(0) += 1;
+        }
+    }
+    let mut composites = vec![n];
+    while let Some(m) = composites.pop() {
+        if m.is_one() { continue; }
+        if baillie_psw(&m) {
+            *factors.entry(m).or_insert(0) += 1;
+            continue;
+        }
+        let d = split(&m);
+        composites.push(&m / &d);
+        composites.push(d);
+    }
+    factors.into_iter().collect()
+}
+
+/// Pollard's rho with Brent's cycle detection. x -> x^2 + c (mod n) looks random enough that mod
+/// a prime p of n it starts repeating after about sqrt(p) steps, long before it does mod n, and
+/// gcd(x_i - x_j, n) catches the moment. Brent's version only keeps one saved x at a time (moved
+/// to the current position at every power of 2) and multiplies RHO_BATCH differences together
+/// before each gcd, so the gcd cost all but disappears.
+///
+/// Input: number (n) -> an odd composite.
+/// Input: step limit (steps) -> how many steps to take in total before giving up. A factor p
+/// takes about sqrt(p) of them.
+///
+/// Output: Some(nontrivial factor), or None if n is prime, too small, or the steps ran out.
+pub fn pollard_rho(n: &BigUint, steps: u64) -> Option<BigUint> {
+    if *n < BigUint::from(4u64) || baillie_psw(n) { return None; }
+    if n.is_even() { return Some(BigUint::from(2u64)); }
+    let mut left = steps;
+    for c in 1u64.. {
+        let f = |x: &BigUint| (x * x + c) % n;
+        let (mut x, mut y, mut ys) = (BigUint::from(2u64), BigUint::from(2u64), BigUint::from(2u64));
+        let (mut q, mut g, mut r) = (BigUint::one(), BigUint::one(), 1u64);
+        while g.is_one() {
+            left = left.checked_sub(2 * r)?;
+            x = y.clone();
+            for _ in 0..r { y = f(&y); }
+            let mut k = 0;
+            while k < r && g.is_one() {
+                ys = y.clone();
+                for _ in 0..RHO_BATCH.min(r - k) {
+                    y = f(&y);
+                    q = q * abs_diff(&x, &y) % n;
+                }
+                g = gcd(&q, n);
+                k += RHO_BATCH;
+            }
+            r *= 2;
+        }
+        if g == *n {
+            // The batch overshot (every prime of n cycled within it). Step through it again with
+            // a gcd every time.
+            loop {
+                ys = f(&ys);
+                g = gcd(&abs_diff(&x, &ys), n);
+                if !g.is_one() { break; }
+            }
+        }
+        // g = n even one step at a time means x^2 + c cycles mod every prime at once. Try
+        // another c.
+        if g != *n { return Some(g); }
+    }
+    None
+}
+
+/// Pollard's p - 1 with smoothness bound B: finds a prime p of n when every prime power dividing
+/// p - 1 is at most B.
+///
+/// Input: number (n) -> the number to split.
+/// Input: smoothness bound (bound) -> B.
+///
+/// Output: Some(nontrivial factor), or None.
+pub fn pollard_p_minus_1(n: &BigUint, bound: u64) -> Option<BigUint> {
+    if *n < BigUint::from(4u64) { return None; }
+    p_minus_1(n, &sieve(bound), bound)
+}
+
+/// Lenstra's elliptic curve method. Pollard's p - 1 works in the group (Z/pZ)^*, whose order
+/// p - 1 is fixed. ECM does the same thing on a random curve y^2 = x^3 + ax + b over Z/NZ instead,
+/// whose order mod p is some random number in p + 1 ± 2 sqrt(p), and every new curve is another
+/// chance for that order to be B-smooth. Multiplying a point by every prime power up to B then
+/// ends at the point at infinity mod p, and on the way there some slope has a denominator that
+/// is 0 mod p but not mod N: its gcd with N is the factor.
+///
+/// The curves are picked from a fixed sequence, so the same arguments always give the same answer.
+///
+/// Input: number (n) -> the number to split.
+/// Input: curves (curves) -> how many curves to try.
+/// Input: smoothness bound (bound) -> B. Around 2000 finds 20-digit factors, and 50000
+/// 30-digit ones, given enough curves.
+///
+/// Output: Some(nontrivial factor), or None.
+pub fn ecm(n: &BigUint, curves: usize, bound: u64) -> Option<BigUint> {
+    if *n < BigUint::from(4u64) || baillie_psw(n) { return None; }
+    if n.is_even() { return Some(BigUint::from(2u64)); }
+    let primes = sieve(bound);
+    let mut rng = SplitMix(bound ^ n.bits());
+    for _ in 0..curves {
+        // A random point and a random a; b is whatever puts the point on the curve, and it never
+        // shows up in the formulas.
+        let mut point = AffinePoint::new(BigUint::from(rng.next()) % n, BigUint::from(rng.next()) % n);
+        let curve = Curve { a: BigUint::from(rng.next()) % n, n };
+        for &l in &primes {
+            match point.mul(&BigUint::from(prime_power(l, bound)), &curve) {
+                Ok(next) => point = next,
+                Err(g) if g != *n => return Some(g),
+                // Infinity mod every prime at once: this curve is no use.
+                Err(_) => break,
+            }
+            if point.infinity { break; }
+        }
+    }
+    None
+}
+
+/// A small self-initializing quadratic sieve (SIQS, see Contini's thesis). It looks for many x
+/// with (ax + b)^2 - N = a Q(x) where Q(x) is small and factors completely over a base of small
+/// primes, and once there are more of those relations than primes, linear algebra mod 2 finds a
+/// subset whose product is a square on both sides: X^2 = Y^2 (mod N), and gcd(X - Y, N) is a
+/// factor half the time.
+///
+/// Every a is a product of a few primes from the factor base, and each a comes with 2^(s-1)
+/// different b. Going from one b to the next only changes one ±B_l, so the sieve starting
+/// points are updated with one subtraction per prime instead of being recomputed: that's the
+/// "self-initializing" part, and it's why small intervals (and lots of polynomials) pay off.
+///
+/// Input: number (n) -> an odd composite of at most QS_MAX_BITS bits that isn't a prime power.
+///
+/// Output: Some(nontrivial factor), or None if n is out of range.
+pub fn quadratic_sieve(n: &BigUint) -> Option<BigUint> {
+    if n.bits() > QS_MAX_BITS || *n < BigUint::from(4u64) || baillie_psw(n) { return None; }
+    if n.is_even() { return Some(BigUint::from(2u64)); }
+    // Every relation is a square mod p^k as well, so prime powers never split this way.
+    if let Some(root) = perfect_power(n) { return Some(root); }
+    let nn = n.to_u128()?;
+    let (base_size, half) = qs_parameters(n.bits());
+
+    // The factor base: 2, and every odd prime p that N is a square mod, with t^2 = N (mod p).
+    let mut base = vec![FactorBasePrime { p: 2, t: 1, log: 1 }];
+    for p in sieve(1 << 16).into_iter().skip(1) {
+        let r = (nn % p as u128) as u64;
+        if r == 0 { return Some(BigUint::from(p)); }
+        if pow_mod(r, (p - 1) / 2, p) != 1 { continue; }
+        let t = sqrt_mod_prime(&BigUint::from(r), &BigUint::from(p))?.to_u64()?;
+        base.push(FactorBasePrime { p, t, log: (p as f64).log2().round() as u8 });
+        if base.len() == base_size { break; }
+    }
+    let columns = base.len() + 1;
+
+    // a should be about sqrt(2N) / M, so that |Q(x)| stays below M sqrt(N / 2) over [-M, M). Its
+    // primes come from the top two thirds of the base, out of the way of the small primes that
+    // do most of the sieving.
+    let target = ((2.0 * nn as f64).sqrt() / half as f64).log2();
+    let pool: Vec<usize> = (base.len() / 3..base.len()).collect();
+    let average = pool.iter().map(|&j| (base[j].p as f64).log2()).sum::<f64>() / pool.len() as f64;
+    let s = ((target / average).round() as usize).clamp(1, pool.len() / 2);
+    let max_log = (half as f64).log2() + (nn as f64 / 2.0).sqrt().log2();
+    let threshold = (max_log - (base[base.len() - 1].p as f64).log2() - 3.0).max(0.0) as u8;
+
+    let mut rng = SplitMix(nn as u64);
+    let mut used = BTreeSet::new();
+    let mut relations = Vec::new();
+    let mut wanted = columns + 10;
+    let mut sieve_array = vec![0u8; 2 * half as usize];
+    loop {
+        // Pick s - 1 of the primes at random and the last one to land a as close to the target
+        // as possible.
+        let mut a_primes = Vec::new();
+        for _ in 0..1000 {
+            let mut chosen: Vec<usize> = Vec::new();
+            while chosen.len() < s - 1 {
+                let j = pool[rng.next() as usize % pool.len()];
+                if !chosen.contains(&j) { chosen.push(j); }
+            }
+            let log: f64 = chosen.iter().map(|&j| (base[j].p as f64).log2()).sum();
+            let miss = |j: &usize| (log + (base[*j].p as f64).log2() - target).abs();
+            let last = pool.iter().filter(|j| !chosen.contains(j)).min_by(|x, y| miss(x).total_cmp(&miss(y)))?;
+            chosen.push(*last);
+            chosen.sort_unstable();
+            if used.insert(chosen.clone()) {
+                a_primes = chosen;
+                break;
+            }
+        }
+        if a_primes.is_empty() { return None; }
+        let a: u128 = a_primes.iter().map(|&j| base[j].p as u128).product();
+
+        // B_l = (a / q_l) γ with γ = t_l (a / q_l)^-1 (mod q_l), so that B_l^2 = N (mod q_l) and
+        // B_l = 0 mod every other q. Any b = ±B_1 ± ... ± B_s then has b^2 = N (mod a).
+        let bs: Vec<i128> = a_primes.iter().map(|&j| {
+            let FactorBasePrime { p, t, .. } = base[j];
+            let rest = a / p as u128;
+            let gamma = t * inv_mod((rest % p as u128) as u64, p) % p;
+            (rest * gamma.min(p - gamma) as u128) as i128
+        }).collect();
+        let mut signs = vec![1i128; s];
+        let mut b: i128 = bs.iter().sum();
+
+        // For every other prime: the two roots x = a^-1 (±t - b) (mod p), and 2 B_l a^-1 (mod p)
+        // to move them when B_l flips sign.
+        let in_a: Vec<bool> = (0..base.len()).map(|j| a_primes.contains(&j)).collect();
+        let mut roots = vec![(0u64, 0u64); base.len()];
+        let mut shifts = vec![vec![0u64; base.len()]; s];
+        for (j, prime) in base.iter().enumerate().skip(1) {
+            if in_a[j] { continue; }
+            let p = prime.p;
+            let ainv = inv_mod((a % p as u128) as u64, p);
+            let bp = b.rem_euclid(p as i128) as u64;
+            roots[j] = (ainv * ((prime.t + p - bp) % p) % p, ainv * ((2 * p - prime.t - bp) % p) % p);
+            for l in 0..s {
+                shifts[l][j] = 2 * (bs[l].rem_euclid(p as i128) as u64) * ainv % p;
+            }
+        }
+
+        for poly in 0..1usize << (s - 1) {
+            if poly > 0 {
+                // Gray code: flip the sign of B_v, where 2^v is the lowest bit of poly.
+                let v = poly.trailing_zeros() as usize;
+                signs[v] = -signs[v];
+                b += 2 * signs[v] * bs[v];
+                for j in 1..base.len() {
+                    if in_a[j] { continue; }
+                    let p = base[j].p;
+                    let shift = if signs[v] > 0 { p - shifts[v][j] } else { shifts[v][j] };
+                    roots[j] = ((roots[j].0 + shift) % p, (roots[j].1 + shift) % p);
+                }
+            }
+            let c = (b * b - nn as i128) / a as i128;
+
+            // Add log2(p) everywhere p divides Q(x), i.e. at x = root (mod p). Index i stands for
+            // x = i - M.
+            sieve_array.fill(0);
+            for j in 1..base.len() {
+                if in_a[j] { continue; }
+                let FactorBasePrime { p, log, .. } = base[j];
+                for root in [roots[j].0, roots[j].1] {
+                    let start = ((root + half as u64 % p) % p) as usize;
+                    for i in (start..sieve_array.len()).step_by(p as usize) { sieve_array[i] += log; }
+                }
+            }
+
+            for (i, _) in sieve_array.iter().enumerate().filter(|(_, &v)| v >= threshold) {
+                let x = i as i128 - half as i128;
+                let q = (a as i128 * x + 2 * b) * x + c;
+                if q == 0 { continue; }
+                let mut rest = q.unsigned_abs();
+                let mut exponents: Vec<(usize, u32)> = if q < 0 { vec![(0, 1)] } else { Vec::new() };
+                for (j, prime) in base.iter().enumerate() {
+                    let p = prime.p as u128;
+                    let xp = x.rem_euclid(p as i128) as u64;
+                    if j > 0 && !in_a[j] && xp != roots[j].0 && xp != roots[j].1 { continue; }
+                    // (ax + b)^2 = a Q(x), so every prime of a counts once more on the right.
+                    let mut e = in_a[j] as u32;
+                    while rest.is_multiple_of(p) {
+                        rest /= p;
+                        e += 1;
+                    }
+                    if e > 0 { exponents.push((j + 1, e)); }
+                }
+                if rest == 1 { relations.push(Relation { root: (a as i128 * x + b).unsigned_abs(), exponents }); }
+            }
+
+            if relations.len() >= wanted {
+                if let Some(d) = combine(n, &base, &relations, columns) { return Some(d); }
+                wanted = relations.len() + 10;
+            }
+        }
+    }
+}
+
+/// A prime in the factor base: p, a square root t of N mod p, and log2(p) rounded for the sieve.
+#[derive(Clone, Copy, Debug)]
+struct FactorBasePrime {
+    p: u64,
+    t: u64,
+    log: u8,
+}
+
+/// root^2 = the product of the factor base primes in exponents (mod N). Column 0 is the sign and
+/// column j + 1 is base[j].
+#[derive(Clone, Debug)]
+struct Relation {
+    root: u128,
+    exponents: Vec<(usize, u32)>,
+}
+
+/// Finds the subsets of relations whose exponents all add up to even numbers, and tries each
+/// until one gives a factor.
+fn combine(n: &BigUint, base: &[FactorBasePrime], relations: &[Relation], columns: usize) -> Option<BigUint> {
+    let odd: Vec<Vec<usize>> = relations.iter().map(|r| r.exponents.iter().filter(|(_, e)| e % 2 == 1).map(|&(j, _)| j).collect()).collect();
+    for subset in dependencies(&odd, columns) {
+        let mut x = BigUint::one();
+        let mut total = vec![0u32; columns];
+        for &r in &subset {
+            x = x * relations[r].root % n;
+            for &(j, e) in &relations[r].exponents { total[j] += e; }
+        }
+        // The sign column is even too, so the product is a square: Y is its square root.
+        let y = total.iter().enumerate().skip(1).fold(BigUint::one(), |y, (j, &e)| {
+            y * mod_exp(&BigUint::from(base[j - 1].p), &BigUint::from(e / 2), n) % n
+        });
+        let g = gcd(&abs_diff(&x, &y), n);
+        if !g.is_one() && g != *n { return Some(g); }
+    }
+    None
+}
+
+/// Gaussian elimination over GF(2). Every row is a relation with a 1 in each column where its
+/// exponent is odd, and each row also remembers which original rows were added into it. Rows that
+/// end up all zero are the dependencies.
+fn dependencies(rows: &[Vec<usize>], columns: usize) -> Vec<Vec<usize>> {
+    let (width, history_width) = (columns.div_ceil(64), rows.len().div_ceil(64));
+    let mut matrix: Vec<(Vec<u64>, Vec<u64>)> = rows.iter().enumerate().map(|(r, row)| {
+        let mut bits = vec![0u64; width];
+        for &j in row { bits[j / 64] |= 1 << (j % 64); }
+        let mut history = vec![0u64; history_width];
+        history[r / 64] |= 1 << (r % 64);
+        (bits, history)
+    }).collect();
+    let mut pivoted = vec![false; rows.len()];
+    for column in 0..columns {
+        let bit = |bits: &[u64]| bits[column / 64] >> (column % 64) & 1 == 1;
+        let Some(pivot) = (0..rows.len()).find(|&r| !pivoted[r] && bit(&matrix[r].0)) else { continue };
+        pivoted[pivot] = true;
+        let (bits, history) = matrix[pivot].clone();
+        for (r, row) in matrix.iter_mut().enumerate() {
+            if r == pivot || !bit(&row.0) { continue; }
+            row.0.iter_mut().zip(&bits).for_each(|(a, b)| *a ^= b);
+            row.1.iter_mut().zip(&history).for_each(|(a, b)| *a ^= b);
+        }
+    }
+    matrix.iter().zip(&pivoted).filter(|(_, &p)| !p)
+        .map(|((_, history), _)| (0..rows.len()).filter(|&r| history[r / 64] >> (r % 64) & 1 == 1).collect())
+        .collect()
+}
+
+/// Factor base size and sieve half-width M for an n of this many bits.
+fn qs_parameters(bits: u64) -> (usize, i64) {
+    match bits {
+        0..=50 => (40, 1 << 12),
+        51..=70 => (80, 1 << 13),
+        71..=90 => (160, 1 << 14),
+        91..=105 => (260, 1 << 15),
+        _ => (400, 1 << 15),
+    }
+}
+
+/// Splits a composite with no prime factors below TRIAL_DIVISION_BOUND.
+fn split(n: &BigUint) -> BigUint {
+    if let Some(root) = perfect_power(n) { return root; }
+    if n.bits() <= 50 {
+        return pollard_rho(n, u64::MAX).expect("rho always splits a composite eventually");
+    }
+    if let Some(d) = pollard_rho(n, 1 << 16) { return d; }
+    if let Some(d) = pollard_p_minus_1(n, 20_000) { return d; }
+    if let Some(d) = quadratic_sieve(n) { return d; }
+    let mut bound = 2000;
+    loop {
+        if let Some(d) = ecm(n, 50, bound) { return d; }
+        bound *= 4;
+    }
+}
+
+/// r if n = r^k for some k >= 2, picking the smallest r.
+fn perfect_power(n: &BigUint) -> Option<BigUint> {
+    (2..=n.bits() as u32).rev().map(|k| (k, n.nth_root(k))).find(|(k, r)| !r.is_one() && r.pow(*k) == *n).map(|(_, r)| r)
+}
+
+/// Pollard's p - 1: for every prime l up to B raise a to the largest power of l that is still at
+/// most B. If some prime r of N has r - 1 built only out of those prime powers, a^(r - 1) = 1
+/// (mod r), and gcd(a - 1, N) picks r out.
+///
+/// If every prime of N is smooth at once the gcd is N itself, so we check after each batch of
+/// primes and redo a batch one prime at a time if it overshoots.
+pub(crate) fn p_minus_1(n: &BigUint, primes: &[u64], bound: u64) -> Option<BigUint> {
+    let mut a = BigUint::from(2u64);
+    for batch in primes.chunks(64) {
+        let exponent: BigUint = batch.iter().map(|&l| BigUint::from(prime_power(l, bound))).product();
+        let next = mod_exp(&a, &exponent, n);
+        let g = gcd(&(&next + n - 1u64), n);
+        if g.is_one() {
+            a = next;
+            continue;
+        }
+        if g != *n { return Some(g); }
+        // Overshot. Go through the batch again one prime at a time.
+        for &l in batch {
+            a = mod_exp(&a, &BigUint::from(prime_power(l, bound)), n);
+            let g = gcd(&(&a + n - 1u64), n);
+            if g == *n { return None; }
+            if !g.is_one() { return Some(g); }
+        }
+    }
+    None
+}
+
+/// The largest power of l that is at most bound.
+fn prime_power(l: u64, bound: u64) -> u64 {
+    let mut power = l;
+    while power <= bound / l { power *= l; }
+    power
+}
+
+/// A point on y^2 = x^3 + ax + b over Z/NZ, the same affine arithmetic as the secp256k1
+/// AffinePoint. N isn't prime, so some denominators have no inverse, and that is exactly what
+/// ECM is waiting for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct AffinePoint {
+    x: BigUint,
+    y: BigUint,
+    infinity: bool,
+}
+
+/// The curve parameter a and the modulus. b never shows up in the addition formulas.
+struct Curve<'a> {
+    a: BigUint,
+    n: &'a BigUint,
+}
+
+impl AffinePoint {
+
+    fn new(x: BigUint, y: BigUint) -> Self {
+        Self { x, y, infinity: false }
+    }
+
+    fn infinity() -> Self {
+        Self { x: BigUint::zero(), y: BigUint::zero(), infinity: true }
+    }
+
+    /// P + Q with the usual chord (or tangent) rule: λ = (y2 - y1) / (x2 - x1), or
+    /// (3 x1^2 + a) / 2 y1 for P = Q, then x3 = λ^2 - x1 - x2 and y3 = λ (x1 - x3) - y1.
+    ///
+    /// Output: Ok(P + Q), or Err(gcd(denominator, N)) if the denominator has no inverse mod N.
+    fn add(&self, other: &AffinePoint, curve: &Curve) -> Result<AffinePoint, BigUint> {
+        if self.infinity { return Ok(other.clone()); }
+        if other.infinity { return Ok(self.clone()); }
+        let n = curve.n;
+        let (dy, dx) = if self.x == other.x {
+            if ((&self.y + &other.y) % n).is_zero() { return Ok(AffinePoint::infinity()); }
+            ((&self.x * &self.x * 3u64 + &curve.a) % n, (&self.y << 1) % n)
+        } else {
+            ((&other.y + n - &self.y) % n, (&other.x + n - &self.x) % n)
+        };
+        let inv = mod_inverse(&dx, n).ok_or_else(|| gcd(&dx, n))?;
+        let lambda = dy * inv % n;
+        let x3 = (&lambda * &lambda + (n << 1) - &self.x - &other.x) % n;
+        let y3 = (lambda * ((&self.x + n - &x3) % n) + n - &self.y) % n;
+        Ok(AffinePoint::new(x3, y3))
+    }
+
+    /// kP by double-and-add, stopping at the first denominator that can't be inverted.
+    fn mul(&self, k: &BigUint, curve: &Curve) -> Result<AffinePoint, BigUint> {
+        let mut result = AffinePoint::infinity();
+        let mut multiplier = self.clone();
+        for i in 0..k.bits() {
+            if k.bit(i) { result = result.add(&multiplier, curve)?; }
+            if i + 1 < k.bits() { multiplier = multiplier.add(&multiplier, curve)?; }
+        }
+        Ok(result)
+    }
+}
+
+/// SplitMix64, a tiny deterministic generator for picking curves and polynomials. None of it has
+/// to be unpredictable, only different from one call to the next.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// |a - b|.
+fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint {
+    if a >= b { a - b } else { b - a }
+}
+
+/// b^e (mod p) for a p below 2^32.
+fn pow_mod(b: u64, e: u64, p: u64) -> u64 {
+    let (mut result, mut b, mut e) = (1, b % p, e);
+    while e > 0 {
+        if e & 1 == 1 { result = result * b % p; }
+        b = b * b % p;
+        e >>= 1;
+    }
+    result
+}
+
+/// a^-1 (mod p) for a prime p below 2^32, by Fermat's little theorem.
+fn inv_mod(a: u64, p: u64) -> u64 {
+    pow_mod(a, p - 2, p)
+}
@@ -0,0 +1,80 @@
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Hash functions that can be plugged into the padding schemes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlg {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlg {
+
+    /// Output size of the hash in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            HashAlg::Sha256 => 32,
+            HashAlg::Sha384 => 48,
+            HashAlg::Sha512 => 64,
+        }
+    }
+
+    /// DER encoding of the DigestInfo header (algorithm identifier plus the OCTET STRING tag and
+    /// length) that goes in front of the hash in PKCS#1 v1.5 signatures (RFC 8017, section 9.2).
+    pub fn digest_info_prefix(&self) -> &'static [u8] {
+        match self {
+            HashAlg::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+            HashAlg::Sha384 => &[
+                0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x02, 0x05, 0x00, 0x04, 0x30,
+            ],
+            HashAlg::Sha512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x03, 0x05, 0x00, 0x04, 0x40,
+            ],
+        }
+    }
+
+    /// Hashes the concatenation of all the given byte strings.
+    pub fn digest(&self, parts: &[&[u8]]) -> Vec<u8> {
+        match self {
+            HashAlg::Sha256 => {
+                let mut h = Sha256::new();
+                parts.iter().for_each(|p| h.update(p));
+                h.finalize().to_vec()
+            }
+            HashAlg::Sha384 => {
+                let mut h = Sha384::new();
+                parts.iter().for_each(|p| h.update(p));
+                h.finalize().to_vec()
+            }
+            HashAlg::Sha512 => {
+                let mut h = Sha512::new();
+                parts.iter().for_each(|p| h.update(p));
+                h.finalize().to_vec()
+            }
+        }
+    }
+}
+
+/// MGF1 from RFC 8017 (appendix B.2.1) stretches a seed into a mask of any length by hashing the
+/// seed together with a 4-byte counter and gluing the outputs together.
+///
+/// Input: hash function (hash) -> hash to build the mask from.
+/// Input: seed (seed) -> seed to stretch.
+/// Input: mask length (len) -> number of bytes of mask to produce.
+///
+/// Output: mask -> "len" bytes of mask.
+pub fn mgf1(hash: HashAlg, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + hash.size());
+    let mut counter = 0u32;
+    while mask.len() < len {
+        mask.extend(hash.digest(&[seed, &counter.to_be_bytes()]));
+        counter += 1;
+    }
+    mask.truncate(len);
+    mask
+}
@@ -0,0 +1,96 @@
+use crate::error::RsaError;
+use crate::hash::HashAlg;
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, os2ip, random_range};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use num_bigint::BigUint;
+use rand::{CryptoRng, RngCore};
+
+/// First byte of everything seal produces. Anything that changes the layout, the KDF or the AEAD
+/// gets a new version so old ciphertexts never get read the wrong way.
+pub const KEM_VERSION: u8 = 0x01;
+
+/// Size of the AES-GCM tag at the end of every sealed message.
+const TAG_LEN: usize = 16;
+
+/// Encrypts any amount of data to an RSA public key with RSA-KEM (ISO 18033-2). Textbook RSA can
+/// only carry a single number smaller than N, so instead we pick a uniformly random r in [0, N),
+/// send r^e (mod N), and turn r into an AES-256-GCM key with KDF2 (SHA-256). The data itself is
+/// then encrypted with AES-GCM. Since r is completely random there is no padding to get wrong and
+/// nothing for a padding oracle to work with.
+///
+/// The wire format (version 1) is:
+///
+/// 0x01 || C0 || AES-256-GCM(data) || tag
+///
+/// where C0 = I2OSP(r^e mod N, k) is exactly k bytes (the size of N) and the tag is 16 bytes. The
+/// AES key is never used twice, so the nonce is all zeros. The version byte is authenticated
+/// along with the associated data.
+///
+/// Input: public key (key) -> recipient's public key.
+/// Input: plaintext (plaintext) -> bytes to encrypt, of any length.
+/// Input: associated data (aad) -> bytes that aren't encrypted but have to be handed to open
+/// unchanged (a file name, a header, and so on). Can be empty.
+/// Input: random number generator (rng) -> source of r.
+///
+/// Output: sealed message -> 1 + k + plaintext.len() + 16 bytes.
+pub fn seal<R: CryptoRng + RngCore>(key: &RsaPublicKey, plaintext: &[u8], aad: &[u8], rng: &mut R) -> Vec<u8> {
+    let k = key.size();
+    let r = random_range(rng, &BigUint::from(0u64), &(key.n() - 1u64));
+    let z = i2osp(&r, k).expect("r is smaller than N");
+    let c0 = i2osp(&key.public_exp(&r), k).expect("c0 is smaller than N");
+
+    let mut sealed = Vec::with_capacity(1 + k + plaintext.len() + TAG_LEN);
+    sealed.push(KEM_VERSION);
+    sealed.extend(c0);
+    let ct = cipher(&z).encrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg: plaintext, aad: &header(aad) })
+        .expect("AES-GCM can encrypt anything this size");
+    sealed.extend(ct);
+    sealed
+}
+
+/// Decrypts a message made by seal. Any problem at all (wrong version, wrong size, C0 out of
+/// range, a wrong key, modified ciphertext or different associated data) comes back as the same
+/// RsaError::Decryption.
+///
+/// Input: private key (key) -> recipient's private key.
+/// Input: sealed message (ciphertext) -> output of seal.
+/// Input: associated data (aad) -> exactly what was passed to seal.
+/// Input: random number generator (rng) -> source of the blinding factor.
+///
+/// Output: plaintext -> the decrypted bytes, or RsaError::Decryption.
+pub fn open<R: CryptoRng + RngCore>(key: &RsaPrivateKey, ciphertext: &[u8], aad: &[u8], rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let k = key.size();
+    if ciphertext.len() < 1 + k + TAG_LEN || ciphertext[0] != KEM_VERSION { return Err(RsaError::Decryption); }
+    let (c0, ct) = ciphertext[1..].split_at(k);
+    let c0 = os2ip(c0);
+    if c0 >= *key.n() { return Err(RsaError::Decryption); }
+    let z = i2osp(&key.private_exp(&c0, rng), k).ok_or(RsaError::Decryption)?;
+
+    cipher(&z).decrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg: ct, aad: &header(aad) })
+        .map_err(|_| RsaError::Decryption)
+}
+
+/// AES-256-GCM keyed with KDF2(Z).
+pub fn cipher(z: &[u8]) -> Aes256Gcm {
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&kdf2(HashAlg::Sha256, z, 32)))
+}
+
+/// What the AEAD authenticates: the version byte followed by the caller's associated data.
+fn header(aad: &[u8]) -> Vec<u8> {
+    [&[KEM_VERSION], aad].concat()
+}
+
+/// KDF2 from ISO 18033-2: the same thing as MGF1, except the counter starts at 1 instead of 0.
+fn kdf2(hash: HashAlg, z: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + hash.size());
+    let mut counter = 1u32;
+    while out.len() < len {
+        out.extend(hash.digest(&[z, &counter.to_be_bytes()]));
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
@@ -1,12 +1,41 @@
-use crate::math::mod_exp;
-use crate::euclidian::{gcd, modinv};
-use crate::flt::flt;
+pub use num_bigint::BigUint;
 
-use bigint::uint::U512;
+use rand::{CryptoRng, RngCore};
+
+pub use crate::accumulator::{hash_to_prime, Accumulator, AccumulatorSet, AccumulatorUpdate, NonMembershipWitness, ACCUMULATOR_PRIME_BITS};
+pub use crate::audit::{audit, audit_batch, audit_with, batch_gcd, AuditConfig, Weakness};
+pub use crate::cryptanalysis::{common_modulus_attack, hastad_broadcast, stereotyped_message_attack, wiener_attack, MAX_UNKNOWN_BITS};
+pub use crate::error::RsaError;
+pub use crate::factor::{ecm, factor, pollard_p_minus_1, pollard_rho, quadratic_sieve, QS_MAX_BITS};
+pub use crate::hash::HashAlg;
+pub use crate::kem::{open, seal, KEM_VERSION};
+pub use crate::key::{OtherPrimeInfo, RsaPrivateKey, RsaPublicKey, DEFAULT_EXPONENT};
+pub use crate::oaep::{rsa_decrypt_oaep, rsa_encrypt_oaep};
+pub use crate::paillier::{PaillierPrivateKey, PaillierPublicKey};
+pub use crate::pkcs1v15::{rsa_decrypt_pkcs1v15, rsa_encrypt_pkcs1v15};
+pub use crate::timelock::{square_repeatedly, TimeLockPuzzle};
+pub use crate::vdf::{vdf_eval, vdf_eval_trapdoor, vdf_verify, VdfOutput};
+pub use crate::rabin::{sqrt_mod_prime, RabinPrivateKey, RabinPublicKey, RabinSignature, RABIN_TAG_LEN};
+pub use crate::prime::{is_prime, Primality};
 
-mod flt;
 mod math;
+mod accumulator;
+mod audit;
+mod cryptanalysis;
+mod der;
 mod euclidian;
+mod error;
+mod factor;
+mod hash;
+mod kem;
+mod key;
+mod oaep;
+mod paillier;
+mod pkcs1v15;
+mod prime;
+mod rabin;
+mod timelock;
+mod vdf;
 
 /// Deriving a rsa public key requires the user to have two large prime numbers and an exponent.
 /// Note that the exponent doesn't necessarily have to be large, but the larger it is, the more
@@ -16,13 +45,28 @@ mod euclidian;
 /// Input: secret prime #2 (p) -> second secret key for RSA encryption.
 /// Input: public exponent (e) -> exponent to use as the second part of the public key.
 ///
-/// Output: public keys (N, e) -> N, which is the finite field modulo, and e, the exponent used to
+/// Output: public key (N, e) -> N, which is the finite field modulo, and e, the exponent used to
 /// encrpt data to a user.
-pub fn rsa_derive_pubkey(q: U512, p: U512, e: U512) -> (U512, U512) {
-    assert!(gcd(e, (q - U512::one()) * (p - U512::one())) == U512::one(), "chosen exponent is not coprime with p-1 * q-1");
-    assert!(flt(q));
-    assert!(flt(p));
-    ((p * q), e)
+pub fn rsa_derive_pubkey(q: BigUint, p: BigUint, e: BigUint) -> RsaPublicKey {
+    match RsaPrivateKey::from_primes(p, q, e) {
+        Ok(key) => key.public_key(),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+/// Same as rsa_derive_pubkey, but for a multi-prime key: N is the product of every prime in the
+/// list instead of just two of them. Nothing changes for whoever encrypts (or verifies); they only
+/// ever see N and e.
+///
+/// Input: secret primes (primes) -> two or more distinct primes.
+/// Input: public exponent (e) -> exponent to use as the second part of the public key.
+///
+/// Output: public key (N, e).
+pub fn rsa_derive_multi_prime_pubkey(primes: Vec<BigUint>, e: BigUint) -> RsaPublicKey {
+    match RsaPrivateKey::from_prime_factors(primes, e) {
+        Ok(key) => key.public_key(),
+        Err(err) => panic!("{}", err),
+    }
 }
 
 /// Encrypting a message in RSA is as simple as raising the plaintext to the power of the public
@@ -30,45 +74,42 @@ pub fn rsa_derive_pubkey(q: U512, p: U512, e: U512) -> (U512, U512) {
 ///
 /// Input: message (m) -> ascii encoded plaintext message to send to the recipient/holder of the
 /// private keys.
-/// Input: public key #1 (p1) -> recipient's field modulo (N).
-/// Input: public key #2 (p2) -> recipient's public exponent (e).
+/// Input: public key (key) -> recipient's public key (N, e).
 ///
 /// Output: ciphertext (c) -> the RSA encrypted ciphertext to send to the owner of the respective
 /// private keys.
-pub fn rsa_encrypt(m: U512, p1: U512, p2: U512) -> U512 {
-    mod_exp(m, p2, p1)
+pub fn rsa_encrypt(m: &BigUint, key: &RsaPublicKey) -> BigUint {
+    key.public_exp(m)
 }
 
-/// Decrypting an RSA message requires us to solve two equations: de = 1 (mod (p-1)(q-1)) for d,
-/// and m = c^d (mod pq) for m. Of course, this is where it matters to have strong private keys.
-/// If a user could ever reverse engineer any value, whether pq, (p-1)(q-1), etc., they can crack
-/// your secret keys. RSA should be thought of as deprecated with the now standard use of ECC.
+/// Decrypting an RSA message means computing m = c^d (mod pq), where de = 1 (mod λ(N)). Of course,
+/// this is where it matters to have strong private keys. If a user could ever reverse engineer
+/// any value, whether pq, (p-1)(q-1), etc., they can crack your secret keys. RSA should be thought
+/// of as deprecated with the now standard use of ECC.
+///
+/// d and the CRT parameters are worked out once when the private key is built, so all that is
+/// left to do here is one small exponentiation per prime (two half-size ones for an ordinary key,
+/// three third-size ones for a three-prime key, and so on). The ciphertext is blinded with a random
+/// factor first (see RsaPrivateKey::private_exp), so the rng is needed even though decryption is
+/// deterministic.
 ///
-/// Note: The order of private keys inputed does not matter.
 /// Input: ciphertext (c) -> ciphertext to decrypt.
-/// Input: private key #1 (q) -> recipent's private key.
-/// Input: private key #2 (p) -> recipient's second private key.
-/// Input: exponent (e) -> public exponent used for encryption.
+/// Input: private key (key) -> recipient's private key.
+/// Input: random number generator (rng) -> source of the blinding factor.
 ///
 /// Output: message (m) -> plaintext message sent to the owner of the secret keys.
-pub fn rsa_decrypt(c: U512, q: U512, p: U512, e: U512) -> U512 {
-    assert!(flt(q));
-    assert!(flt(p));
-    let pub_key = p * q;
-    let sec_exp = (q - U512::one()) * (p - U512::one());
-    let dec = modinv(e, sec_exp);
-    //let dec = mod_exp(e, sec_exp - U512::from(2), sec_exp);
-    mod_exp(c, dec, pub_key)
+pub fn rsa_decrypt<R: CryptoRng + RngCore>(c: &BigUint, key: &RsaPrivateKey, rng: &mut R) -> BigUint {
+    key.private_exp(c, rng)
 }
 
 /// RSA test basically combines all the functions for encrypting your own data and tests to make
 /// sure that the RSA algorithm works as intended. It is meant to be used exclusively for testing
 /// (which is why it gets a random number inputed).
-pub fn rsa_test(p: U512, q: U512, e: U512, m: U512) -> bool {
-    #[allow(non_snake_case)]
-    let (N, e) = rsa_derive_pubkey(q, p, e);
-    let c = rsa_encrypt(m, N, e);
-    let m2 = rsa_decrypt(c, q, p, e);
+pub fn rsa_test(p: BigUint, q: BigUint, e: BigUint, m: BigUint) -> bool {
+    let key = RsaPrivateKey::from_primes(p.clone(), q.clone(), e.clone()).unwrap();
+    let public = rsa_derive_pubkey(q, p, e);
+    let c = rsa_encrypt(&m, &public);
+    let m2 = rsa_decrypt(&c, &key, &mut rand::thread_rng());
     eprintln!("{:?}", m);
     eprintln!("{:?}", m2);
     assert!(m2 == m);
@@ -79,40 +120,991 @@ pub fn rsa_test(p: U512, q: U512, e: U512, m: U512) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::euclidian::{binary_gcd, gcd, mod_inverse, xgcd};
+    use num_bigint::BigInt;
+    use crate::math::{mod_exp, mod_exp_ct, os2ip, random_bits};
+    use crate::prime::baillie_psw;
+    use num_traits::{One, Zero};
     use rand::RngCore;
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+    #[test]
+    fn primality() {
+        let mut rng = rand::thread_rng();
+        let mut check = |n: u64| {
+            let mr = is_prime(&BigUint::from(n), Primality::MillerRabin, &mut rng);
+            let bpsw = is_prime(&BigUint::from(n), Primality::BailliePsw, &mut rng);
+            assert_eq!(mr, bpsw, "tests disagree on {}", n);
+            mr
+        };
+        assert!(check(10093));
+        assert!(check(10099));
+        assert!(check(10103));
+        assert!(check(10111));
+        assert!(check(10133));
+        assert!(check(10139));
+        assert!(check(10141));
+        assert!(check(10151));
+        assert!(check(10159));
+        assert!(check(10163));
+        assert!(check(10169));
+        assert!(!check(10102));
+        assert!(!check(10143));
+        assert!(!check(10165));
+        assert!(!check(10137));
+        assert!(!check(10199));
+        assert!(check(2) && check(3) && check(5) && check(251) && check(257));
+        assert!(!check(0) && !check(1) && !check(4) && !check(9) && !check(65536));
+
+        // Mersenne primes 2^61 - 1, 2^89 - 1, 2^127 - 1, 2^521 - 1 and 2^1279 - 1, and the
+        // composites around them.
+        for e in [61, 89, 127, 521, 1279] {
+            let m = (BigUint::one() << e) - 1u64;
+            assert!(prime::baillie_psw(&m));
+            assert!(prime::is_probable_prime(&m, &mut rng));
+            assert!(!prime::baillie_psw(&(&m - 2u64)));
+            assert!(!prime::baillie_psw(&(&m * 1000003u64)));
+        }
+        // Product of two 128-bit primes.
+        let p = (BigUint::one() << 127) - 1u64;
+        let q = big("340282366920938463463374607431768211297");
+        assert!(prime::baillie_psw(&q));
+        assert!(!prime::baillie_psw(&(&p * &q)));
+        assert!(!prime::is_probable_prime(&(&p * &q), &mut rng));
+    }
+    #[test]
+    fn carmichael_numbers() {
+        // Carmichael numbers fool the Fermat test for every base coprime to them. The ones in
+        // the second list have no factor below 10, so they even passed the old flt check.
+        let carmichael: [u64; 19] = [
+            561, 1105, 1729, 2465, 2821, 6601, 8911, 10585, 15841, 29341, 41041, 46657, 52633,
+            62745, 63973, 75361, 101101, 115921, 126217,
+        ];
+        let fool_flt: [u64; 10] = [162401, 252601, 294409, 314821, 334153, 340561, 399001, 410041, 488881, 512461];
+        let mut rng = rand::thread_rng();
+        for &n in carmichael.iter().chain(fool_flt.iter()) {
+            let n = BigUint::from(n);
+            assert!(!is_prime(&n, Primality::MillerRabin, &mut rng), "{} passed Miller-Rabin", n);
+            assert!(!is_prime(&n, Primality::BailliePsw, &mut rng), "{} passed Baillie-PSW", n);
+        }
+        for &n in fool_flt.iter() {
+            for a in 2..10u64 {
+                assert!(mod_exp(&BigUint::from(a), &BigUint::from(n - 1), &BigUint::from(n)).is_one());
+            }
+        }
+    }
+    #[test]
+    fn strong_pseudoprimes() {
+        let mut rng = rand::thread_rng();
+        // Strong pseudoprimes to base 2: they pass a single Miller-Rabin round with a = 2, so
+        // Baillie-PSW has to catch them in the Lucas half.
+        let base_2: [u64; 16] = [
+            2047, 3277, 4033, 4681, 8321, 15841, 29341, 42799, 49141, 52633, 65281, 74665, 80581,
+            85489, 88357, 90751,
+        ];
+        for &n in base_2.iter() {
+            assert!(!prime::baillie_psw(&BigUint::from(n)), "{} passed Baillie-PSW", n);
+            assert!(!prime::is_probable_prime(&BigUint::from(n), &mut rng), "{} passed Miller-Rabin", n);
+        }
+        // 3215031751 is a strong pseudoprime to bases 2, 3, 5 and 7, and 3825123056546413051 to
+        // every prime base up to 23.
+        for n in ["3215031751", "3825123056546413051"] {
+            let n = big(n);
+            assert!(!prime::baillie_psw(&n));
+            assert!(!prime::is_probable_prime(&n, &mut rng));
+        }
+        // Strong Lucas pseudoprimes (Selfridge parameters) fool the Lucas half, so they have to
+        // fail the base 2 half instead.
+        let lucas: [u64; 10] = [5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519];
+        for &n in lucas.iter() {
+            assert!(prime::strong_lucas_probable_prime(&BigUint::from(n)));
+            assert!(!prime::baillie_psw(&BigUint::from(n)), "{} passed Baillie-PSW", n);
+            assert!(!prime::is_probable_prime(&BigUint::from(n), &mut rng), "{} passed Miller-Rabin", n);
+        }
+    }
     #[test]
-    fn fermats_little_theorem() {
-        assert!(flt(U512::from(10093)));
-        assert!(flt(U512::from(10099)));
-        assert!(flt(U512::from(10103)));
-        assert!(flt(U512::from(10111)));
-        assert!(flt(U512::from(10133)));
-        assert!(flt(U512::from(10139)));
-        assert!(flt(U512::from(10141)));
-        assert!(flt(U512::from(10151)));
-        assert!(flt(U512::from(10159)));
-        assert!(flt(U512::from(10163)));
-        assert!(flt(U512::from(10169)));
-        assert!(!flt(U512::from(10102)));
-        assert!(!flt(U512::from(10143)));
-        assert!(!flt(U512::from(10165)));
-        assert!(!flt(U512::from(10137)));
-        assert!(!flt(U512::from(10199)));
+    fn jacobi_symbols() {
+        // (a/p) for a prime p agrees with Euler's criterion a^((p-1)/2).
+        let p = BigUint::from(10169u64);
+        for a in 1..200u64 {
+            let euler = mod_exp(&BigUint::from(a), &((&p - 1u64) >> 1), &p);
+            let expected = if euler.is_one() { 1 } else { -1 };
+            assert_eq!(prime::jacobi(&BigUint::from(a), &p), expected);
+        }
+        assert_eq!(prime::jacobi(&BigUint::from(1001u64), &BigUint::from(9907u64)), -1);
+        assert_eq!(prime::jacobi(&BigUint::from(19u64), &BigUint::from(45u64)), 1);
+        assert_eq!(prime::jacobi(&BigUint::from(8u64), &BigUint::from(21u64)), -1);
+        assert_eq!(prime::jacobi(&BigUint::from(5u64), &BigUint::from(21u64)), 1);
+        assert_eq!(prime::jacobi(&BigUint::from(15u64), &BigUint::from(45u64)), 0);
+    }
+    #[test]
+    fn extended_gcds() {
+        let a = big("836924423851314686957903970262947033592661064025187511582267500056692617427");
+        let b = big("60772039568148124987496788008570400867100447637981291630550967949578841");
+        let pairs = [(2024u64, 748u64), (748, 2024), (7834, 48), (40183, 45791821), (12, 0), (0, 12), (0, 0)];
+        let pairs = pairs.iter().map(|&(a, b)| (BigUint::from(a), BigUint::from(b))).chain([(a.clone(), b.clone())]);
+        for (a, b) in pairs {
+            let (g, x, y) = xgcd(&a, &b);
+            assert_eq!(g, gcd(&a, &b));
+            assert_eq!(g, binary_gcd(&a, &b));
+            assert_eq!(BigInt::from(a) * x + BigInt::from(b) * y, BigInt::from(g));
+        }
+        assert_eq!(xgcd(&a, &b).0, BigUint::from(3u64));
+
+        let m = (BigUint::one() << 255) - 19u64;
+        let x = big("21582102299826525449504797940759260576379968499998982132066757867315193878103");
+        let inv = big("17072501176736913924375703095939437871033759977920439439095577500683350405846");
+        assert_eq!(mod_inverse(&x, &m), Some(inv.clone()));
+        assert_eq!(mod_inverse(&(&x + &m), &m), Some(inv));
+        assert_eq!(mod_inverse(&BigUint::from(3u64), &BigUint::from(7u64)), Some(BigUint::from(5u64)));
+        assert_eq!(mod_inverse(&BigUint::from(5u64), &BigUint::one()), Some(BigUint::zero()));
+        assert_eq!(mod_inverse(&BigUint::zero(), &m), None);
+        assert_eq!(mod_inverse(&m, &m), None);
+        assert_eq!(mod_inverse(&BigUint::from(6u64), &BigUint::from(15u64)), None);
+        assert_eq!(mod_inverse(&BigUint::from(6u64), &BigUint::zero()), None);
     }
     #[test]
     fn rsa_encryption() {
+        let mut rng = rand::thread_rng();
         #[allow(non_snake_case)]
-        let (N, e) = rsa_derive_pubkey(U512::from(1223u64), U512::from(1987u64), U512::from(948047u64));
-        let c = rsa_encrypt(U512::from(1070777u64), N, e);
-        let m = rsa_decrypt(c, U512::from(1223u64), U512::from(1987u64), e);
-        assert_eq!(m, U512::from(1070777u64));
-        assert!(rsa_test(U512::from(1223u64), U512::from(1987u64), U512::from(948047u64), U512::from(1070777u64)));
-        assert!(rsa_test(U512::from(1223u64), U512::from(1987u64), U512::from(9129874561u64), U512::from(rand::thread_rng().next_u64() % 1000000)));
-        assert!(rsa_test(U512::from(1000193u64), U512::from(1000199u64), U512::from(246258617u64), U512::from(rand::thread_rng().next_u64() % 100000000)));
-        assert!(rsa_test(U512::from(1000211u64), U512::from(1000213u64), U512::from(83731u64), U512::from(rand::thread_rng().next_u64() % 213546712)));
-        assert!(rsa_test(U512::from(1000231u64), U512::from(1000249u64), U512::from(45641737u64), U512::from(rand::thread_rng().next_u64() % 123645321)));
-        assert!(rsa_test(U512::from(542000861u64), U512::from(542000867u64), U512::from(24727u64), U512::from(rand::thread_rng().next_u64() % 138765413)));
-        assert!(rsa_test(U512::from(542000929u64), U512::from(542000951u64), U512::from(948047u64), U512::from(rand::thread_rng().next_u64() % 1827635418)));
-        assert!(rsa_test(U512::from(542000929u64), U512::from(1987u64), U512::from(9480473u64), U512::from(rand::thread_rng().next_u64() % 27635418)));
+        let public = rsa_derive_pubkey(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64));
+        let key = RsaPrivateKey::from_primes(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64)).unwrap();
+        let c = rsa_encrypt(&BigUint::from(1070777u64), &public);
+        let m = rsa_decrypt(&c, &key, &mut rng);
+        assert_eq!(m, BigUint::from(1070777u64));
+        assert!(rsa_test(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64), BigUint::from(1070777u64)));
+        assert!(rsa_test(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(9129874561u64), BigUint::from(rand::thread_rng().next_u64() % 1000000)));
+        assert!(rsa_test(BigUint::from(1000193u64), BigUint::from(1000199u64), BigUint::from(246258617u64), BigUint::from(rand::thread_rng().next_u64() % 100000000)));
+        assert!(rsa_test(BigUint::from(1000211u64), BigUint::from(1000213u64), BigUint::from(83731u64), BigUint::from(rand::thread_rng().next_u64() % 213546712)));
+        assert!(rsa_test(BigUint::from(1000231u64), BigUint::from(1000249u64), BigUint::from(45641737u64), BigUint::from(rand::thread_rng().next_u64() % 123645321)));
+        assert!(rsa_test(BigUint::from(542000861u64), BigUint::from(542000867u64), BigUint::from(24727u64), BigUint::from(rand::thread_rng().next_u64() % 138765413)));
+        assert!(rsa_test(BigUint::from(542000929u64), BigUint::from(542000951u64), BigUint::from(948047u64), BigUint::from(rand::thread_rng().next_u64() % 1827635418)));
+        assert!(rsa_test(BigUint::from(542000929u64), BigUint::from(1987u64), BigUint::from(9480473u64), BigUint::from(rand::thread_rng().next_u64() % 27635418)));
+    }
+    #[test]
+    fn constant_time_exponentiation() {
+        let mut rng = rand::thread_rng();
+        // One limb, exactly at a limb boundary, and a few limbs longer than that.
+        let moduli = [
+            BigUint::from(3u64),
+            BigUint::from(u64::MAX),
+            (BigUint::one() << 64) + 1u64,
+            (BigUint::one() << 128) - 1u64,
+            random_bits(&mut rng, 1000) | BigUint::one(),
+            random_bits(&mut rng, 2048) | (BigUint::one() << 2047) | BigUint::one(),
+        ];
+        for f in moduli.iter() {
+            for _ in 0..5 {
+                let g = random_bits(&mut rng, f.bits() as usize + 10);
+                let len = 1 + (rng.next_u32() % 600) as usize;
+                let a = random_bits(&mut rng, len);
+                assert_eq!(mod_exp_ct(&g, &a, f), mod_exp(&g, &a, f));
+            }
+            let g = f - 1u64;
+            assert_eq!(mod_exp_ct(&g, &BigUint::from(2u64), f), BigUint::one());
+            assert_eq!(mod_exp_ct(&g, &BigUint::one(), f), g);
+            assert_eq!(mod_exp_ct(&g, &BigUint::zero(), f), BigUint::one());
+            assert!(mod_exp_ct(&BigUint::zero(), &BigUint::from(17u64), f).is_zero());
+        }
+        assert!(mod_exp_ct(&BigUint::from(5u64), &BigUint::from(3u64), &BigUint::one()).is_zero());
+    }
+    /// Wraps thread_rng and counts how many bytes get pulled out of it.
+    struct CountingRng(rand::rngs::ThreadRng, usize);
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 { self.1 += 4; self.0.next_u32() }
+        fn next_u64(&mut self) -> u64 { self.1 += 8; self.0.next_u64() }
+        fn fill_bytes(&mut self, dest: &mut [u8]) { self.1 += dest.len(); self.0.fill_bytes(dest) }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> { self.1 += dest.len(); self.0.try_fill_bytes(dest) }
+    }
+    impl rand::CryptoRng for CountingRng {}
+    #[test]
+    #[allow(non_snake_case)]
+    fn blinding() {
+        let mut rng = CountingRng(rand::thread_rng(), 0);
+        let mut key = RsaPrivateKey::generate(&mut rng, 512);
+        let N = key.n().clone();
+        assert!(key.blinding());
+
+        let x = random_bits(&mut rng, 500);
+        let expected = mod_exp(&x, key.d(), &N);
+        rng.1 = 0;
+        assert_eq!(key.private_exp(&x, &mut rng), expected);
+        assert!(rng.1 > 0, "blinding is on by default and needs randomness");
+        // The blinding factor is fresh every time, and the answer never changes.
+        for _ in 0..10 {
+            assert_eq!(key.private_exp(&x, &mut rng), expected);
+        }
+
+        key.set_blinding(false);
+        rng.1 = 0;
+        assert_eq!(key.private_exp(&x, &mut rng), expected);
+        assert_eq!(rng.1, 0);
+        assert!(key.private_exp(&BigUint::zero(), &mut rng).is_zero());
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn key_generation() {
+        let mut rng = rand::thread_rng();
+        for bits in [32, 64, 128, 200, 256, 512, 1024] {
+            let key = RsaPrivateKey::generate(&mut rng, bits);
+            let public = key.public_key();
+            let (N, e) = (public.n(), public.e());
+            assert_eq!(public.bits(), bits);
+            assert_eq!(*e, BigUint::from(DEFAULT_EXPONENT));
+            assert!(baillie_psw(key.p()));
+            assert!(baillie_psw(key.q()));
+            assert!(!key::too_close(key.p(), key.q(), bits));
+            assert!(gcd(e, &key::carmichael_lambda(&key.primes())).is_one());
+
+            let m = BigUint::from(rng.next_u64()) % N;
+            let c = rsa_encrypt(&m, &public);
+            assert_eq!(rsa_decrypt(&c, &key, &mut rng), m);
+            assert_eq!(mod_exp(&c, key.d(), N), m);
+        }
+        let key = RsaPrivateKey::generate_with_exponent(&mut rng, 128, BigUint::from(3u64));
+        assert_eq!(*key.e(), BigUint::from(3u64));
+        assert!(rsa_test(key.p().clone(), key.q().clone(), key.e().clone(), BigUint::from(rng.next_u64())));
+    }
+    #[test]
+    fn key_sizes() {
+        let mut rng = rand::thread_rng();
+        for bits in [2048, 3072, 4096] {
+            let key = RsaPrivateKey::generate(&mut rng, bits);
+            let public = key.public_key();
+            assert_eq!(public.bits(), bits);
+            assert_eq!(public.size(), bits / 8);
+            // FIPS 186-4 closeness bound: |p - q| > 2^(bits/2 - 100).
+            assert!(!key::too_close(key.p(), key.q(), bits));
+            let diff = if key.p() > key.q() { key.p() - key.q() } else { key.q() - key.p() };
+            assert!(diff.bits() as usize > bits / 2 - 100);
+
+            let m = math::random_bits(&mut rng, bits - 1);
+            assert_eq!(rsa_decrypt(&rsa_encrypt(&m, &public), &key, &mut rng), m);
+
+            let msg = vec![0x5au8; public.size() - 2 * HashAlg::Sha512.size() - 2];
+            let c = rsa_encrypt_oaep(&msg, &public, HashAlg::Sha512, Some(b"label"), &mut rng).unwrap();
+            assert_eq!(c.len(), public.size());
+            assert_eq!(rsa_decrypt_oaep(&c, &key, HashAlg::Sha512, Some(b"label"), &mut rng).unwrap(), msg);
+            assert_eq!(rsa_decrypt_oaep(&c, &key, HashAlg::Sha512, None, &mut rng), Err(RsaError::Decryption));
+
+            let c = rsa_encrypt_pkcs1v15(&msg, &public, &mut rng).unwrap();
+            assert_eq!(rsa_decrypt_pkcs1v15(&c, &key, &mut rng).unwrap(), msg);
+        }
+    }
+    #[test]
+    fn openssl_interop() {
+        // A 4096-bit key and two ciphertexts of "attack at dawn" made with OpenSSL (through
+        // python's cryptography package): OAEP with SHA-256 and the label "label", and PKCS#1 v1.5.
+        let p = BigUint::parse_bytes(b"dba60d409f6925ee98b6b942e381f45a5cdc1f15d17bcd7a91ed4bd699cf891a697a7862898f0a4bbfa347869ba82a8f86224891633d96c93cb00b45b041c69d4ba6ced95c1f555dbf465142ec85ef5253ec4004d767e954dc3679fa99912b4269ac47a873679184c5a0d9d8b5e91be6e156662c91eec58ac85ee007ea776ef957574fa951e5996d7b35eb5fb082f8a50e16a81183e45543bc0b41f47e43fa15a015beb5de9c2c4ca0ab5896d4f389339ddb0b0ab01b99f6c90c7dc2db1d6ca1c1591466a7217e6149bb2589f3960995371be13f487d009958a79a673718a5933c7e80cc0f2c42f1d9a7c3c13b055fad536ba1529cf79e95c00457d3378c85c1", 16).unwrap();
+        let q = BigUint::parse_bytes(b"c3aa17831d7490b91d6fb308cf4db18a02515178602bb774b941d30333eec266e52add92925af5d85899d174dcd2eed692e654740dc2def2449080af0a657d1717ccfcfc604266db5fd432bccbfde6e8aa356293ad8daf94ca98b0b1569e3c605378f91c4b8812de4f2a066f9bbef2d5f97bfa83f6e9b836fb0a464642db62d6613582dce2d7ffa6210d3154882b6bea6a3860e2daff85b0873b54f6a0e0d605906e1183e4fbbd904d2480e5dd7efbf1d0074a31d42183f0ff8652e57777d56b8ac295cd63b8fbef88af209630f9e6f98fb8bd3404a2d0c5594f666875b0ab007f7a3a3330ba8ee6ca5fc7afa865f6982b82b1f33cb61388994b2f6008217955", 16).unwrap();
+        let key = RsaPrivateKey::from_primes(p, q, BigUint::from(DEFAULT_EXPONENT)).unwrap();
+        assert_eq!(key.bits(), 4096);
+        let mut rng = rand::thread_rng();
+        let c = hex("5071f4f33ebff6133543a70e69be27ef8e5d6d166998a6b3d1d15c59143cb500483f0e2ea19ee5a8f515d6d325b738ad9d3fd32a747a74c6ba085b1f777e38435c2234a5cd4fb62c593f16f563a8102e246c83da6536947a732268c1be43c62639f64c8ad24d022addc6a221e19eaf684e59abd8d30a95f5379610810f066b638629ef643e3e32752425b6330a21e57dba668f2f5066aeb73206202f306b965402c16f05c89ef4480b91c4fedba0289b11ef9a56d3f1cd07fcefe6d9d781a18296129ba766acf847a8d76e43bd89f40e2fe0d78f09ecee7f6732329a2493216c585ddd29a90a2fc7450ddf17553af7f462f5dbb77e4b7033eb740f561be5dc1f56ef3cc8e84f7a942956f6523ab8c4670d3dcd7a7158b4c367cf7ddf8260a348e9152923235689284762259f00a6a030e7cfd8ae3535525edfd338788c4a8bfd41b8389211ac128bf23bca2d840364d8c5e9e801d8061ba6501959f60d4cfee75b1e26b81723c152efeb72c1bb1ccb9950874dbe43d20ba6f8d38c8f06bc0eafb0607ddce2029e53591561c59966fbb4dc58ec98708c25f478325f785151c2886140bead6754202a0fe7a487d2fe7da078eb59c3361e60db3ca420dfff4ac24c87711de8c6b51c877aa1a72f6aeef05ab320f985d631dfeff2db11d148f54e0ee608ea218262692a49b73f077ee05dd3ff8a351fea84b597b5d29cbeba35e912");
+        assert_eq!(rsa_decrypt_oaep(&c, &key, HashAlg::Sha256, Some(b"label"), &mut rng).unwrap(), b"attack at dawn");
+        assert_eq!(rsa_decrypt_oaep(&c, &key, HashAlg::Sha256, None, &mut rng), Err(RsaError::Decryption));
+        let c = hex("4fe2d95120d2053deb3a6a8fd17c44ff58cbc5a3f8b4b4c702fabdf60eab3953614e995f5e0eb6331d048a60f92d7315b9f10a2b2be12585f0eecdc2649789c6ecc00a6044edc5bf4aaa3d97c3e0813181c3190d089ea5adeffe653d97f3a5a7b6745d86770c3473566a4c5124379a72b60feb04d958e0da20c9e5e75c0f93890f2a34a8c400bdf202d92cc4b0bf784773ce0f26d5f5bf28d2499b75e15debea88991ec9246c2ca5b1d1a8caa43419b3aaaa941fb19e1f9e6900f6afe62135fbf9d9c8c41ef12fddf110a8e284fb016fcedc573264bd597095ff13ce700156c0ec38d47d24e176699449ab542baf17980be857aae03882b131a40ff73adecd99a0c2e19928693b81a1f845dc7f97bb88b89708f5a75eeb2644b63db715a69bb212db0d5f1b4b626bc44b14aa13e60d7572a5ace7c22c262624d84b539f1ef4f988775a2d3f8152595fb7a22b27b4b03506d5b7c0a7d3091b96c288230ae075bb68561d564258b7a430f75e0d6a69ebef0c15473f1fb2e7820a63e94aec081c317e7aa08e9abb986ea529babd84c485a8da6e02a32fd128f8390681a9c6d825e6f94bdecaac336b248b7b47560368ed09d7025e933c1afd148b7ec6abca50723593d9b7c92f2cfc4eae3ac3f46d260a0abc928be2089b83fedab728c5bb1818d7542e1420435c4aa2658da0c896574f572091f7fd46bfe0d9a5df81b76b245753");
+        assert_eq!(rsa_decrypt_pkcs1v15(&c, &key, &mut rng).unwrap(), b"attack at dawn");
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn crt_keys() {
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 1024);
+        let (p, q, N) = (key.p(), key.q(), key.n());
+        assert_eq!(*key.dp(), key.d() % (p - 1u64));
+        assert_eq!(*key.dq(), key.d() % (q - 1u64));
+        assert!(((key.qinv() * q) % p).is_one());
+        for _ in 0..20 {
+            let c = math::random_bits(&mut rng, 1024) % N;
+            assert_eq!(key.private_exp(&c, &mut rng), mod_exp(&c, key.d(), N));
+        }
+        // Values that are multiples of p or q still come out right.
+        assert_eq!(key.private_exp(p, &mut rng), mod_exp(p, key.d(), N));
+        assert_eq!(key.private_exp(&(q * 3u64), &mut rng), mod_exp(&(q * 3u64), key.d(), N));
+        assert!(key.private_exp(&BigUint::zero(), &mut rng).is_zero());
+
+        // Swapping the primes gives the same key as far as anybody else can tell.
+        let swapped = RsaPrivateKey::from_primes(q.clone(), p.clone(), key.e().clone()).unwrap();
+        assert_eq!(swapped.public_key(), key.public_key());
+        assert_eq!(swapped.d(), key.d());
+        assert_eq!(swapped.private_exp(&BigUint::from(12345u64), &mut rng), key.private_exp(&BigUint::from(12345u64), &mut rng));
+    }
+    #[test]
+    fn key_validation() {
+        let (p, q, e) = (BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(65537u64));
+        let key = |p: &BigUint, q: &BigUint, e: &BigUint| RsaPrivateKey::from_primes(p.clone(), q.clone(), e.clone());
+        assert!(key(&p, &q, &e).is_ok());
+        assert_eq!(key(&p, &p, &e), Err(RsaError::InvalidKey("p and q must be different")));
+        assert_eq!(key(&BigUint::from(1225u64), &q, &e), Err(RsaError::InvalidKey("p is not prime")));
+        assert_eq!(key(&p, &BigUint::from(29341u64), &e), Err(RsaError::InvalidKey("q is not prime")));
+        assert_eq!(key(&p, &q, &BigUint::from(65536u64)), Err(RsaError::InvalidKey("public exponent must be odd and at least 3")));
+        assert_eq!(key(&p, &q, &BigUint::one()), Err(RsaError::InvalidKey("public exponent must be odd and at least 3")));
+        // λ(1223 * 1987) = lcm(1222, 1986) is divisible by 3.
+        assert_eq!(key(&p, &q, &BigUint::from(3u64)), Err(RsaError::InvalidKey("chosen exponent is not coprime with λ(N)")));
+        // The size is checked before the (slow, at this size) primality tests.
+        let huge = (BigUint::one() << 9689) - 1u64;
+        let bigger = (BigUint::one() << 9941) - 1u64;
+        assert_eq!(key(&huge, &bigger, &e), Err(RsaError::InvalidKey("modulus is too large")));
+
+        assert!(RsaPublicKey::new(&p * &q, e.clone()).is_ok());
+        assert!(RsaPublicKey::new(&p * &q + 1u64, e.clone()).is_err());
+        assert!(RsaPublicKey::new(&p * &q, BigUint::from(2u64)).is_err());
+        assert!(RsaPublicKey::new((BigUint::one() << 20000) + 1u64, e).is_err());
+    }
+    #[test]
+    #[should_panic(expected = "q is not prime")]
+    fn derive_composite() {
+        rsa_derive_pubkey(BigUint::from(29341u64), BigUint::from(1223u64), BigUint::from(65537u64));
+    }
+    #[test]
+    fn close_primes() {
+        // 1000193 and 1000199 are only 6 apart, Fermat factors their product in one step.
+        assert!(key::too_close(&BigUint::from(1000193u64), &BigUint::from(1000199u64), 40));
+        assert!(!key::too_close(&BigUint::from(1223u64), &BigUint::from(1000199u64), 32));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn multi_prime_keys() {
+        let mut rng = rand::thread_rng();
+        for (bits, primes) in [(64, 3), (96, 4), (1024, 3), (2048, 4)] {
+            let key = RsaPrivateKey::generate_multi_prime(&mut rng, bits, primes);
+            let N = key.n();
+            assert_eq!(key.bits(), bits);
+            assert_eq!(key.primes().len(), primes);
+            assert_eq!(key.primes().iter().product::<BigUint>(), *N);
+            assert!(key.primes().iter().all(baillie_psw));
+            assert!(gcd(key.e(), &key::carmichael_lambda(&key.primes())).is_one());
+
+            let mut product = key.p() * key.q();
+            for info in key.other_primes() {
+                assert_eq!(*info.exponent(), key.d() % (info.prime() - 1u64));
+                assert!(((info.coefficient() * &product) % info.prime()).is_one());
+                product *= info.prime();
+            }
+            for _ in 0..10 {
+                let c = math::random_bits(&mut rng, bits) % N;
+                assert_eq!(key.private_exp(&c, &mut rng), mod_exp(&c, key.d(), N));
+            }
+            let m = BigUint::from(rng.next_u64()) % N;
+            assert_eq!(rsa_decrypt(&rsa_encrypt(&m, &key.public_key()), &key, &mut rng), m);
+        }
+
+        // Three primes of 1223, 1987 and 2003 work, and the order they come in doesn't matter to
+        // anybody holding the public key.
+        let (r1, r2, r3, e) = (BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(2003u64), BigUint::from(65537u64));
+        let key = RsaPrivateKey::from_prime_factors(vec![r1.clone(), r2.clone(), r3.clone()], e.clone()).unwrap();
+        assert_eq!(rsa_derive_multi_prime_pubkey(vec![r1.clone(), r2.clone(), r3.clone()], e.clone()), key.public_key());
+        let shuffled = RsaPrivateKey::from_prime_factors(vec![r3.clone(), r1.clone(), r2.clone()], e.clone()).unwrap();
+        assert_eq!(key.public_key(), shuffled.public_key());
+        let c = BigUint::from(4242424242u64);
+        assert_eq!(key.private_exp(&c, &mut rng), shuffled.private_exp(&c, &mut rng));
+
+        let key = |primes: &[&BigUint]| RsaPrivateKey::from_prime_factors(primes.iter().map(|&r| r.clone()).collect(), e.clone());
+        assert_eq!(key(&[&r1]), Err(RsaError::InvalidKey("a key needs at least two primes")));
+        assert_eq!(key(&[&r1, &r2, &r1]), Err(RsaError::InvalidKey("primes must all be different")));
+        assert_eq!(key(&[&r1, &r2, &BigUint::from(29341u64)]), Err(RsaError::InvalidKey("one of the other primes is not prime")));
+    }
+    #[test]
+    fn kem_hybrid_encryption() {
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 1024);
+        let public = key.public_key();
+        let k = public.size();
+        for len in [0, 1, 100, 1 << 20] {
+            let mut data = vec![0u8; len];
+            rng.fill_bytes(&mut data);
+            let sealed = seal(&public, &data, b"header", &mut rng);
+            assert_eq!(sealed.len(), 1 + k + len + 16);
+            assert_eq!(sealed[0], KEM_VERSION);
+            assert_eq!(open(&key, &sealed, b"header", &mut rng).unwrap(), data);
+        }
+
+        // Sealing the same thing twice gives two different ciphertexts.
+        let sealed = seal(&public, b"attack at dawn", b"", &mut rng);
+        assert_ne!(sealed, seal(&public, b"attack at dawn", b"", &mut rng));
+        assert_eq!(open(&key, &sealed, b"", &mut rng).unwrap(), b"attack at dawn");
+
+        // Flipping any bit (version, encapsulated key, body or tag), changing the associated data,
+        // cutting it short or using another key all fail the same way.
+        for i in [0, 1, k, k + 1, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert_eq!(open(&key, &tampered, b"", &mut rng), Err(RsaError::Decryption));
+        }
+        assert_eq!(open(&key, &sealed, b"x", &mut rng), Err(RsaError::Decryption));
+        assert_eq!(open(&key, &sealed[..k + 16], b"", &mut rng), Err(RsaError::Decryption));
+        assert_eq!(open(&key, &[], b"", &mut rng), Err(RsaError::Decryption));
+        let other = RsaPrivateKey::generate(&mut rng, 1024);
+        assert_eq!(open(&other, &sealed, b"", &mut rng), Err(RsaError::Decryption));
+
+        // Multi-prime keys don't change anything for the sender.
+        let key = RsaPrivateKey::generate_multi_prime(&mut rng, 1536, 3);
+        let sealed = seal(&key.public_key(), b"three primes", b"", &mut rng);
+        assert_eq!(open(&key, &sealed, b"", &mut rng).unwrap(), b"three primes");
+
+        // A message sealed with python's cryptography package (KDF2-SHA256 of r and AES-256-GCM)
+        // for a fixed r, so the wire format is pinned down by something other than this crate.
+        let p = BigUint::parse_bytes(b"e76770f1580ee5a9f5cb6af54df3b5d61d8c0318bbf87ec8d165afd20e6663016355fcd0d631592d33411cb20255df94d995e9fa0a6b777d2cd2d25e8738a267", 16).unwrap();
+        let q = BigUint::parse_bytes(b"c85eecf153666174785fe670eb20c61df53a7c0bc2a3fa62739a3f8154178362f45c3918c7b60cef8c7f984d4ae39e7b02d8b7328a57b44035e90f18144062a5", 16).unwrap();
+        let key = RsaPrivateKey::from_primes(p, q, BigUint::from(DEFAULT_EXPONENT)).unwrap();
+        let sealed = hex("0125d397ca6c919eb38217ef297f2c2a4ccedb798d820c4d98b59e7d55a6f59736537acc3eb178aa31e26ad25299b39edb5e70fda36c4a0911873549f8d163d01c702da81b15a91f31d3f00af586ab5bfef329f22cb6838fe8a7d13a5400ec2d00862138bdd1631c70063c8e7db7f80cb66b8c3f2810b37afe7440c68d2d5103e4abf0b96dfcadc4c7d48e2c1df5849582a3091f97c0001ec6b52900c31c89ea31cca2dac27fca2d6e4cf5044012cba911fc1fd3926b2fd1b63677b56e57439c8b5f2c047f5c0685c82e13ac3f1d4bb2f970bfbddab7bf2d5410920ba89cd8ac31294c7eec11");
+        assert_eq!(open(&key, &sealed, b"report.txt", &mut rng).unwrap(), b"The quick brown fox jumps over the lazy dog, and then does it again for good measure.");
+    }
+    #[test]
+    fn pkcs1_der() {
+        let mut rng = rand::thread_rng();
+        for primes in [2, 3, 4] {
+            let key = RsaPrivateKey::generate_multi_prime(&mut rng, 1024, primes);
+            let der = key.to_pkcs1_der();
+            let parsed = RsaPrivateKey::from_pkcs1_der(&der).unwrap();
+            assert_eq!(parsed.primes(), key.primes());
+            assert_eq!(parsed.d(), key.d());
+            assert_eq!(parsed.other_primes(), key.other_primes());
+            assert_eq!(parsed.to_pkcs1_der(), der);
+
+            // Cutting the encoding short or adding to the end of it are both caught.
+            assert!(RsaPrivateKey::from_pkcs1_der(&der[..der.len() - 1]).is_err());
+            let mut longer = der.clone();
+            longer.push(0);
+            assert!(RsaPrivateKey::from_pkcs1_der(&longer).is_err());
+        }
+
+        // A three-prime 2048-bit key made by "openssl genpkey -pkeyopt rsa_keygen_primes:3" and
+        // written out with "openssl rsa -traditional -outform DER", plus "multi-prime RSA"
+        // encrypted to it with "openssl pkeyutl" (OAEP with SHA-256).
+        let der = hex("308204d80201010282010100a94ed22d7c6d1204137d08ae91c023c378181a23d340a2e0908b1d76338aaff71d8f39b09d85de02d4211ce357e2e0c98f13cf0e4a89483c5e7093ef4fc0436455ec46dd1df750527981623844828c96ecca1a957351a87538f596195cb121799242eddadc11f394da5cb492d9915b391b22dd8b9860a115b6835611b883faf4751218ff0d2f13643325486f672e98bfc9b918d5f2259f44b8e4abc8d5e418a823d6c61293c987084400e4a591788cd87a6baf40e46f05289fd4a2151656ae8352e30e3a07525be12d9501ed62c2642e7982945901aaf657ab1b8aff0deea0c33a3010f2ef5a7f0820a81def95457c8820437cffafe5923d7d809e025b57a6350203010001028201004581976597144bf6226e0d57657ca4f2056697aca7787ca58691a619b5953522551f4c86c65483cd537af89a18a58ab514a9043310c485444b5d1a4e7fc625a49665b6bde62476f434efb9fbf19c4acd570ead953727fbdf87fcb3c618aff5e3a940108de540111214577bd4b70bd61cbd977a43a808e6da2a065e3ff190cde0377410c8ec640b98ff4b35df31b1ad679c7668bb5813a02d65b58a52954b7075464e6bf4712022e850ac95d31d017f375bc080a58f8264216b1a25186b7c4fb40a655b45fd459f8fc3364b18e9cea8f6163801dd4b8d9bb459e0ba8ba0a47d678987a093dd62b2a7e1146c85478b163475db60f8f2088871b7b2bd6379ded0b1025607225798e9ac87d58c32f87856b9e7cf0afdbe2105021f80367c58a97513e73080c93b70efd26431315af6ff85959bc7f4e2cd2cb02529385afdf0f5fcf40cea328664f56af55cd0d19b3bf538af4c8478aacfb557ff0256068ff93670dfb3dadccdf8aae74e18777ba98a76e2f7c4323d93dd8b50c97d1328c6fe3b0f430d364c42869a08951d0304372759122de9933a4fa2d10e94c3e9cb9e6ee281f5b7d30265f416f01739eab30c5e573dbd0256040370ee487f544771c0f7019420afe54d54a492a4e3a5df1647ec0521255a49721a43436cd79aa006191b9d72845108339f2837fcbc5b2d261b78649ab29d555fa935c0cf344bcbfa2f6f92ac1a0a5cf819781297f70256053ca188c35c917f87934aecbf773fe921a3335f1a52d49baf2e2faebc827e306aeb19f8717e75adadffacc08136d59711a71ddc63b885dc3abd5055fa87d72bdc950b31f0b9f0bf0ffbe264e7a5f14f0d2142abe61502560433b9dca8897f2883f353aa3383141e225320aa2267401927512f476c890abbd21966a8b94ac27c458851daf9da44cd947067c6101707443743387b44e0db5ff2ef7fbb3ac910a684bf4864f75dd608225cee951d193082010b308201070256039dca437453ce7cc51037ba882e12b9c01dd8c74a5a502ad4ffa78a643ac1425fa146207c27e9c27d34a746d7e69a1862b39e7aa04e441354a1f56ac78754bb2646bd507a9102901eb47e6e5470be761b4a347842270255460d4b035f600d82dfd247b40c40707bc0066a360f1567d84c2c0245c9485c5c5e18af5695b2937be3e310723fc9c3349cc0ce2899237f8ef4de5c2112f56f5fdb14722584197c8c463e16dd1ed6da9b00076e5e1b025602167ad27b948b4b8443fb468aa947b5397a7b6b108afbd7136f45aeba15b65b5cf9ae14cf3561c4a56663fb207bb44d608a760d882ac7f747dea384b8853b25a08a7887b627f6ddc7d56d760cfff1730aba8f51bf36");
+        let key = RsaPrivateKey::from_pkcs1_der(&der).unwrap();
+        assert_eq!(key.bits(), 2048);
+        assert_eq!(key.other_primes().len(), 1);
+        assert_eq!(key.to_pkcs1_der(), der);
+        let c = hex("02978c89a26745b6bfddcc020fce4ec928e5ee3bbb36a2e1f5a327609ce2c3c83ac1f6e90c2a9c054bacd4aa49a2d2eb73effcf9946c1c456d311db226677e25b151727548399d0308f953519d9600d0a9f70283055c4b078c086cd0c89eefb119457ae5167f2efdad3e0bf367b221489960eda303501a58a549218f4da6d25047606c4765d176e60dae08683ecba88e7fd2625b5bf7e314ccbf97df8ad7ec64215a6fa3220371356a464c407cdb4a6a405dce0dafd19463e666754c3cd3c8033b897e1d0d79d3e1464d0d198f7ad38d3aa9afe45237364116b35d15f6c0e47648c6241b1dea2c13829ecfc78a5c1ad13d5a868f541ca00fa0926a310b8f66e3");
+        assert_eq!(rsa_decrypt_oaep(&c, &key, HashAlg::Sha256, None, &mut rng).unwrap(), b"multi-prime RSA");
+
+        // Changing a single CRT value makes it a different (broken) key.
+        let mut tampered = der.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert_eq!(RsaPrivateKey::from_pkcs1_der(&tampered).err(), Some(RsaError::InvalidKey("key values don't match the primes")));
+        assert!(RsaPrivateKey::from_pkcs1_der(&[0x30, 0x00]).is_err());
+        assert!(RsaPrivateKey::from_pkcs1_der(&[]).is_err());
+    }
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+    #[test]
+    fn oaep_encoding() {
+        assert_eq!(hash::mgf1(HashAlg::Sha256, b"foo", 50), hex("3bdaba83cff13337b323ac383ca3995863e922f511b931b9efd4e0118cfc70f08678390d67e3c12dbeb2d7a78bdfa597b5a3"));
+
+        let seed: Vec<u8> = (0..64).collect();
+        let em = oaep::oaep_encode(b"hello", 80, HashAlg::Sha256, b"", &seed[..32]).unwrap();
+        assert_eq!(em, hex("001855a56bedb1aa37deea65b0906ef23ea07f33c75644d06d60b6c2c7044c5ac79344c47fca4af717407eda5bbc04e0a2927ac9d4fc20ea3f18c681d71e31c2d104a6950a06d3e3308ad6bb0502947f"));
+        assert_eq!(oaep::oaep_decode(&em, HashAlg::Sha256, b"").unwrap(), b"hello");
+        let em = oaep::oaep_encode(b"attack at dawn", 160, HashAlg::Sha512, b"label", &seed).unwrap();
+        assert_eq!(em, hex("00fcc9afca2bcc09443ed82ac623cd3c14c3059934ba324acdc90cd7d888f79fecf580c839b36ad0ddf81c4ce1a9c9e6832475dbfb77d4dd4926d29ca2e523f909d213088d748ece25e7d8f88515eb2e75bb78b8ac995b2036ab759302cdd54e2effdbd0128e2bf856a54d3c5113d40d3828e393571f6b795935ae79688ec22b9f97a45017c508401d375ad0fa856da3dfec41f0037716374b62621a884ce82d"));
+        assert_eq!(oaep::oaep_decode(&em, HashAlg::Sha512, b"label").unwrap(), b"attack at dawn");
+
+        // Wrong label, wrong hash, or any flipped byte has to be rejected.
+        assert_eq!(oaep::oaep_decode(&em, HashAlg::Sha512, b"lab3l"), Err(RsaError::Decryption));
+        assert_eq!(oaep::oaep_decode(&em, HashAlg::Sha256, b"label"), Err(RsaError::Decryption));
+        for i in [0, 1, 64, 65, 128, 159] {
+            let mut bad = em.clone();
+            bad[i] ^= 0x40;
+            assert_eq!(oaep::oaep_decode(&bad, HashAlg::Sha512, b"label"), Err(RsaError::Decryption));
+        }
+
+        // Longest message that fits, empty message, and one byte too many.
+        for hash in [HashAlg::Sha256, HashAlg::Sha512] {
+            let k = 256;
+            let max = k - 2 * hash.size() - 2;
+            for len in [0, 1, max] {
+                let m = vec![0x01u8; len];
+                let em = oaep::oaep_encode(&m, k, hash, b"", &seed[..hash.size()]).unwrap();
+                assert_eq!(em.len(), k);
+                assert_eq!(oaep::oaep_decode(&em, hash, b"").unwrap(), m);
+            }
+            assert_eq!(oaep::oaep_encode(&vec![0u8; max + 1], k, hash, b"", &seed[..hash.size()]), Err(RsaError::MessageTooLong));
+        }
+        assert_eq!(oaep::oaep_encode(b"", 65, HashAlg::Sha256, b"", &seed[..32]), Err(RsaError::KeyTooSmall));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn oaep_encryption() {
+        // A 256-bit modulus is 32 bytes, which is not even enough room for the 66 bytes of OAEP
+        // overhead with SHA-256.
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 256);
+        let public = key.public_key();
+        assert_eq!(rsa_encrypt_oaep(b"hi", &public, HashAlg::Sha256, None, &mut rng), Err(RsaError::KeyTooSmall));
+        assert_eq!(rsa_encrypt_oaep(b"hi", &public, HashAlg::Sha512, Some(b"label"), &mut rng), Err(RsaError::KeyTooSmall));
+        assert_eq!(rsa_decrypt_oaep(&[0u8; 32], &key, HashAlg::Sha256, None, &mut rng), Err(RsaError::Decryption));
+
+        let key = RsaPrivateKey::generate(&mut rng, 1024);
+        let public = key.public_key();
+        for hash in [HashAlg::Sha256, HashAlg::Sha384] {
+            let max = 128 - 2 * hash.size() - 2;
+            for len in [0, 2, max] {
+                let m = vec![0xffu8; len];
+                let c = rsa_encrypt_oaep(&m, &public, hash, None, &mut rng).unwrap();
+                assert_eq!(c.len(), 128);
+                assert_eq!(rsa_decrypt_oaep(&c, &key, hash, None, &mut rng).unwrap(), m);
+                assert_ne!(rsa_encrypt_oaep(&m, &public, hash, None, &mut rng).unwrap(), c);
+                let mut bad = c.clone();
+                bad[64] ^= 1;
+                assert_eq!(rsa_decrypt_oaep(&bad, &key, hash, None, &mut rng), Err(RsaError::Decryption));
+            }
+            assert_eq!(rsa_encrypt_oaep(&vec![0u8; max + 1], &public, hash, None, &mut rng), Err(RsaError::MessageTooLong));
+        }
+        // 2 * 64 + 2 bytes of overhead don't fit in 128, so SHA-512 needs a bigger key.
+        assert_eq!(rsa_encrypt_oaep(b"", &public, HashAlg::Sha512, None, &mut rng), Err(RsaError::KeyTooSmall));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn pkcs1v15_encryption() {
+        let mut rng = rand::thread_rng();
+        for bits in [128, 200, 256] {
+            let key = RsaPrivateKey::generate(&mut rng, bits);
+            let public = key.public_key();
+            let k = bits / 8;
+            for len in [0, 1, k - 11] {
+                let m: Vec<u8> = (0..len as u8).collect();
+                let c = rsa_encrypt_pkcs1v15(&m, &public, &mut rng).unwrap();
+                assert_eq!(c.len(), k);
+                assert_eq!(rsa_decrypt_pkcs1v15(&c, &key, &mut rng).unwrap(), m);
+                // Random padding means encrypting twice gives two different ciphertexts.
+                assert_ne!(rsa_encrypt_pkcs1v15(&m, &public, &mut rng).unwrap(), c);
+            }
+            assert_eq!(rsa_encrypt_pkcs1v15(&vec![0u8; k - 10], &public, &mut rng), Err(RsaError::MessageTooLong));
+            assert_eq!(rsa_decrypt_pkcs1v15(&[0u8; 3], &key, &mut rng), Err(RsaError::Decryption));
+            assert_eq!(rsa_decrypt_pkcs1v15(&vec![0xffu8; k], &key, &mut rng), Err(RsaError::Decryption));
+        }
+    }
+    #[test]
+    fn audit_weak_moduli() {
+        let e = BigUint::from(65537u64);
+        let config = AuditConfig { smoothness_bound: 200, fermat_steps: 1 << 16 };
+
+        // The adjacent primes from close_primes fall to Fermat straight away.
+        let (p, q) = (BigUint::from(1000193u64), BigUint::from(1000199u64));
+        let n = &p * &q;
+        let close = Weakness::CloseFactors { p: p.clone(), q: q.clone(), steps: 0 };
+        assert!(audit_with(&n, &e, None, config).contains(&close));
+        assert!(audit_with(&n, &e, Some((&q, &p)), config).contains(&close));
+
+        // 4222414611871 - 1 = 2 * 3 * 5 * 7 * 13 * 17 * 61 * 67 * 113 * 197, while 1000000000039 - 1
+        // has the factor 26005097.
+        let (p, q) = (BigUint::from(4222414611871u64), BigUint::from(1000000000039u64));
+        let n = &p * &q;
+        let smooth = vec![Weakness::SmoothFactor { factor: p.clone(), bound: 200 }];
+        assert_eq!(audit_with(&n, &e, None, config), smooth);
+        assert_eq!(audit_with(&n, &e, Some((&p, &q)), config), smooth);
+        let config = AuditConfig { smoothness_bound: 100, ..config };
+        assert!(audit_with(&n, &e, None, config).is_empty());
+        assert!(audit_with(&n, &e, Some((&p, &q)), config).is_empty());
+
+        // A 1024-bit key with a 128-bit d is well under Wiener's bound of roughly 2^254.
+        let mut rng = rand::thread_rng();
+        let (p, q) = (key::generate_prime(&mut rng, 512), key::generate_prime(&mut rng, 512));
+        let n = &p * &q;
+        let phi = (&p - 1u64) * (&q - 1u64);
+        let (d, e) = loop {
+            let d = random_bits(&mut rng, 128) | BigUint::one();
+            if let Some(e) = mod_inverse(&d, &phi) { break (d, e); }
+        };
+        let weaknesses = audit(&n, &e, None);
+        assert_eq!(weaknesses, vec![Weakness::SmallPrivateExponent { d: d.clone() }]);
+        assert_eq!(audit(&n, &e, Some((&p, &q))), weaknesses);
+
+        // A properly generated key passes, unless its exponent is small.
+        let key = RsaPrivateKey::generate(&mut rng, 1024);
+        assert!(audit(key.n(), key.e(), Some((key.p(), key.q()))).is_empty());
+        assert!(audit(key.n(), key.e(), None).is_empty());
+        assert_eq!(audit(key.n(), &BigUint::from(3u64), None), vec![Weakness::SmallPublicExponent]);
+    }
+    #[test]
+    fn audit_shared_factors() {
+        let mut rng = rand::thread_rng();
+        let primes: Vec<BigUint> = (0..6).map(|_| key::generate_prime(&mut rng, 256)).collect();
+        // Moduli 0 and 3 share primes[0], 1 and 4 share primes[2]; 2 is on its own.
+        let moduli = vec![
+            &primes[0] * &primes[1],
+            &primes[2] * &primes[3],
+            &primes[4] * &primes[5],
+            &primes[0] * &primes[3],
+            &primes[2] * &primes[1],
+        ];
+        let gcds = batch_gcd(&moduli);
+        assert_eq!(gcds[0], &primes[0] * &primes[1]);
+        assert_eq!(gcds[1], &primes[2] * &primes[3]);
+        assert!(gcds[2].is_one());
+        assert_eq!(gcds[3], &primes[0] * &primes[3]);
+        assert_eq!(gcds[4], &primes[2] * &primes[1]);
+
+        let shared = |other, factor: &BigUint| Weakness::SharedFactor { other, factor: factor.clone() };
+        let weaknesses = audit_batch(&moduli);
+        assert_eq!(weaknesses[0], vec![shared(3, &primes[0]), shared(4, &primes[1])]);
+        assert_eq!(weaknesses[1], vec![shared(3, &primes[3]), shared(4, &primes[2])]);
+        assert!(weaknesses[2].is_empty());
+        assert_eq!(weaknesses[3], vec![shared(0, &primes[0]), shared(1, &primes[3])]);
+        assert_eq!(weaknesses[4], vec![shared(0, &primes[1]), shared(1, &primes[2])]);
+
+        assert!(batch_gcd(&moduli[..1])[0].is_one());
+        assert!(audit_batch(&[]).is_empty());
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn paillier_homomorphic_encryption() {
+        let mut rng = rand::thread_rng();
+        let key = PaillierPrivateKey::generate(&mut rng, 1024);
+        let public = key.public_key();
+        let N = key.n();
+        assert_eq!(key.p() * key.q(), *N);
+        assert_eq!(public.n_squared(), &(N * N));
+
+        let (m1, m2) = (random_bits(&mut rng, 1000) % N, random_bits(&mut rng, 1000) % N);
+        let (c1, c2) = (public.encrypt(&m1, &mut rng).unwrap(), public.encrypt(&m2, &mut rng).unwrap());
+        assert_eq!(key.decrypt(&c1), Ok(m1.clone()));
+        assert_eq!(key.decrypt(&c2), Ok(m2.clone()));
+        // Same message, different ciphertext.
+        assert_ne!(public.encrypt(&m1, &mut rng).unwrap(), c1);
+
+        assert_eq!(key.decrypt(&public.add(&c1, &c2)), Ok((&m1 + &m2) % N));
+        let k = BigUint::from(123456789u64);
+        assert_eq!(key.decrypt(&public.mul_plain(&c1, &k)), Ok((&m1 * &k) % N));
+        assert_eq!(key.decrypt(&public.add_plain(&c1, &k)), Ok((&m1 + &k) % N));
+        let c3 = public.rerandomize(&c1, &mut rng);
+        assert_ne!(c3, c1);
+        assert_eq!(key.decrypt(&c3), Ok(m1.clone()));
+
+        // Adding up a column of encrypted votes without decrypting any of them.
+        let votes = [1u64, 0, 1, 1, 0, 1, 1];
+        let zero = public.encrypt(&BigUint::zero(), &mut rng).unwrap();
+        let tally = votes.iter().map(|&v| public.encrypt(&BigUint::from(v), &mut rng).unwrap()).fold(zero, |acc, c| public.add(&acc, &c));
+        assert_eq!(key.decrypt(&tally), Ok(BigUint::from(5u64)));
+        // Everything wraps around mod N.
+        let c = public.encrypt(&(N - 1u64), &mut rng).unwrap();
+        assert_eq!(key.decrypt(&public.add_plain(&c, &BigUint::from(2u64))), Ok(BigUint::one()));
+
+        assert_eq!(public.encrypt(N, &mut rng), Err(RsaError::MessageTooLong));
+        assert_eq!(key.decrypt(public.n_squared()), Err(RsaError::Decryption));
+        assert_eq!(key.decrypt(key.p()), Err(RsaError::Decryption));
+
+        // Small primes with a known answer: N = 1223 * 1987, c = (1 + 42N) * 5^N (mod N^2).
+        let (p, q) = (BigUint::from(1223u64), BigUint::from(1987u64));
+        let key = PaillierPrivateKey::from_primes(p.clone(), q.clone()).unwrap();
+        let N = key.n();
+        let c = (N * 42u64 + 1u64) * mod_exp(&BigUint::from(5u64), N, key.public_key().n_squared()) % key.public_key().n_squared();
+        assert_eq!(key.decrypt(&c), Ok(BigUint::from(42u64)));
+        assert_eq!(PaillierPrivateKey::from_primes(p.clone(), p.clone()), Err(RsaError::InvalidKey("p and q must be different")));
+        assert_eq!(PaillierPrivateKey::from_primes(BigUint::from(29341u64), q.clone()), Err(RsaError::InvalidKey("p is not prime")));
+        // 7 divides 29 - 1, so N = 7 * 29 shares a factor with φ(N).
+        assert_eq!(PaillierPrivateKey::from_primes(BigUint::from(7u64), BigUint::from(29u64)), Err(RsaError::InvalidKey("N is not coprime with φ(N)")));
+        assert!(PaillierPublicKey::new(BigUint::from(1000u64)).is_err());
+    }
+    #[test]
+    fn modular_square_roots() {
+        // 3 (mod 4), 5 (mod 8) and 1 (mod 8) primes, the last one with 2^4 dividing p - 1.
+        for p in [1223u64, 1987, 1000000007, 13, 1000000021, 17, 1000000009, 7340033] {
+            let p = BigUint::from(p);
+            let mut squares = 0;
+            for a in 0u64..200 {
+                let a = BigUint::from(a);
+                match sqrt_mod_prime(&a, &p) {
+                    Some(x) => {
+                        assert_eq!(&x * &x % &p, &a % &p);
+                        squares += 1;
+                    }
+                    None => assert_eq!(prime::jacobi(&a, &p), -1),
+                }
+            }
+            assert!(squares > 50 && squares < 150);
+        }
+
+        let key = RabinPrivateKey::from_primes(BigUint::from(1987u64), BigUint::from(1223u64)).unwrap();
+        assert_eq!((key.p(), key.q()), (&BigUint::from(1987u64), &BigUint::from(1223u64)));
+        let n = key.n();
+        let x = BigUint::from(123456u64);
+        let roots = key.sqrt(&(&x * &x % n)).unwrap();
+        assert!(roots.contains(&x));
+        for r in roots.iter() {
+            assert_eq!(r * r % n, &x * &x % n);
+        }
+        // The principal root is the one root that is a square itself.
+        assert!(key.sqrt(&roots[0]).is_some());
+        assert!(roots[1..].iter().all(|r| key.sqrt(r).is_none()));
+        // Two different roots of the same square factor N.
+        let p = gcd(&(&roots[0] + n - &roots[2]), n);
+        assert!(p == *key.p() || p == *key.q());
+        assert!(key.sqrt(&(n - 1u64)).is_none());
+    }
+    #[test]
+    fn rabin_encryption_and_signatures() {
+        let mut rng = rand::thread_rng();
+        let key = RabinPrivateKey::generate(&mut rng, 1024);
+        let public = key.public_key();
+        assert_eq!(key.p() * key.q(), *key.n());
+        assert_eq!(key.p() % 8u64, BigUint::from(3u64));
+        assert_eq!(key.q() % 8u64, BigUint::from(7u64));
+
+        let k = public.size();
+        for m in [&b""[..], b"hello", &[0u8, 0, 1], &[0xffu8; 118]] {
+            let c = public.encrypt(m).unwrap();
+            assert_eq!(c.len(), k);
+            assert_eq!(key.decrypt(&c).unwrap(), m);
+        }
+        assert_eq!(public.encrypt(&[0xffu8; 119]), Err(RsaError::MessageTooLong));
+        assert_eq!(key.decrypt(&[0u8; 3]), Err(RsaError::Decryption));
+        // A random square almost surely carries no valid tag in any of its roots.
+        let x = random_bits(&mut rng, 1000);
+        assert_eq!(key.decrypt(&math::i2osp(&(&x * &x % key.n()), k).unwrap()), Err(RsaError::Decryption));
+
+        let mut tweaks = [false; 4];
+        for i in 0..64u32 {
+            let msg = i.to_be_bytes();
+            let sig = key.sign(&msg);
+            assert!(public.verify(&msg, &sig));
+            assert_eq!(key.sign(&msg), sig);
+            assert!(!public.verify(b"other", &sig));
+            let mut bad = sig.clone();
+            bad.negate = !bad.negate;
+            assert!(!public.verify(&msg, &bad));
+            let mut bad = sig.clone();
+            bad.s = key.n() - &bad.s;
+            assert!(public.verify(&msg, &bad), "-s is a root too");
+            bad.s += 1u64;
+            assert!(!public.verify(&msg, &bad));
+            tweaks[sig.negate as usize * 2 + sig.double as usize] = true;
+        }
+        // 64 messages land on all four tweaks.
+        assert_eq!(tweaks, [true; 4]);
+
+        assert_eq!(RabinPrivateKey::from_primes(BigUint::from(1223u64), BigUint::from(1223u64)), Err(RsaError::InvalidKey("primes must be 3 and 7 (mod 8)")));
+        assert_eq!(RabinPrivateKey::from_primes(BigUint::from(1019u64), BigUint::from(1009u64)), Err(RsaError::InvalidKey("primes must be 3 and 7 (mod 8)")));
+        assert_eq!(RabinPrivateKey::from_primes(BigUint::from(11u64 * 17), BigUint::from(1223u64)), Err(RsaError::InvalidKey("primes must be prime")));
+        assert!(RabinPublicKey::new(BigUint::from(1987u64 * 1009)).is_err());
+    }
+    #[test]
+    fn time_lock_puzzles() {
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 1024);
+        let bid = b"sealed bid: 1250";
+        let puzzle = TimeLockPuzzle::create(&key, bid, 10000, &mut rng);
+        assert_eq!((puzzle.n(), puzzle.t()), (key.n(), 10000));
+        assert_eq!(puzzle.ciphertext().len(), bid.len() + 16);
+        assert_eq!(puzzle.solve().unwrap(), bid);
+        assert_eq!(square_repeatedly(puzzle.a(), 3, key.n()), mod_exp(puzzle.a(), &BigUint::from(8u64), key.n()));
+
+        // Same message, fresh a: nothing in common between two puzzles.
+        let other = TimeLockPuzzle::create(&key, bid, 10000, &mut rng);
+        assert_ne!(other.ciphertext(), puzzle.ciphertext());
+        assert_eq!(TimeLockPuzzle::create(&key, b"", 0, &mut rng).solve().unwrap(), b"");
+
+        // Solving with the wrong T (or a changed ciphertext) gets nowhere.
+        let (n, a) = (puzzle.n().clone(), puzzle.a().clone());
+        let received = TimeLockPuzzle::from_parts(n.clone(), a.clone(), 10000, puzzle.ciphertext().to_vec());
+        assert_eq!(received, puzzle);
+        let wrong = TimeLockPuzzle::from_parts(n.clone(), a.clone(), 9999, puzzle.ciphertext().to_vec());
+        assert_eq!(wrong.solve(), Err(RsaError::Decryption));
+        let mut ciphertext = puzzle.ciphertext().to_vec();
+        ciphertext[0] ^= 1;
+        assert_eq!(TimeLockPuzzle::from_parts(n, a, 10000, ciphertext).solve(), Err(RsaError::Decryption));
+
+        // Multi-prime keys work as trapdoors too.
+        let key = RsaPrivateKey::generate_multi_prime(&mut rng, 1024, 3);
+        assert_eq!(TimeLockPuzzle::create(&key, bid, 5000, &mut rng).solve().unwrap(), bid);
+    }
+    #[test]
+    fn verifiable_delay_functions() {
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 1024);
+        let public = key.public_key();
+        let half = key.n() >> 1;
+        for t in [0u64, 1, 2, 127, 128, 129, 5000] {
+            let out = vdf_eval(&public, b"block 1234", t);
+            assert!(out.y <= half && out.proof <= half);
+            assert!(vdf_verify(&public, b"block 1234", t, &out));
+            assert_eq!(vdf_eval_trapdoor(&key, b"block 1234", t), out);
+            assert!(!vdf_verify(&public, b"block 1235", t, &out));
+            assert!(!vdf_verify(&public, b"block 1234", t + 1, &out));
+
+            // -y and -π are the same group elements, but only the canonical ones are accepted.
+            let negated = VdfOutput { y: key.n() - &out.y, proof: key.n() - &out.proof };
+            assert!(!vdf_verify(&public, b"block 1234", t, &negated));
+            let bad = VdfOutput { y: out.y.clone(), proof: &out.proof * 2u64 % key.n() };
+            assert!(!vdf_verify(&public, b"block 1234", t, &bad));
+        }
+        // The trapdoor takes no time even for delays nobody could ever evaluate.
+        let out = vdf_eval_trapdoor(&key, b"far future", 1 << 40);
+        assert!(vdf_verify(&public, b"far future", 1 << 40, &out));
+    }
+    #[test]
+    fn rsa_accumulator() {
+        let mut rng = rand::thread_rng();
+        let revoked: Vec<BigUint> = (0..6u32).map(|i| hash_to_prime(&i.to_be_bytes())).collect();
+        assert!(revoked.iter().all(|x| x.bits() as usize == ACCUMULATOR_PRIME_BITS));
+        assert_eq!(hash_to_prime(&0u32.to_be_bytes()), revoked[0]);
+
+        let mut set = AccumulatorSet::setup(&mut rng, 1024);
+        set.insert(&revoked[..4]).unwrap();
+        assert_eq!(set.insert(&revoked[..1]), Err(RsaError::InvalidElement("already in the set")));
+        assert_eq!(set.insert(&[revoked[4].clone(), revoked[4].clone()]), Err(RsaError::InvalidElement("listed twice")));
+        assert_eq!(set.delete(&revoked[4..5]), Err(RsaError::InvalidElement("not in the set")));
+        assert_eq!(set.insert(&[&revoked[4] + 2u64]), Err(RsaError::InvalidElement("not a prime")));
+        assert_eq!(set.elements().count(), 4);
+
+        // Witnesses from the issuer, one by one and all at once.
+        let acc = set.accumulator().clone();
+        let witnesses = set.membership_witnesses();
+        for (x, w) in set.elements().zip(&witnesses) {
+            assert_eq!(set.membership_witness(x).as_ref(), Some(w));
+            assert!(acc.verify_membership(x, w));
+        }
+        let w0 = set.membership_witness(&revoked[0]).unwrap();
+        assert!(!acc.verify_membership(&revoked[1], &w0));
+        assert!(!acc.verify_membership(&(&revoked[0] * &revoked[1]), &set.membership_witness(&revoked[2]).unwrap()));
+        assert_eq!(set.membership_witness(&revoked[5]), None);
+        let nw5 = set.non_membership_witness(&revoked[5]).unwrap();
+        assert!(acc.verify_non_membership(&revoked[5], &nw5));
+        assert!(!acc.verify_non_membership(&revoked[4], &nw5));
+        assert_eq!(set.non_membership_witness(&revoked[0]), None);
+
+        // A holder keeps its witnesses current through a batch insert and a batch delete.
+        let mut holder = acc.clone();
+        let (mut w0, mut nw5) = (w0, nw5);
+        let mut w1 = set.membership_witness(&revoked[1]).unwrap();
+        let insert = set.insert(&revoked[4..5]).unwrap();
+        let delete = set.delete(&revoked[1..3]).unwrap();
+        for update in [&insert, &delete] {
+            w0 = holder.update_membership(&revoked[0], &w0, update).unwrap();
+            nw5 = holder.update_non_membership(&revoked[5], &nw5, update).unwrap();
+            assert_eq!(holder.update_non_membership(&revoked[4], &nw5, &insert), None);
+            if let Some(w) = holder.update_membership(&revoked[1], &w1, update) { w1 = w; }
+            holder.apply(update).unwrap();
+        }
+        assert_eq!(holder, *set.accumulator());
+        assert!(holder.verify_membership(&revoked[0], &w0));
+        assert!(holder.verify_non_membership(&revoked[5], &nw5));
+        assert!(!holder.verify_membership(&revoked[1], &w1));
+        assert_eq!(holder.update_membership(&revoked[1], &w1, &delete), None);
+        let nw1 = set.non_membership_witness(&revoked[1]).unwrap();
+        assert!(holder.verify_non_membership(&revoked[1], &nw1));
+
+        // Updates only apply in order, to the value they came from.
+        assert!(holder.apply(&insert).is_err());
+        assert_eq!(AccumulatorSet::new(acc.clone()).accumulator().value(), acc.g());
+        assert!(Accumulator::new(acc.n().clone(), BigUint::one()).is_err());
+    }
+    #[test]
+    fn rsa_cryptanalysis() {
+        let mut rng = rand::thread_rng();
+        // Primes that are 2 mod 3, so that e = 3 is allowed.
+        let mut prime = |bits| loop {
+            let p = key::generate_prime(&mut rng, bits);
+            if &p % 3u64 == BigUint::from(2u64) { break p; }
+        };
+
+        // Wiener: a 128-bit d in a 1024-bit key.
+        let (p, q) = (prime(512), prime(512));
+        let phi = (&p - 1u64) * (&q - 1u64);
+        let e = (1u64..).map(|i| mod_inverse(&((BigUint::one() << 127) + 2 * i + 1u64), &phi)).find_map(|e| e).unwrap();
+        let public = rsa_derive_pubkey(q.clone(), p.clone(), e);
+        let broken = wiener_attack(&public).unwrap();
+        assert_eq!(broken.public_key(), public);
+        let m = BigUint::from(424242u64);
+        assert_eq!(rsa_decrypt(&rsa_encrypt(&m, &public), &broken, &mut rand::thread_rng()), m);
+        assert_eq!(wiener_attack(&rsa_derive_pubkey(q.clone(), p.clone(), BigUint::from(65537u64))), None);
+
+        // Håstad: the same message to three e = 3 keys.
+        let m = os2ip(b"attack at dawn, bring snacks");
+        let keys: Vec<RsaPublicKey> = (0..3).map(|_| rsa_derive_pubkey(prime(256), prime(256), BigUint::from(3u64))).collect();
+        let ciphertexts: Vec<BigUint> = keys.iter().map(|key| rsa_encrypt(&m, key)).collect();
+        assert_eq!(hastad_broadcast(&keys, &ciphertexts), Some(m.clone()));
+        assert_eq!(hastad_broadcast(&keys[..2], &ciphertexts[..2]), None);
+        let mut wrong = ciphertexts.clone();
+        wrong[1] = rsa_encrypt(&(&m + 1u64), &keys[1]);
+        assert_eq!(hastad_broadcast(&keys, &wrong), None);
+
+        // Common modulus: one N handed out with e = 3 and e = 65537.
+        let key1 = rsa_derive_pubkey(q.clone(), p.clone(), BigUint::from(3u64));
+        let key2 = rsa_derive_pubkey(q.clone(), p.clone(), BigUint::from(65537u64));
+        let (c1, c2) = (rsa_encrypt(&m, &key1), rsa_encrypt(&m, &key2));
+        assert_eq!(common_modulus_attack(&key1, &c1, &key2, &c2), Some(m.clone()));
+        assert_eq!(common_modulus_attack(&key2, &c2, &key1, &c1), Some(m.clone()));
+        assert_eq!(common_modulus_attack(&key1, &c1, &keys[0], &ciphertexts[0]), None);
+
+        // Stereotyped message: everything but the last two bytes is known.
+        let m = os2ip(b"your PIN is 4271");
+        let known = os2ip(b"your PIN is 42\x00\x00");
+        let c = rsa_encrypt(&m, &key1);
+        assert_eq!(stereotyped_message_attack(&key1, &c, &known, 16), Some(m.clone()));
+        assert_eq!(stereotyped_message_attack(&key1, &c, &known, 8), None);
+        assert_eq!(stereotyped_message_attack(&key1, &c, &known, MAX_UNKNOWN_BITS + 1), None);
+    }
+    #[test]
+    fn integer_factorization() {
+        let mut rng = rand::thread_rng();
+        let big = |s: &str| BigUint::parse_bytes(s.as_bytes(), 10).unwrap();
+        let mut semiprime = |low: usize, high: usize| {
+            let (p, q) = (key::generate_prime(&mut rng, low), key::generate_prime(&mut rng, high));
+            let (p, q) = if p < q { (p, q) } else { (q, p) };
+            (&p * &q, vec![(p, 1), (q, 1)])
+        };
+
+        // Each method on its own.
+        let (n, factors) = semiprime(32, 32);
+        assert!(pollard_rho(&n, 1 << 20).is_some_and(|d| d == factors[0].0 || d == factors[1].0));
+        assert_eq!(pollard_rho(&factors[0].0, 1 << 20), None);
+        // 4222414611871 - 1 is 200-smooth and 1000000000039 - 1 isn't.
+        let smooth = BigUint::from(4222414611871u64) * 1000000000039u64;
+        assert_eq!(pollard_p_minus_1(&smooth, 200), Some(BigUint::from(4222414611871u64)));
+        assert_eq!(pollard_p_minus_1(&smooth, 100), None);
+        // ECM doesn't care how big the cofactor is, only how big the smallest prime is.
+        let (n, factors) = semiprime(24, 256);
+        assert_eq!(ecm(&n, 200, 1000), Some(factors[0].0.clone()));
+        for bits in [60, 72, 84] {
+            let (n, factors) = semiprime(bits / 2, bits / 2);
+            let d = quadratic_sieve(&n).unwrap();
+            assert!(d == factors[0].0 || d == factors[1].0, "{} bits", bits);
+        }
+        assert_eq!(quadratic_sieve(&(BigUint::one() << (QS_MAX_BITS as usize + 1) | BigUint::one())), None);
+
+        // factor picks whatever works.
+        assert_eq!(factor(&BigUint::one()), vec![]);
+        assert_eq!(factor(&BigUint::from(360u64)), [(2u64, 3), (3, 2), (5, 1)].map(|(p, e)| (BigUint::from(p), e)));
+        let (n, factors) = semiprime(48, 48);
+        assert_eq!(factor(&n), factors);
+        let p = big("1000000000000000000000000000057");
+        assert_eq!(factor(&(&p * &p * &p * 1001u64)), [(7u64, 1), (11, 1), (13, 1)].map(|(q, e)| (BigUint::from(q), e)).into_iter().chain([(p, 3)]).collect::<Vec<_>>());
+        // F_6 = 2^64 + 1 = 274177 * 67280421310721.
+        assert_eq!(factor(&((BigUint::one() << 64) + 1u64)), vec![(BigUint::from(274177u64), 1), (BigUint::from(67280421310721u64), 1)]);
+    }
+    #[test]
+    fn pkcs1v15_padding() {
+        let mut em = vec![0x00, 0x02, 1, 2, 3, 4, 5, 6, 7, 8, 0x00];
+        em.extend_from_slice(b"message");
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&em).unwrap(), b"message");
+
+        // Wrong first byte, wrong block type, PS one byte too short, no separator at all.
+        let mut bad = em.clone();
+        bad[0] = 0x01;
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&bad), Err(RsaError::Decryption));
+        let mut bad = em.clone();
+        bad[1] = 0x01;
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&bad), Err(RsaError::Decryption));
+        let mut bad = em.clone();
+        bad[9] = 0x00;
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&bad), Err(RsaError::Decryption));
+        let bad = [0x00, 0x02, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&bad), Err(RsaError::Decryption));
+
+        // A separator right at the end is just an empty message.
+        let empty = [0x00, 0x02, 1, 2, 3, 4, 5, 6, 7, 8, 0x00];
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&empty).unwrap(), b"");
+        // Only the first zero after the header counts.
+        let mut em = vec![0x00, 0x02, 1, 2, 3, 4, 5, 6, 7, 8, 0x00, 0x00, 0x05];
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&em).unwrap(), [0x00, 0x05]);
+        em[10] = 0x09;
+        assert_eq!(pkcs1v15::pkcs1v15_decode(&em).unwrap(), [0x05]);
     }
 }
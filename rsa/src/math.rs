@@ -1,24 +1,178 @@
-use bigint::uint::U512;
-
-#[allow(dead_code)]
-pub fn mod_exp(g: U512, a: U512, f: U512) -> U512 {
-    if g == U512::zero() { return U512::zero(); }
-    if a == U512::zero() { return U512::one(); }
-    let is_even = |x : &U512| x.low_u64() & 1 == 0;
-    let mut ret = U512::one();
-    let mut exp = a;
-    let mut x = g;
-    while exp > U512::one() {
-        if is_even(&exp) {
-            x = (x * x) % f;
-            exp = exp >> 1;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+/// Bits of the exponent handled per table lookup in mod_exp_ct. Four bits means a 16 entry table,
+/// which is the usual sweet spot between precomputation and multiplications saved.
+const WINDOW: usize = 4;
+
+/// g^a (mod f). BigUint grows as needed, so the intermediate products can never overflow no matter
+/// how big the modulus is.
+///
+/// This is the fast path and it is NOT constant time: how long it takes depends on the bits of a.
+/// That's fine for public exponents (e, or anything in a primality test), but anything secret has
+/// to go through mod_exp_ct instead.
+pub fn mod_exp(g: &BigUint, a: &BigUint, f: &BigUint) -> BigUint {
+    if g.is_zero() { return BigUint::zero(); }
+    g.modpow(a, f)
+}
+
+/// g^a (mod f) for secret exponents. Square-and-multiply only multiplies when it sees a 1 bit, so
+/// its running time spells out the exponent for anybody with a stopwatch. Here the exponent is
+/// eaten four bits at a time from the top: every window costs exactly four squarings and one
+/// multiplication by table[window], and the table entry is fetched by touching every entry and
+/// keeping the right one with a mask. The only thing left to leak is the bit length of a.
+///
+/// The arithmetic is Montgomery multiplication on fixed-length limb arrays. BigUint trims its
+/// leading zeros after every operation, which would leak the size of each intermediate value.
+///
+/// Input: base (g) -> any number. It's reduced mod f first.
+/// Input: exponent (a) -> the secret exponent.
+/// Input: modulus (f) -> must be odd, which every RSA prime and modulus is.
+///
+/// Output: g^a (mod f).
+pub fn mod_exp_ct(g: &BigUint, a: &BigUint, f: &BigUint) -> BigUint {
+    assert!(f.bit(0), "Montgomery multiplication needs an odd modulus");
+    let m = f.to_u64_digits();
+    let n = m.len();
+    let m_inv = mont_inverse(m[0]);
+
+    // R = 2^(64n). Multiplying by R^2 and reducing once moves a number into Montgomery form.
+    let r2 = to_limbs(&((BigUint::one() << (128 * n)) % f), n);
+    let one = to_limbs(&BigUint::one(), n);
+    let base = mont_mul(&to_limbs(&(g % f), n), &r2, &m, m_inv);
+
+    // table[i] = g^i * R (mod f)
+    let mut table = vec![mont_mul(&one, &r2, &m, m_inv), base.clone()];
+    for i in 2..1 << WINDOW {
+        table.push(mont_mul(&table[i - 1], &base, &m, m_inv));
+    }
+
+    let mut acc = table[0].clone();
+    let windows = (a.bits() as usize).div_ceil(WINDOW);
+    for w in (0..windows).rev() {
+        for _ in 0..WINDOW {
+            acc = mont_mul(&acc, &acc, &m, m_inv);
+        }
+        let digit = (0..WINDOW).fold(0u64, |d, j| d | (a.bit((w * WINDOW + j) as u64) as u64) << j);
+        acc = mont_mul(&acc, &ct_lookup(&table, digit), &m, m_inv);
+    }
+    from_limbs(&mont_mul(&acc, &one, &m, m_inv))
+}
+
+/// a * b / R (mod m) for a, b < m, using the CIOS method (Koc, Acar and Kaliski, 1996). The loops
+/// only depend on the number of limbs and the final subtraction is done with a mask, so the
+/// running time doesn't depend on the values.
+fn mont_mul(a: &[u64], b: &[u64], m: &[u64], m_inv: u64) -> Vec<u64> {
+    let n = m.len();
+    let mut t = vec![0u64; n + 2];
+    for &ai in a {
+        // t += ai * b
+        let mut carry = 0u128;
+        for j in 0..n {
+            let s = t[j] as u128 + ai as u128 * b[j] as u128 + carry;
+            t[j] = s as u64;
+            carry = s >> 64;
+        }
+        let s = t[n] as u128 + carry;
+        t[n] = s as u64;
+        t[n + 1] = (s >> 64) as u64;
+
+        // t = (t + u * m) / 2^64, where u is picked so that the low limb cancels out.
+        let u = t[0].wrapping_mul(m_inv);
+        let mut carry = (t[0] as u128 + u as u128 * m[0] as u128) >> 64;
+        for j in 1..n {
+            let s = t[j] as u128 + u as u128 * m[j] as u128 + carry;
+            t[j - 1] = s as u64;
+            carry = s >> 64;
         }
-        else {
-            ret = (ret * x) % f;
-            x = (x * x) % f;
-            exp = exp >> 1;
+        let s = t[n] as u128 + carry;
+        t[n - 1] = s as u64;
+        t[n] = t[n + 1] + (s >> 64) as u64;
+    }
+
+    // t < 2m here. Compute t - m and keep it unless that borrowed past the top limb.
+    let mut out = vec![0u64; n];
+    let mut borrow = 0u64;
+    for j in 0..n {
+        let (d, b1) = t[j].overflowing_sub(m[j]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        out[j] = d;
+        borrow = (b1 | b2) as u64;
+    }
+    let t_smaller = Choice::from(t[n].overflowing_sub(borrow).1 as u8);
+    for j in 0..n {
+        out[j].conditional_assign(&t[j], t_smaller);
+    }
+    out
+}
+
+/// -m^-1 (mod 2^64) by Newton's iteration. Every step doubles the number of correct low bits, and
+/// x = 1 is already right mod 2 because m is odd.
+fn mont_inverse(m: u64) -> u64 {
+    let mut x = 1u64;
+    for _ in 0..6 {
+        x = x.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(x)));
+    }
+    x.wrapping_neg()
+}
+
+/// table[index] without the memory access pattern depending on index.
+fn ct_lookup(table: &[Vec<u64>], index: u64) -> Vec<u64> {
+    let mut out = vec![0u64; table[0].len()];
+    for (i, entry) in table.iter().enumerate() {
+        let hit = (i as u64).ct_eq(&index);
+        for (o, e) in out.iter_mut().zip(entry) {
+            o.conditional_assign(e, hit);
         }
     }
-    (x * ret) % f
+    out
+}
+
+fn to_limbs(x: &BigUint, n: usize) -> Vec<u64> {
+    let mut limbs = x.to_u64_digits();
+    limbs.resize(n, 0);
+    limbs
+}
+
+fn from_limbs(limbs: &[u64]) -> BigUint {
+    BigUint::from_bytes_le(&limbs.iter().flat_map(|l| l.to_le_bytes()).collect::<Vec<u8>>())
+}
+
+
+/// Uniformly random number of at most "bits" bits.
+pub fn random_bits<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> BigUint {
+    let len = bits.div_ceil(8);
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    if len > 0 { bytes[0] &= 0xff >> (len * 8 - bits); }
+    BigUint::from_bytes_be(&bytes)
+}
+
+/// Uniformly random number in [low, high]. We draw numbers with as many bits as the width of the
+/// range and throw out anything too big, which takes fewer than two draws on average and (unlike
+/// reducing mod the width) doesn't favor small values.
+pub fn random_range<R: CryptoRng + RngCore>(rng: &mut R, low: &BigUint, high: &BigUint) -> BigUint {
+    let width = high - low;
+    loop {
+        let x = random_bits(rng, width.bits() as usize);
+        if x <= width { return low + x; }
+    }
+}
+
+/// I2OSP from RFC 8017: x as a big-endian byte string of exactly "len" bytes, or None if x is too
+/// big to fit.
+pub fn i2osp(x: &BigUint, len: usize) -> Option<Vec<u8>> {
+    if x.bits() as usize > len * 8 { return None; }
+    let mut out = vec![0u8; len];
+    if x.is_zero() { return Some(out); }
+    let bytes = x.to_bytes_be();
+    out[len - bytes.len()..].copy_from_slice(&bytes);
+    Some(out)
 }
 
+/// OS2IP from RFC 8017: reads a big-endian byte string as an integer.
+pub fn os2ip(bytes: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(bytes)
+}
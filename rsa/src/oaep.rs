@@ -0,0 +1,104 @@
+use crate::error::RsaError;
+use crate::hash::{mgf1, HashAlg};
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, os2ip};
+
+use rand::{CryptoRng, RngCore};
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+
+/// RSAES-OAEP encryption (RFC 8017, section 7.1.1). Textbook RSA is deterministic (the same message
+/// always gives the same ciphertext) and malleable (c * 2^e decrypts to 2m). OAEP fixes both by
+/// mixing a random seed into the message with two rounds of a Feistel-like network before the
+/// exponentiation, and lets us encrypt bytes instead of a raw integer.
+///
+/// Input: message (m) -> bytes to encrypt. Can be at most k - 2 * hLen - 2 bytes long, where k is
+/// the size of N in bytes and hLen is the size of the hash.
+/// Input: public key (key) -> recipient's public key.
+/// Input: hash function (hash) -> hash used for the label and for MGF1.
+/// Input: label (label) -> optional label bound to the ciphertext. Decryption only succeeds with
+/// the same label.
+/// Input: random number generator (rng) -> source of the random seed.
+///
+/// Output: ciphertext -> exactly k bytes.
+pub fn rsa_encrypt_oaep<R: CryptoRng + RngCore>(m: &[u8], key: &RsaPublicKey, hash: HashAlg, label: Option<&[u8]>, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let k = key.size();
+    let mut seed = vec![0u8; hash.size()];
+    rng.fill_bytes(&mut seed);
+    let em = oaep_encode(m, k, hash, label.unwrap_or(&[]), &seed)?;
+    let c = key.public_exp(&os2ip(&em));
+    Ok(i2osp(&c, k).expect("c is smaller than N"))
+}
+
+/// RSAES-OAEP decryption (RFC 8017, section 7.1.2). Every check on the decoded block is done
+/// without branching and folded into a single error at the very end, so the caller (or an
+/// attacker timing the caller) can't tell which part of the padding was wrong.
+///
+/// Input: ciphertext (c) -> ciphertext from rsa_encrypt_oaep.
+/// Input: private key (key) -> recipient's private key.
+/// Input: hash function (hash) -> same hash that was used for encryption.
+/// Input: label (label) -> same label that was used for encryption.
+/// Input: random number generator (rng) -> source of the blinding factor.
+///
+/// Output: message -> the decrypted bytes, or RsaError::Decryption.
+pub fn rsa_decrypt_oaep<R: CryptoRng + RngCore>(c: &[u8], key: &RsaPrivateKey, hash: HashAlg, label: Option<&[u8]>, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let k = key.size();
+    if k < 2 * hash.size() + 2 { return Err(RsaError::Decryption); }
+    if c.len() != k { return Err(RsaError::Decryption); }
+    let c = os2ip(c);
+    if c >= *key.n() { return Err(RsaError::Decryption); }
+    let em = i2osp(&key.private_exp(&c, rng), k).ok_or(RsaError::Decryption)?;
+    oaep_decode(&em, hash, label.unwrap_or(&[]))
+}
+
+/// EME-OAEP encoding: EM = 0x00 || maskedSeed || maskedDB, where DB = lHash || PS || 0x01 || M.
+pub fn oaep_encode(m: &[u8], k: usize, hash: HashAlg, label: &[u8], seed: &[u8]) -> Result<Vec<u8>, RsaError> {
+    let h_len = hash.size();
+    if k < 2 * h_len + 2 { return Err(RsaError::KeyTooSmall); }
+    if m.len() > k - 2 * h_len - 2 { return Err(RsaError::MessageTooLong); }
+
+    let mut db = hash.digest(&[label]);
+    db.resize(k - m.len() - h_len - 2, 0);
+    db.push(0x01);
+    db.extend_from_slice(m);
+
+    let db_mask = mgf1(hash, seed, k - h_len - 1);
+    db.iter_mut().zip(db_mask).for_each(|(b, mask)| *b ^= mask);
+    let seed_mask = mgf1(hash, &db, h_len);
+
+    let mut em = Vec::with_capacity(k);
+    em.push(0x00);
+    em.extend(seed.iter().zip(seed_mask).map(|(s, mask)| s ^ mask));
+    em.extend(db);
+    Ok(em)
+}
+
+/// EME-OAEP decoding. The leading zero, the label hash, the zero padding and the 0x01 separator
+/// are all checked in constant time.
+pub fn oaep_decode(em: &[u8], hash: HashAlg, label: &[u8]) -> Result<Vec<u8>, RsaError> {
+    let h_len = hash.size();
+    if em.len() < 2 * h_len + 2 { return Err(RsaError::Decryption); }
+    let (masked_seed, masked_db) = em[1..].split_at(h_len);
+
+    let seed_mask = mgf1(hash, masked_db, h_len);
+    let seed: Vec<u8> = masked_seed.iter().zip(seed_mask).map(|(s, mask)| s ^ mask).collect();
+    let db_mask = mgf1(hash, &seed, masked_db.len());
+    let db: Vec<u8> = masked_db.iter().zip(db_mask).map(|(b, mask)| b ^ mask).collect();
+
+    let mut valid = em[0].ct_eq(&0) & db[..h_len].ct_eq(&hash.digest(&[label]));
+
+    // Scan the whole rest of DB for the first 0x01, remembering where it was and whether anything
+    // other than zeros showed up before it.
+    let mut looking = subtle::Choice::from(1);
+    let mut index = 0u32;
+    for (i, b) in db[h_len..].iter().enumerate() {
+        let is_one = b.ct_eq(&1);
+        let is_zero = b.ct_eq(&0);
+        index.conditional_assign(&(i as u32), looking & is_one);
+        valid &= !looking | is_one | is_zero;
+        looking &= !is_one;
+    }
+    valid &= !looking;
+
+    if valid.unwrap_u8() == 0 { return Err(RsaError::Decryption); }
+    Ok(db[h_len + index as usize + 1..].to_vec())
+}
@@ -0,0 +1,190 @@
+use crate::error::RsaError;
+use crate::euclidian::{gcd, mod_inverse};
+use crate::key::{generate_prime, MAX_MODULUS_BITS, MIN_MODULUS_BITS};
+use crate::math::{mod_exp, mod_exp_ct, random_range};
+use crate::prime::baillie_psw;
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore};
+
+/// A Paillier public key. Ciphertexts live mod N^2, and the generator is fixed to g = N + 1,
+/// which turns g^m into the much cheaper 1 + mN.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaillierPublicKey {
+    n: BigUint,
+    n2: BigUint,
+}
+
+/// A Paillier private key. Decryption works mod p^2 and mod q^2 separately (Paillier's own CRT
+/// trick from section 7 of the paper), so all of its values are worked out once up front.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaillierPrivateKey {
+    public: PaillierPublicKey,
+    p: BigUint,
+    q: BigUint,
+    p2: BigUint,
+    q2: BigUint,
+    /// h_p = L_p(g^(p-1) mod p^2)^-1 (mod p), and the same for q.
+    hp: BigUint,
+    hq: BigUint,
+    /// q^-1 (mod p), to glue m_p and m_q back together.
+    qinv: BigUint,
+}
+
+impl PaillierPublicKey {
+
+    /// Builds a public key out of somebody else's modulus. As with RsaPublicKey::new, all we can
+    /// check is that N isn't obviously nonsense.
+    ///
+    /// Input: public modulus (n) -> odd modulus of at most 16384 bits.
+    ///
+    /// Output: PaillierPublicKey, or RsaError::InvalidKey.
+    pub fn new(n: BigUint) -> Result<Self, RsaError> {
+        if !n.bit(0) || n < BigUint::from(15u64) { return Err(RsaError::InvalidKey("modulus must be an odd composite")); }
+        if n.bits() as usize > MAX_MODULUS_BITS { return Err(RsaError::InvalidKey("modulus is too large")); }
+        let n2 = &n * &n;
+        Ok(Self { n, n2 })
+    }
+
+    /// The public modulus N = pq. Plaintexts are numbers mod N.
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// N^2, the modulus ciphertexts live under.
+    pub fn n_squared(&self) -> &BigUint {
+        &self.n2
+    }
+
+    /// Encrypts m as c = g^m r^N = (1 + mN) r^N (mod N^2) for a random r. The r^N part is what
+    /// makes the scheme probabilistic: the same m encrypts to a different c every time.
+    ///
+    /// Input: message (m) -> plaintext, smaller than N.
+    /// Input: random number generator (rng) -> source of r.
+    ///
+    /// Output: ciphertext (c), or RsaError::MessageTooLong if m doesn't fit.
+    pub fn encrypt<R: CryptoRng + RngCore>(&self, m: &BigUint, rng: &mut R) -> Result<BigUint, RsaError> {
+        if *m >= self.n { return Err(RsaError::MessageTooLong); }
+        let gm = (m * &self.n + 1u64) % &self.n2;
+        Ok(gm * self.random_mask(rng) % &self.n2)
+    }
+
+    /// E(m1) * E(m2) = E(m1 + m2 mod N). Anybody with the public key can add up ciphertexts
+    /// without learning anything about what's inside them.
+    pub fn add(&self, c1: &BigUint, c2: &BigUint) -> BigUint {
+        c1 * c2 % &self.n2
+    }
+
+    /// E(m)^k = E(km mod N), multiplying the plaintext by a known scalar.
+    pub fn mul_plain(&self, c: &BigUint, k: &BigUint) -> BigUint {
+        mod_exp(c, k, &self.n2)
+    }
+
+    /// E(m) * g^k = E(m + k mod N), adding a known plaintext without encrypting it first.
+    pub fn add_plain(&self, c: &BigUint, k: &BigUint) -> BigUint {
+        c * ((k % &self.n) * &self.n + 1u64) % &self.n2
+    }
+
+    /// Multiplies in a fresh r^N. The plaintext stays the same, but the new ciphertext can't be
+    /// linked to the old one. Do this before passing on the result of add or mul_plain, since
+    /// those are deterministic functions of their inputs.
+    pub fn rerandomize<R: CryptoRng + RngCore>(&self, c: &BigUint, rng: &mut R) -> BigUint {
+        c * self.random_mask(rng) % &self.n2
+    }
+
+    /// r^N (mod N^2) for a random unit r.
+    fn random_mask<R: CryptoRng + RngCore>(&self, rng: &mut R) -> BigUint {
+        let r = loop {
+            let r = random_range(rng, &BigUint::one(), &(&self.n - 1u64));
+            if gcd(&r, &self.n).is_one() { break r; }
+        };
+        mod_exp_ct(&r, &self.n, &self.n2)
+    }
+}
+
+impl PaillierPrivateKey {
+
+    /// Generates a fresh Paillier key pair out of two random primes of the same size, using the
+    /// same prime generation as the RSA keys.
+    ///
+    /// Input: random number generator (rng) -> source of randomness for the primes.
+    /// Input: modulus size (bits) -> size of N in bits (2048 or more for real use).
+    ///
+    /// Output: PaillierPrivateKey.
+    pub fn generate<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> Self {
+        assert!((MIN_MODULUS_BITS..=MAX_MODULUS_BITS).contains(&bits), "modulus size out of range");
+        loop {
+            let p = generate_prime(rng, bits / 2);
+            let q = generate_prime(rng, bits - bits / 2);
+            if let Ok(key) = Self::from_primes(p, q) { return key; }
+        }
+    }
+
+    /// Builds a private key from two primes and precomputes the CRT values. Paillier needs
+    /// gcd(pq, (p-1)(q-1)) = 1, which always holds for two distinct primes of the same size.
+    ///
+    /// Input: secret prime #1 (p) -> first prime factor of N.
+    /// Input: secret prime #2 (q) -> second prime factor of N.
+    ///
+    /// Output: PaillierPrivateKey, or RsaError::InvalidKey saying what was wrong.
+    pub fn from_primes(p: BigUint, q: BigUint) -> Result<Self, RsaError> {
+        if p == q { return Err(RsaError::InvalidKey("p and q must be different")); }
+        if !baillie_psw(&p) { return Err(RsaError::InvalidKey("p is not prime")); }
+        if !baillie_psw(&q) { return Err(RsaError::InvalidKey("q is not prime")); }
+        let public = PaillierPublicKey::new(&p * &q)?;
+        if !gcd(&public.n, &((&p - 1u64) * (&q - 1u64))).is_one() { return Err(RsaError::InvalidKey("N is not coprime with φ(N)")); }
+
+        let (p2, q2) = (&p * &p, &q * &q);
+        let g = &public.n + 1u64;
+        let h = |r: &BigUint, r2: &BigUint| mod_inverse(&l(&mod_exp(&g, &(r - 1u64), r2), r), r);
+        let hp = h(&p, &p2).ok_or(RsaError::InvalidKey("N is not coprime with φ(N)"))?;
+        let hq = h(&q, &q2).ok_or(RsaError::InvalidKey("N is not coprime with φ(N)"))?;
+        let qinv = mod_inverse(&q, &p).ok_or(RsaError::InvalidKey("p and q must be different"))?;
+        Ok(Self { public, p, q, p2, q2, hp, hq, qinv })
+    }
+
+    /// The public half of the key.
+    pub fn public_key(&self) -> PaillierPublicKey {
+        self.public.clone()
+    }
+
+    /// The public modulus N = pq.
+    pub fn n(&self) -> &BigUint {
+        &self.public.n
+    }
+
+    /// The first secret prime.
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    /// The second secret prime.
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    /// Decrypts with the CRT: m_p = L_p(c^(p-1) mod p^2) h_p (mod p), the same for q, and m is
+    /// the number mod N that is m_p mod p and m_q mod q. Each half is an exponentiation with a
+    /// half-size exponent mod a half-size modulus, so this is about 4x faster than computing
+    /// L(c^λ mod N^2) μ directly.
+    ///
+    /// Input: ciphertext (c) -> a ciphertext under this key.
+    ///
+    /// Output: message (m), or RsaError::Decryption if c isn't a valid ciphertext at all.
+    pub fn decrypt(&self, c: &BigUint) -> Result<BigUint, RsaError> {
+        if *c >= self.public.n2 || !gcd(c, &self.public.n).is_one() { return Err(RsaError::Decryption); }
+        let mp = l(&mod_exp_ct(c, &(&self.p - 1u64), &self.p2), &self.p) * &self.hp % &self.p;
+        let mq = l(&mod_exp_ct(c, &(&self.q - 1u64), &self.q2), &self.q) * &self.hq % &self.q;
+        // Garner: m = m_q + q * ((m_p - m_q) * qInv mod p).
+        let diff = (&mp + &self.p - &mq % &self.p) % &self.p;
+        Ok(mq + &self.q * (diff * &self.qinv % &self.p))
+    }
+}
+
+/// L_r(x) = (x - 1) / r, which is exact for every x = 1 (mod r). x = 0 can't come out of a
+/// ciphertext coprime with N, so it maps to 0 rather than underflowing.
+fn l(x: &BigUint, r: &BigUint) -> BigUint {
+    if x.is_zero() { return BigUint::zero(); }
+    (x - 1u64) / r
+}
@@ -0,0 +1,74 @@
+use crate::error::RsaError;
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, os2ip};
+
+use rand::{CryptoRng, RngCore};
+use subtle::{ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater};
+
+/// RSAES-PKCS1-v1_5 encryption (RFC 8017, section 7.2.1). This is the older padding scheme that a
+/// lot of legacy systems still speak: EM = 0x00 || 0x02 || PS || 0x00 || M, where PS is at least
+/// eight random nonzero bytes. Prefer OAEP for anything new.
+///
+/// Input: message (m) -> bytes to encrypt. Can be at most k - 11 bytes long.
+/// Input: public key (key) -> recipient's public key.
+/// Input: random number generator (rng) -> source of the padding bytes.
+///
+/// Output: ciphertext -> exactly k bytes, where k is the size of N in bytes.
+pub fn rsa_encrypt_pkcs1v15<R: CryptoRng + RngCore>(m: &[u8], key: &RsaPublicKey, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let k = key.size();
+    if k < 11 { return Err(RsaError::KeyTooSmall); }
+    if m.len() > k - 11 { return Err(RsaError::MessageTooLong); }
+
+    let mut em = vec![0x00, 0x02];
+    while em.len() < k - m.len() - 1 {
+        let mut b = [0u8; 1];
+        rng.fill_bytes(&mut b);
+        if b[0] != 0 { em.push(b[0]); }
+    }
+    em.push(0x00);
+    em.extend_from_slice(m);
+
+    let c = key.public_exp(&os2ip(&em));
+    Ok(i2osp(&c, k).expect("c is smaller than N"))
+}
+
+/// RSAES-PKCS1-v1_5 decryption (RFC 8017, section 7.2.2). Bleichenbacher showed in 1998 that a
+/// server which answers "bad padding" any differently (or any faster) than "bad message" can be
+/// used to decrypt arbitrary ciphertexts, one oracle query at a time. So the padding check below
+/// walks the whole block no matter what it finds, and all the checks are folded into a single
+/// flag that is only looked at once at the end.
+///
+/// Input: ciphertext (c) -> ciphertext from rsa_encrypt_pkcs1v15.
+/// Input: private key (key) -> recipient's private key.
+/// Input: random number generator (rng) -> source of the blinding factor.
+///
+/// Output: message -> the decrypted bytes, or RsaError::Decryption.
+pub fn rsa_decrypt_pkcs1v15<R: CryptoRng + RngCore>(c: &[u8], key: &RsaPrivateKey, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let k = key.size();
+    if k < 11 || c.len() != k { return Err(RsaError::Decryption); }
+    let c = os2ip(c);
+    if c >= *key.n() { return Err(RsaError::Decryption); }
+    let em = i2osp(&key.private_exp(&c, rng), k).ok_or(RsaError::Decryption)?;
+    pkcs1v15_decode(&em)
+}
+
+/// Checks EM = 0x00 || 0x02 || PS || 0x00 || M in constant time and pulls out M.
+pub fn pkcs1v15_decode(em: &[u8]) -> Result<Vec<u8>, RsaError> {
+    if em.len() < 11 { return Err(RsaError::Decryption); }
+    let mut valid = em[0].ct_eq(&0x00) & em[1].ct_eq(&0x02);
+
+    // Find the first zero byte after the header without stopping at it.
+    let mut looking = subtle::Choice::from(1);
+    let mut index = 0u32;
+    for (i, b) in em.iter().enumerate().skip(2) {
+        let is_zero = b.ct_eq(&0);
+        index.conditional_assign(&(i as u32), looking & is_zero);
+        looking &= !is_zero;
+    }
+    valid &= !looking;
+    // PS has to be at least 8 bytes, so the separator can't come before index 10.
+    valid &= index.ct_gt(&9);
+
+    if valid.unwrap_u8() == 0 { return Err(RsaError::Decryption); }
+    Ok(em[index as usize + 1..].to_vec())
+}
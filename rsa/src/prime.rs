@@ -0,0 +1,248 @@
+use crate::math::{mod_exp, random_range};
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore};
+
+/// Primes below 2^8, used for trial division before we bother with any of the heavier tests.
+pub const SMALL_PRIMES: [u64; 54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// Every prime up to and including "bound", with the sieve of Eratosthenes.
+#[allow(dead_code)]
+pub fn sieve(bound: u64) -> Vec<u64> {
+    let bound = bound as usize;
+    let mut composite = vec![false; bound + 1];
+    let mut primes = Vec::new();
+    for i in 2..=bound {
+        if composite[i] { continue; }
+        primes.push(i as u64);
+        (i.saturating_mul(i)..=bound).step_by(i).for_each(|j| composite[j] = true);
+    }
+    primes
+}
+
+/// Which primality test to run. Miller-Rabin is probabilistic (with random bases), so the more
+/// rounds we run the less likely it is that a composite sneaks through. Baillie-PSW is
+/// deterministic and there is no known composite that passes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Primality {
+    MillerRabin,
+    BailliePsw,
+}
+
+/// Runs the chosen primality test on n.
+///
+/// Input: candidate (n) -> number to test.
+/// Input: test (mode) -> which test to run.
+/// Input: random number generator (rng) -> source of random bases for Miller-Rabin.
+///
+/// Output: bool -> true if n is (probably) prime.
+pub fn is_prime<R: CryptoRng + RngCore>(n: &BigUint, mode: Primality, rng: &mut R) -> bool {
+    match mode {
+        Primality::MillerRabin => is_probable_prime(n, rng),
+        Primality::BailliePsw => baillie_psw(n),
+    }
+}
+
+/// Miller-Rabin with the number of rounds picked from the bit length of n. The bigger n is, the
+/// less likely a random composite is to pass even a single round, so we can get away with fewer.
+///
+/// Input: candidate (n) -> number to test.
+/// Input: random number generator (rng) -> source of random bases.
+///
+/// Output: bool -> true if n is probably prime.
+pub fn is_probable_prime<R: CryptoRng + RngCore>(n: &BigUint, rng: &mut R) -> bool {
+    match trial_division(n) {
+        Some(res) => res,
+        None => miller_rabin(n, mr_rounds(n.bits() as usize), rng),
+    }
+}
+
+/// Number of Miller-Rabin rounds needed to get the error probability for a random k-bit candidate
+/// below 2^-80 (Handbook of Applied Cryptography, table 4.4). Below 100 bits we just run a lot of
+/// rounds since they are cheap anyway.
+pub fn mr_rounds(bits: usize) -> usize {
+    match bits {
+        0..=99 => 40,
+        100..=149 => 27,
+        150..=199 => 18,
+        200..=249 => 15,
+        250..=299 => 12,
+        300..=349 => 9,
+        350..=399 => 8,
+        400..=449 => 7,
+        450..=549 => 6,
+        550..=649 => 5,
+        650..=849 => 4,
+        850..=1299 => 3,
+        _ => 2,
+    }
+}
+
+/// Miller-Rabin using "rounds" bases drawn uniformly from [2, n-2]. Write n - 1 = d * 2^s with d
+/// odd. For a prime n, every base a has either a^d = 1 or a^(d * 2^r) = -1 for some r < s, since
+/// the only square roots of 1 mod a prime are 1 and -1. Unlike the plain Fermat test, a composite
+/// passes for at most a quarter of all bases, so there is no Carmichael number equivalent.
+///
+/// Input: candidate (n) -> odd number greater than 3 to test.
+/// Input: rounds (rounds) -> number of random bases to try.
+/// Input: random number generator (rng) -> source of random bases.
+///
+/// Output: bool -> false if a witness for compositeness was found.
+pub fn miller_rabin<R: CryptoRng + RngCore>(n: &BigUint, rounds: usize, rng: &mut R) -> bool {
+    let (two, high) = (BigUint::from(2u64), n - 2u64);
+    for _ in 0..rounds {
+        let a = random_range(rng, &two, &high);
+        if !strong_probable_prime(n, &a) { return false; }
+    }
+    true
+}
+
+/// Baillie-PSW: a strong probable prime test to base 2 followed by a strong Lucas probable prime
+/// test. The two tests fail on very different sets of composites (nobody has found a number that
+/// fools both), which makes this the test of choice when we have no rng to pick bases with.
+///
+/// Input: candidate (n) -> number to test.
+///
+/// Output: bool -> true if n is prime (as far as anybody knows).
+pub fn baillie_psw(n: &BigUint) -> bool {
+    if let Some(res) = trial_division(n) { return res; }
+    if !strong_probable_prime(n, &BigUint::from(2u64)) { return false; }
+    strong_lucas_probable_prime(n)
+}
+
+/// Handles the small and even cases. Returns None if n still needs a real test.
+fn trial_division(n: &BigUint) -> Option<bool> {
+    if *n < BigUint::from(2u64) { return Some(false); }
+    for &p in SMALL_PRIMES.iter() {
+        if *n == BigUint::from(p) { return Some(true); }
+        if (n % p).is_zero() { return Some(false); }
+    }
+    None
+}
+
+/// One round of Miller-Rabin with the base a.
+fn strong_probable_prime(n: &BigUint, a: &BigUint) -> bool {
+    let n1 = n - 1u64;
+    let s = n1.trailing_zeros().expect("n is odd and greater than 1");
+    let d = &n1 >> s;
+    let mut x = mod_exp(a, &d, n);
+    if x.is_one() || x == n1 { return true; }
+    for _ in 1..s {
+        x = (&x * &x) % n;
+        if x == n1 { return true; }
+        if x.is_one() { return false; }
+    }
+    false
+}
+
+/// Strong Lucas probable prime test with the parameters from Selfridge's method A: D is the first
+/// of 5, -7, 9, -11, ... with Jacobi symbol (D/n) = -1, P = 1 and Q = (1 - D) / 4. Writing
+/// n + 1 = d * 2^s with d odd, n passes if U_d = 0 or V_(d * 2^r) = 0 (mod n) for some r < s.
+pub fn strong_lucas_probable_prime(n: &BigUint) -> bool {
+    // A perfect square never gives (D/n) = -1, so the search below would never end.
+    if is_square(n) { return false; }
+
+    // D is stored as its magnitude and sign, and everything else as residues mod n.
+    let mut d_abs = 5u64;
+    let mut d_neg = false;
+    loop {
+        let d_mod = signed_mod(d_abs, d_neg, n);
+        match jacobi(&d_mod, n) {
+            -1 => break,
+            0 if BigUint::from(d_abs) != *n => return false,
+            _ => {}
+        }
+        d_abs += 2;
+        d_neg = !d_neg;
+    }
+    let d_mod = signed_mod(d_abs, d_neg, n);
+    // Q = (1 - D) / 4, which is an integer since D = 1 (mod 4).
+    let q_mod = if d_neg {
+        BigUint::from((1 + d_abs) / 4) % n
+    } else {
+        signed_mod((d_abs - 1) / 4, true, n)
+    };
+
+    let half = |x: BigUint| if !x.bit(0) { x >> 1 } else { (x + n) >> 1 };
+    let add = |a: &BigUint, b: &BigUint| (a + b) % n;
+    let sub = |a: BigUint, b: BigUint| if a >= b { a - b } else { n - (b - a) };
+
+    let n1 = n + 1u64;
+    let s = n1.trailing_zeros().expect("n + 1 is positive");
+    let k = &n1 >> s;
+
+    // Walk down the bits of k, starting from U_1 = 1, V_1 = P = 1, Q^1 = Q.
+    let (mut u, mut v, mut qk) = (BigUint::one(), BigUint::one(), q_mod.clone());
+    for i in (0..k.bits() - 1).rev() {
+        u = (&u * &v) % n;
+        v = sub((&v * &v) % n, add(&qk, &qk));
+        qk = (&qk * &qk) % n;
+        if k.bit(i) {
+            let (u_old, v_old) = (u, v);
+            u = half(add(&u_old, &v_old));
+            v = half(add(&((&d_mod * &u_old) % n), &v_old));
+            qk = (&qk * &q_mod) % n;
+        }
+    }
+
+    if u.is_zero() || v.is_zero() { return true; }
+    for _ in 1..s {
+        v = sub((&v * &v) % n, add(&qk, &qk));
+        if v.is_zero() { return true; }
+        qk = (&qk * &qk) % n;
+    }
+    false
+}
+
+/// The residue of the small signed integer (-1)^neg * a modulo n.
+fn signed_mod(a: u64, neg: bool, n: &BigUint) -> BigUint {
+    let a = BigUint::from(a) % n;
+    if neg && !a.is_zero() { n - a } else { a }
+}
+
+/// The Jacobi symbol (a/n) for odd n, computed with quadratic reciprocity so we never have to
+/// factor n. For a prime n this is the Legendre symbol: 1 if a is a square mod n, -1 if it isn't
+/// and 0 if n divides a.
+pub fn jacobi(a: &BigUint, n: &BigUint) -> i32 {
+    assert!(n.bit(0), "jacobi symbol is only defined for odd n");
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut res = 1;
+    while !a.is_zero() {
+        let tz = a.trailing_zeros().expect("a is not zero");
+        a >>= tz;
+        let n_mod_8 = low_u64(&n) & 7;
+        if tz & 1 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) { res = -res; }
+        if low_u64(&a) & 3 == 3 && n_mod_8 & 3 == 3 { res = -res; }
+        std::mem::swap(&mut a, &mut n);
+        a %= &n;
+    }
+    if n.is_one() { res } else { 0 }
+}
+
+/// Floor of the square root of n by Newton's method.
+pub fn isqrt(n: &BigUint) -> BigUint {
+    if *n < BigUint::from(2u64) { return n.clone(); }
+    let mut x = BigUint::one() << n.bits().div_ceil(2);
+    loop {
+        let y = (&x + n / &x) >> 1;
+        if y >= x { return x; }
+        x = y;
+    }
+}
+
+/// True if n is a perfect square.
+pub fn is_square(n: &BigUint) -> bool {
+    let r = isqrt(n);
+    &r * &r == *n
+}
+
+/// The lowest 64 bits of n.
+fn low_u64(n: &BigUint) -> u64 {
+    n.iter_u64_digits().next().unwrap_or(0)
+}
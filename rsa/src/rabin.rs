@@ -0,0 +1,278 @@
+use crate::error::RsaError;
+use crate::euclidian::mod_inverse;
+use crate::hash::{mgf1, HashAlg};
+use crate::key::{generate_prime, too_close, MAX_MODULUS_BITS, MIN_MODULUS_BITS};
+use crate::math::{i2osp, mod_exp, mod_exp_ct, os2ip};
+use crate::prime::{baillie_psw, jacobi};
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore};
+
+/// Number of hash bytes appended to every Rabin plaintext. Squaring has four roots, and these are
+/// how decryption tells the real one apart from the other three.
+pub const RABIN_TAG_LEN: usize = 8;
+
+/// A Rabin public key: just N. Encrypting is a single squaring and verifying a signature not much
+/// more, which is why Rabin is a good fit for verifiers with next to no cpu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RabinPublicKey {
+    n: BigUint,
+}
+
+/// A Rabin private key. p = 3 (mod 8) and q = 7 (mod 8), so N is a Blum integer (both primes are
+/// 3 mod 4, which makes square roots one exponentiation each) and also a Williams integer (2 is a
+/// square mod q but not mod p, which is what the tweak in Rabin-Williams signatures needs).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RabinPrivateKey {
+    public: RabinPublicKey,
+    p: BigUint,
+    q: BigUint,
+    /// q^-1 (mod p), to glue the roots mod p and mod q back together.
+    qinv: BigUint,
+}
+
+/// A Rabin-Williams signature: s along with the tweaks e (1 or -1) and f (1 or 2), so that
+/// e * f * s^2 = H(m) (mod N). Only one of the four combinations of e and f turns H(m) into a
+/// square, which is how every message gets signed without having to retry with a new hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RabinSignature {
+    /// e = -1 instead of 1.
+    pub negate: bool,
+    /// f = 2 instead of 1.
+    pub double: bool,
+    /// The principal square root of H(m) / (e f).
+    pub s: BigUint,
+}
+
+impl RabinPublicKey {
+
+    /// Builds a public key out of somebody else's modulus.
+    ///
+    /// Input: public modulus (n) -> odd modulus of at most 16384 bits.
+    ///
+    /// Output: RabinPublicKey, or RsaError::InvalidKey.
+    pub fn new(n: BigUint) -> Result<Self, RsaError> {
+        // p = 3 and q = 7 (mod 8) means N = 5 (mod 8).
+        if n.bits() < 8 || (&n % 8u64) != BigUint::from(5u64) { return Err(RsaError::InvalidKey("modulus must be p * q with p = 3 and q = 7 (mod 8)")); }
+        if n.bits() as usize > MAX_MODULUS_BITS { return Err(RsaError::InvalidKey("modulus is too large")); }
+        Ok(Self { n })
+    }
+
+    /// The public modulus N = pq.
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// Size of the modulus in bytes, which is also the size of every ciphertext.
+    pub fn size(&self) -> usize {
+        (self.n.bits() as usize).div_ceil(8)
+    }
+
+    /// Rabin encryption: c = x^2 (mod N), where x = 0x01 || m || the first RABIN_TAG_LEN bytes
+    /// of SHA-256(m). The tag is the redundancy that picks out the right root on decryption, and
+    /// the 0x01 marks where the message starts.
+    ///
+    /// Note that, like textbook RSA, this is deterministic: the same message always encrypts to the
+    /// same ciphertext.
+    ///
+    /// Input: message (m) -> bytes to encrypt, at most k - 10 of them.
+    ///
+    /// Output: ciphertext -> exactly k bytes, or RsaError::MessageTooLong.
+    pub fn encrypt(&self, m: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let k = self.size();
+        // One zero byte on top keeps x below N.
+        if m.len() + RABIN_TAG_LEN + 2 > k { return Err(RsaError::MessageTooLong); }
+        let x = os2ip(&[&[0x01], m, &HashAlg::Sha256.digest(&[m])[..RABIN_TAG_LEN]].concat());
+        Ok(i2osp(&(&x * &x % &self.n), k).expect("c is smaller than N"))
+    }
+
+    /// Checks a Rabin-Williams signature: e * f * s^2 = H(m) (mod N). That's one squaring and
+    /// one multiplication, cheaper even than RSA with e = 3.
+    ///
+    /// Input: message (msg) -> the message that was supposedly signed.
+    /// Input: signature (sig) -> signature to check.
+    ///
+    /// Output: bool -> true means signature is valid. False means that it is invalid.
+    pub fn verify(&self, msg: &[u8], sig: &RabinSignature) -> bool {
+        if sig.s >= self.n { return false; }
+        let mut x = &sig.s * &sig.s % &self.n;
+        if sig.double { x = (x << 1) % &self.n; }
+        if sig.negate && !x.is_zero() { x = &self.n - x; }
+        x == self.hash(msg)
+    }
+
+    /// Full-domain hash of the message: MGF1 with SHA-256 stretched to k - 1 bytes, so it is
+    /// always smaller than N.
+    fn hash(&self, msg: &[u8]) -> BigUint {
+        os2ip(&mgf1(HashAlg::Sha256, &HashAlg::Sha256.digest(&[msg]), self.size() - 1))
+    }
+}
+
+impl RabinPrivateKey {
+
+    /// Generates a key with p = 3 (mod 8) and q = 7 (mod 8), out of the same random primes that
+    /// RSA keys use (we just keep drawing until each one lands in the right class).
+    ///
+    /// Input: random number generator (rng) -> source of randomness for the primes.
+    /// Input: modulus size (bits) -> size of N in bits.
+    ///
+    /// Output: RabinPrivateKey.
+    pub fn generate<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> Self {
+        assert!((MIN_MODULUS_BITS..=MAX_MODULUS_BITS).contains(&bits), "modulus size out of range");
+        let mut prime_mod_8 = |bits: usize, class: u64| loop {
+            let r = generate_prime(rng, bits);
+            if &r % 8u64 == BigUint::from(class) { return r; }
+        };
+        loop {
+            let p = prime_mod_8(bits / 2, 3);
+            let q = prime_mod_8(bits - bits / 2, 7);
+            if too_close(&p, &q, bits) { continue; }
+            return Self::from_primes(p, q).expect("generated primes are valid");
+        }
+    }
+
+    /// Builds a private key from its two primes, in either order.
+    ///
+    /// Input: secret prime #1 (p) -> one prime factor of N.
+    /// Input: secret prime #2 (q) -> the other one. One of them has to be 3 (mod 8) and the other
+    /// 7 (mod 8).
+    ///
+    /// Output: RabinPrivateKey, or RsaError::InvalidKey saying what was wrong.
+    pub fn from_primes(p: BigUint, q: BigUint) -> Result<Self, RsaError> {
+        let (p, q) = if &p % 8u64 == BigUint::from(7u64) { (q, p) } else { (p, q) };
+        if &p % 8u64 != BigUint::from(3u64) || &q % 8u64 != BigUint::from(7u64) {
+            return Err(RsaError::InvalidKey("primes must be 3 and 7 (mod 8)"));
+        }
+        if !baillie_psw(&p) || !baillie_psw(&q) { return Err(RsaError::InvalidKey("primes must be prime")); }
+        let public = RabinPublicKey::new(&p * &q)?;
+        let qinv = mod_inverse(&q, &p).expect("p and q are different primes");
+        Ok(Self { public, p, q, qinv })
+    }
+
+    /// The public half of the key.
+    pub fn public_key(&self) -> RabinPublicKey {
+        self.public.clone()
+    }
+
+    /// The public modulus N = pq.
+    pub fn n(&self) -> &BigUint {
+        &self.public.n
+    }
+
+    /// The prime that is 3 (mod 8).
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    /// The prime that is 7 (mod 8).
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+
+    /// All four square roots of x mod N, principal root first, or None if x isn't a square. The
+    /// principal root is the one that is itself a square, and it's the only one sign hands out:
+    /// two different roots of the same number give away the factors of N (gcd(r1 - r2, N) = p).
+    pub fn sqrt(&self, x: &BigUint) -> Option<[BigUint; 4]> {
+        let (p, q, n) = (&self.p, &self.q, &self.public.n);
+        let rp = sqrt_mod_blum(x, p)?;
+        let rq = sqrt_mod_blum(x, q)?;
+        let crt = |a: &BigUint, b: &BigUint| {
+            let diff = (a + p - b % p) % p;
+            b + q * (diff * &self.qinv % p)
+        };
+        let neg = |r: &BigUint, m: &BigUint| if r.is_zero() { BigUint::zero() } else { m - r };
+        let principal = crt(&rp, &rq);
+        let mixed = crt(&rp, &neg(&rq, q));
+        Some([principal.clone(), neg(&principal, n), mixed.clone(), neg(&mixed, n)])
+    }
+
+    /// Rabin decryption: takes all four square roots of c and keeps the one whose tag matches.
+    ///
+    /// Input: ciphertext (c) -> exactly k bytes.
+    ///
+    /// Output: message, or RsaError::Decryption if c isn't a square or no root (or more than one)
+    /// carries a valid tag.
+    pub fn decrypt(&self, c: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let k = self.public.size();
+        if c.len() != k { return Err(RsaError::Decryption); }
+        let c = os2ip(c);
+        if c >= self.public.n { return Err(RsaError::Decryption); }
+        let roots = self.sqrt(&c).ok_or(RsaError::Decryption)?;
+        let mut found = roots.iter().filter_map(|x| {
+            let x = i2osp(x, k)?;
+            let (m, tag) = x.split_at(k - RABIN_TAG_LEN);
+            let start = m.iter().position(|&b| b != 0)?;
+            if m[start] != 0x01 || start == 0 { return None; }
+            let m = &m[start + 1..];
+            (HashAlg::Sha256.digest(&[m])[..RABIN_TAG_LEN] == *tag).then(|| m.to_vec())
+        });
+        match (found.next(), found.next()) {
+            (Some(m), None) => Ok(m),
+            _ => Err(RsaError::Decryption),
+        }
+    }
+
+    /// Rabin-Williams signing. H(m) is a square mod N only a quarter of the time, but with p = 3
+    /// and q = 7 (mod 8), -1 is a non-square mod both primes and 2 is a non-square mod p only, so
+    /// exactly one of H(m), -H(m), H(m)/2 and -H(m)/2 is a square. We sign that one with its
+    /// principal root, so signing the same message twice always gives the same signature.
+    ///
+    /// Input: message (msg) -> bytes to sign. These are hashed for you.
+    ///
+    /// Output: RabinSignature.
+    pub fn sign(&self, msg: &[u8]) -> RabinSignature {
+        let n = &self.public.n;
+        let h = self.public.hash(msg);
+        let negate = jacobi(&h, &self.q) == -1;
+        let mut x = if negate { n - &h } else { h };
+        let double = jacobi(&x, &self.p) == -1;
+        if double { x = x * mod_inverse(&BigUint::from(2u64), n).expect("N is odd") % n; }
+        let s = self.sqrt(&x).expect("exactly one tweak makes H(m) a square")[0].clone();
+        RabinSignature { negate, double, s }
+    }
+}
+
+/// Square root mod a prime r = 3 (mod 4): x^((r + 1) / 4), which is itself a square. None if x
+/// isn't a square mod r.
+fn sqrt_mod_blum(x: &BigUint, r: &BigUint) -> Option<BigUint> {
+    let x = x % r;
+    let root = mod_exp_ct(&x, &((r + 1u64) >> 2), r);
+    (&root * &root % r == x).then_some(root)
+}
+
+/// A square root of a mod the odd prime p, or None if a isn't a square mod p. Primes that are
+/// 3 (mod 4) take one exponentiation; everything else goes through Tonelli-Shanks.
+///
+/// Input: number (a) -> any BigUint.
+/// Input: prime (p) -> an odd prime.
+///
+/// Output: Some(x) with x^2 = a (mod p) and x < p, or None.
+pub fn sqrt_mod_prime(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let a = a % p;
+    if a.is_zero() { return Some(a); }
+    if jacobi(&a, p) != 1 { return None; }
+    if p.bit(1) { return sqrt_mod_blum(&a, p); }
+
+    // p - 1 = q 2^s with q odd, and z is any non-square.
+    let p1 = p - 1u64;
+    let s = p1.trailing_zeros().expect("p is odd");
+    let q = &p1 >> s;
+    let z = (2u64..).map(BigUint::from).find(|z| jacobi(z, p) == -1).expect("half of all numbers are non-squares");
+    let (mut m, mut c) = (s, mod_exp(&z, &q, p));
+    let (mut t, mut r) = (mod_exp(&a, &q, p), mod_exp(&a, &((&q + 1u64) >> 1), p));
+    while !t.is_one() {
+        // The smallest i with t^(2^i) = 1.
+        let (mut i, mut t2) = (0, t.clone());
+        while !t2.is_one() {
+            t2 = &t2 * &t2 % p;
+            i += 1;
+        }
+        let b = mod_exp(&c, &(BigUint::one() << (m - i - 1)), p);
+        m = i;
+        c = &b * &b % p;
+        t = t * &c % p;
+        r = r * b % p;
+    }
+    Some(r)
+}
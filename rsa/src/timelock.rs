@@ -0,0 +1,102 @@
+use crate::error::RsaError;
+use crate::kem::cipher;
+use crate::key::{carmichael_lambda, RsaPrivateKey};
+use crate::math::{i2osp, mod_exp, mod_exp_ct, random_range};
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::Nonce;
+use num_bigint::BigUint;
+use rand::{CryptoRng, RngCore};
+
+/// A Rivest-Shamir-Wagner time-lock puzzle: a message that anybody can open, but only after doing
+/// T squarings mod N one after another. Squarings can't be done in parallel (as far as anybody
+/// knows), so T sets a lower bound on the wall-clock time it takes no matter how much hardware the
+/// solver has. That's what a sealed-bid auction needs: bids that open themselves after the
+/// deadline, without anybody having to trust the bidders to reveal them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeLockPuzzle {
+    n: BigUint,
+    a: BigUint,
+    t: u64,
+    ciphertext: Vec<u8>,
+}
+
+impl TimeLockPuzzle {
+
+    /// Locks a message for T squarings. The creator knows the factors of N, and with them
+    /// λ(N), so a^(2^T) = a^(2^T mod λ(N)) takes one exponentiation instead of T squarings. That
+    /// value is turned into an AES-256-GCM key (KDF2 with SHA-256, same as seal) that encrypts
+    /// the message.
+    ///
+    /// Input: private key (key) -> an RSA key whose factorization is the trapdoor. Whoever holds
+    /// it can open every puzzle made with it right away, so use a fresh key or one that nobody
+    /// else has.
+    /// Input: message (msg) -> bytes to lock.
+    /// Input: squarings (t) -> how many squarings the solver has to do.
+    /// Input: random number generator (rng) -> source of a.
+    ///
+    /// Output: TimeLockPuzzle.
+    pub fn create<R: CryptoRng + RngCore>(key: &RsaPrivateKey, msg: &[u8], t: u64, rng: &mut R) -> Self {
+        let n = key.n();
+        let a = random_range(rng, &BigUint::from(2u64), &(n - 2u64));
+        let e = mod_exp(&BigUint::from(2u64), &BigUint::from(t), &carmichael_lambda(&key.primes()));
+        let b = mod_exp_ct(&a, &e, n);
+        let ciphertext = puzzle_cipher(&b, n).encrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg, aad: &t.to_be_bytes() })
+            .expect("AES-GCM can encrypt anything this size");
+        Self { n: n.clone(), a, t, ciphertext }
+    }
+
+    /// Rebuilds a puzzle that somebody else created, e.g. after receiving it over the wire.
+    /// Nothing can be checked here; a puzzle that doesn't add up just fails to solve.
+    ///
+    /// Input: public modulus (n) -> the creator's N.
+    /// Input: starting point (a) -> the base of the squarings.
+    /// Input: squarings (t) -> how many squarings it takes.
+    /// Input: ciphertext (ciphertext) -> the locked message.
+    ///
+    /// Output: TimeLockPuzzle.
+    pub fn from_parts(n: BigUint, a: BigUint, t: u64, ciphertext: Vec<u8>) -> Self {
+        Self { n, a, t, ciphertext }
+    }
+
+    /// The modulus N the squarings are done under.
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// The starting point a.
+    pub fn a(&self) -> &BigUint {
+        &self.a
+    }
+
+    /// The number of squarings T.
+    pub fn t(&self) -> u64 {
+        self.t
+    }
+
+    /// The encrypted message.
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    /// Opens the puzzle the honest way, with T squarings in a row.
+    ///
+    /// Output: message, or RsaError::Decryption if the puzzle was tampered with.
+    pub fn solve(&self) -> Result<Vec<u8>, RsaError> {
+        let b = square_repeatedly(&self.a, self.t, &self.n);
+        puzzle_cipher(&b, &self.n).decrypt(Nonce::from_slice(&[0u8; 12]), Payload { msg: &self.ciphertext, aad: &self.t.to_be_bytes() })
+            .map_err(|_| RsaError::Decryption)
+    }
+}
+
+/// x^(2^t) (mod n) by t squarings, which is all anybody without the factors of n can do.
+pub fn square_repeatedly(x: &BigUint, t: u64, n: &BigUint) -> BigUint {
+    (0..t).fold(x % n, |y, _| &y * &y % n)
+}
+
+/// AES-256-GCM keyed with KDF2(b). Every puzzle has its own a, so the key is never reused and the
+/// nonce can stay all zeros.
+fn puzzle_cipher(b: &BigUint, n: &BigUint) -> aes_gcm::Aes256Gcm {
+    let k = (n.bits() as usize).div_ceil(8);
+    cipher(&i2osp(b, k).expect("b is smaller than N"))
+}
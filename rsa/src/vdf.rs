@@ -0,0 +1,133 @@
+use crate::euclidian::mod_inverse;
+use crate::hash::{mgf1, HashAlg};
+use crate::key::{carmichael_lambda, RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, mod_exp, os2ip};
+use crate::prime::baillie_psw;
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+/// Size of the Fiat-Shamir prime l in bits. Forging a proof means guessing l in advance, so 128
+/// bits is plenty.
+const CHALLENGE_BITS: usize = 128;
+
+/// The output of a Wesolowski VDF: y = g^(2^T) and the proof π = g^(floor(2^T / l)).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VdfOutput {
+    /// y = g^(2^T), up to sign.
+    pub y: BigUint,
+    /// π = g^(floor(2^T / l)), up to sign.
+    pub proof: BigUint,
+}
+
+/// Evaluates Wesolowski's verifiable delay function: y = g^(2^T) (mod N) with g a hash of the
+/// input, along with a proof that anybody can check in two small exponentiations instead of T
+/// squarings. Nobody is supposed to know the factors of N here (otherwise they could skip the
+/// squarings, see vdf_eval_trapdoor), so N should come from a setup whose key was thrown away.
+///
+/// Everything is done in Z_N^* / {1, -1}: every element is stored as whichever of x and N - x is
+/// smaller. Without that, (-y, -π) would verify just as well as (y, π) and the output wouldn't
+/// be unique.
+///
+/// The proof takes another T steps: π is computed bit by bit, doing the long division of 2^T by
+/// l on the fly so 2^T never has to be written down.
+///
+/// Input: public key (key) -> the modulus N (e is ignored).
+/// Input: input (x) -> bytes to hash to the starting point g.
+/// Input: delay (t) -> how many squarings it takes.
+///
+/// Output: VdfOutput.
+pub fn vdf_eval(key: &RsaPublicKey, x: &[u8], t: u64) -> VdfOutput {
+    let n = key.n();
+    let g = hash_to_group(n, x);
+    let y = canonical(&(0..t).fold(g.clone(), |y, _| &y * &y % n), n);
+    let l = hash_to_prime(n, &g, &y, t);
+
+    // floor(2^T / l) one bit at a time: r is the running remainder, and every bit of the
+    // quotient that comes out as 1 multiplies g in.
+    let (mut proof, mut r) = (BigUint::one(), BigUint::one());
+    for _ in 0..t {
+        r <<= 1;
+        proof = &proof * &proof % n;
+        if r >= l {
+            r -= &l;
+            proof = proof * &g % n;
+        }
+    }
+    VdfOutput { y, proof: canonical(&proof, n) }
+}
+
+/// The same output as vdf_eval, computed in no time at all by whoever has the factors of N: with
+/// λ(N), 2^T and floor(2^T / l) can both be reduced mod λ(N) before exponentiating.
+///
+/// Input: private key (key) -> the key behind N.
+/// Input: input (x) -> bytes to hash to the starting point g.
+/// Input: delay (t) -> how many squarings everybody else has to do.
+///
+/// Output: VdfOutput, identical to what vdf_eval returns.
+pub fn vdf_eval_trapdoor(key: &RsaPrivateKey, x: &[u8], t: u64) -> VdfOutput {
+    let n = key.n();
+    let lambda = carmichael_lambda(&key.primes());
+    let g = hash_to_group(n, x);
+    let two_t = mod_exp(&BigUint::from(2u64), &BigUint::from(t), &lambda);
+    let y = canonical(&mod_exp(&g, &two_t, n), n);
+    let l = hash_to_prime(n, &g, &y, t);
+    // 2^T = q l + r, so q = (2^T - r) / l. l is a random 128-bit prime, so it divides λ(N) with
+    // negligible probability; if it ever does, fall back to the slow way.
+    let r = mod_exp(&BigUint::from(2u64), &BigUint::from(t), &l);
+    let l_inv = match mod_inverse(&l, &lambda) {
+        Some(l_inv) => l_inv,
+        None => return vdf_eval(&key.public_key(), x, t),
+    };
+    let q = (two_t + &lambda - &r % &lambda) % &lambda * l_inv % &lambda;
+    VdfOutput { y, proof: canonical(&mod_exp(&g, &q, n), n) }
+}
+
+/// Checks a VDF output: π^l g^r = y (mod N, up to sign), where r = 2^T mod l. Two
+/// exponentiations with 128-bit exponents, no matter how big T is.
+///
+/// Input: public key (key) -> the modulus N.
+/// Input: input (x) -> the bytes the output is supposed to be for.
+/// Input: delay (t) -> the delay the output is supposed to be for.
+/// Input: output (output) -> the claimed y and proof.
+///
+/// Output: bool -> true if y really is g^(2^T).
+pub fn vdf_verify(key: &RsaPublicKey, x: &[u8], t: u64, output: &VdfOutput) -> bool {
+    let n = key.n();
+    let half = n >> 1;
+    for z in [&output.y, &output.proof] {
+        if z.is_zero() || *z > half { return false; }
+    }
+    let g = hash_to_group(n, x);
+    let l = hash_to_prime(n, &g, &output.y, t);
+    let r = mod_exp(&BigUint::from(2u64), &BigUint::from(t), &l);
+    let lhs = mod_exp(&output.proof, &l, n) * mod_exp(&g, &r, n) % n;
+    canonical(&lhs, n) == output.y
+}
+
+/// The smaller of x and N - x, which stands in for the pair {x, -x}.
+fn canonical(x: &BigUint, n: &BigUint) -> BigUint {
+    let neg = n - x;
+    if neg < *x { neg } else { x.clone() }
+}
+
+/// g = MGF1-SHA256(N || x) reduced mod N. Hashing N in as well means the same input gives an
+/// unrelated g under every modulus.
+fn hash_to_group(n: &BigUint, x: &[u8]) -> BigUint {
+    let k = (n.bits() as usize).div_ceil(8);
+    let seed = [i2osp(n, k).expect("N fits in k bytes"), x.to_vec()].concat();
+    canonical(&(os2ip(&mgf1(HashAlg::Sha256, &seed, k + 16)) % n), n)
+}
+
+/// The Fiat-Shamir challenge: the first prime at or after a 128-bit hash of N, g, y and T.
+/// Baillie-PSW keeps it deterministic, so the prover and the verifier always land on the same l.
+fn hash_to_prime(n: &BigUint, g: &BigUint, y: &BigUint, t: u64) -> BigUint {
+    let k = (n.bits() as usize).div_ceil(8);
+    let parts = [n, g, y].map(|z| i2osp(z, k).expect("reduced mod N"));
+    let h = HashAlg::Sha256.digest(&[&parts[0], &parts[1], &parts[2], &t.to_be_bytes()]);
+    let mut l = os2ip(&h[..CHALLENGE_BITS / 8]);
+    l.set_bit(CHALLENGE_BITS as u64 - 1, true);
+    l.set_bit(0, true);
+    while !baillie_psw(&l) { l += 2u64; }
+    l
+}
@@ -0,0 +1,148 @@
+use crate::error::RsaError;
+use crate::euclidian::{gcd, mod_inverse};
+use crate::hash::HashAlg;
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, os2ip, random_range};
+use crate::pss::{pss_encode, verify_pss_with, Pss};
+
+use num_bigint::BigUint;
+use num_traits::One;
+use rand::{CryptoRng, RngCore};
+
+/// Length of the random prefix that randomized message preparation puts in front of the message.
+pub const MSG_PREFIX_LEN: usize = 32;
+
+/// One of the RSABSSA variants from RFC 9474 (section 5). All of them use SHA-384; they differ in
+/// whether PSS gets a salt and whether the message gets a random prefix before it is blinded. The
+/// client, the signer and the verifier all have to agree on which one is in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlindVariant {
+    /// Hash and salt length for the PSS encoding of the (prepared) message.
+    pub pss: Pss,
+    /// Whether prepare puts MSG_PREFIX_LEN random bytes in front of the message.
+    pub randomized: bool,
+}
+
+impl BlindVariant {
+
+    /// RSABSSA-SHA384-PSS-Randomized: a 48 byte salt and a random message prefix. This is the one
+    /// the RFC recommends.
+    pub const SHA384_PSS_RANDOMIZED: Self = Self::new(48, true);
+    /// RSABSSA-SHA384-PSSZERO-Randomized: no salt, but a random message prefix.
+    pub const SHA384_PSSZERO_RANDOMIZED: Self = Self::new(0, true);
+    /// RSABSSA-SHA384-PSS-Deterministic: a 48 byte salt, the message is signed as is.
+    pub const SHA384_PSS_DETERMINISTIC: Self = Self::new(48, false);
+    /// RSABSSA-SHA384-PSSZERO-Deterministic: no salt and no prefix, so the signature on a message
+    /// is the same every time (it is a plain RSASSA-PSS signature with an empty salt).
+    pub const SHA384_PSSZERO_DETERMINISTIC: Self = Self::new(0, false);
+
+    const fn new(salt_len: usize, randomized: bool) -> Self {
+        Self { pss: Pss { hash: HashAlg::Sha384, salt_len }, randomized }
+    }
+}
+
+impl Default for BlindVariant {
+    fn default() -> Self {
+        Self::SHA384_PSS_RANDOMIZED
+    }
+}
+
+/// Prepares a message for blinding (RFC 9474, section 4.1). The randomized variants put 32 random
+/// bytes in front of the message, so that the signer can't steer what is actually being signed
+/// even if they know the message; the deterministic variants leave it alone.
+///
+/// Input: message (msg) -> the message the client wants signed.
+/// Input: variant (variant) -> which RSABSSA variant is in use.
+/// Input: random number generator (rng) -> source of the prefix.
+///
+/// Output: prepared message -> what has to go into blind, finalize and verify_blind from here on.
+pub fn prepare<R: CryptoRng + RngCore>(msg: &[u8], variant: BlindVariant, rng: &mut R) -> Vec<u8> {
+    if !variant.randomized { return msg.to_vec(); }
+    let mut prepared = vec![0u8; MSG_PREFIX_LEN];
+    rng.fill_bytes(&mut prepared);
+    prepared.extend_from_slice(msg);
+    prepared
+}
+
+/// The client's first step (RFC 9474, section 4.2). The message is PSS encoded, then multiplied by
+/// r^e for a random r, which makes it look uniformly random to the signer. This is Chaum's trick:
+/// whatever the signer raises to d, the client can divide the r back out of afterwards.
+///
+/// Input: public key (key) -> the signer's public key.
+/// Input: prepared message (msg) -> the output of prepare.
+/// Input: variant (variant) -> which RSABSSA variant is in use.
+/// Input: random number generator (rng) -> source of the salt and of r.
+///
+/// Output: (blinded message, inv) -> the k-byte blinded message to send to the signer, and r^-1
+/// (mod N), which the client keeps for finalize.
+pub fn blind<R: CryptoRng + RngCore>(key: &RsaPublicKey, msg: &[u8], variant: BlindVariant, rng: &mut R) -> Result<(Vec<u8>, BigUint), RsaError> {
+    let mut salt = vec![0u8; variant.pss.salt_len];
+    rng.fill_bytes(&mut salt);
+    let r = random_range(rng, &BigUint::one(), &(key.n() - 1u64));
+    blind_with(key, msg, variant, &salt, &r)
+}
+
+/// Same as blind, but with the salt and r handed in rather than drawn from an rng.
+pub fn blind_with(key: &RsaPublicKey, msg: &[u8], variant: BlindVariant, salt: &[u8], r: &BigUint) -> Result<(Vec<u8>, BigUint), RsaError> {
+    let hash = variant.pss.hash;
+    let m = os2ip(&pss_encode(&hash.digest(&[msg]), key.bits() - 1, hash, salt)?);
+    // An m that shares a factor with N would hand the client the factorization; it can only
+    // happen by accident with negligible probability, but the RFC asks us to check.
+    if !gcd(&m, key.n()).is_one() { return Err(RsaError::InvalidInput); }
+    let inv = mod_inverse(r, key.n()).ok_or(RsaError::InvalidInput)?;
+    let z = (m * key.public_exp(r)) % key.n();
+    Ok((i2osp(&z, key.size()).expect("z is smaller than N"), inv))
+}
+
+/// The signer's only step (RFC 9474, section 4.3): raise the blinded message to d. The signer has
+/// no idea what it is signing, which is the whole point, so the result is checked against e
+/// before it goes out; a fault in the CRT computation would otherwise leak the factors of N.
+///
+/// Input: private key (key) -> the signer's private key.
+/// Input: blinded message (blinded_msg) -> what the client sent, exactly k bytes.
+/// Input: random number generator (rng) -> source of the (usual, RSA) blinding factor.
+///
+/// Output: blind signature -> k bytes to send back to the client.
+#[allow(non_snake_case)]
+pub fn blind_sign<R: CryptoRng + RngCore>(key: &RsaPrivateKey, blinded_msg: &[u8], rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    if blinded_msg.len() != key.size() { return Err(RsaError::InvalidInput); }
+    let m = os2ip(blinded_msg);
+    if m >= *key.n() { return Err(RsaError::InvalidInput); }
+    let S = key.private_exp(&m, rng);
+    if key.public_key().public_exp(&S) != m { return Err(RsaError::SigningFailure); }
+    Ok(i2osp(&S, key.size()).expect("S is smaller than N"))
+}
+
+/// The client's last step (RFC 9474, section 4.4): multiply the blind signature by r^-1 to get a
+/// signature on the prepared message itself, and make sure it verifies before handing it out.
+///
+/// Input: public key (key) -> the signer's public key.
+/// Input: prepared message (msg) -> the same prepared message that went into blind.
+/// Input: blind signature (blind_sig) -> what the signer sent back.
+/// Input: inverse (inv) -> the inverse that blind returned.
+/// Input: variant (variant) -> which RSABSSA variant is in use.
+///
+/// Output: signature -> k bytes, an ordinary RSASSA-PSS signature on the prepared message.
+#[allow(non_snake_case)]
+pub fn finalize(key: &RsaPublicKey, msg: &[u8], blind_sig: &[u8], inv: &BigUint, variant: BlindVariant) -> Result<Vec<u8>, RsaError> {
+    if blind_sig.len() != key.size() { return Err(RsaError::InvalidInput); }
+    let S = (os2ip(blind_sig) * inv) % key.n();
+    let sig = i2osp(&S, key.size()).expect("S is smaller than N");
+    if !verify_blind(&sig, key, msg, variant) { return Err(RsaError::InvalidSignature); }
+    Ok(sig)
+}
+
+/// Verifies a signature that came out of finalize (RFC 9474, section 4.5). Nothing about it is
+/// blind anymore: it is checked like any other RSASSA-PSS signature, on the prepared message.
+///
+/// Input: signature (S) -> signature to check.
+/// Input: public key (key) -> the signer's public key.
+/// Input: prepared message (msg) -> the prepared message (prefix included, for the randomized
+/// variants).
+/// Input: variant (variant) -> which RSABSSA variant is in use.
+///
+/// Output: bool -> true means signature is valid. False means that it is invalid.
+#[allow(non_snake_case)]
+pub fn verify_blind(S: &[u8], key: &RsaPublicKey, msg: &[u8], variant: BlindVariant) -> bool {
+    verify_pss_with(S, key, msg, variant.pss)
+}
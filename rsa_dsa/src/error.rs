@@ -0,0 +1,36 @@
+use std::fmt;
+
+/// Everything that can go wrong when producing a padded signature. Verification just returns
+/// false, same as the textbook verify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RsaError {
+    /// The modulus is too small to hold the encoded message for the chosen hash and salt length.
+    KeyTooSmall,
+    /// The key material doesn't describe a usable RSA key.
+    InvalidKey(&'static str),
+    /// A blind signature protocol message is malformed (wrong length, not smaller than N, or not
+    /// invertible mod N).
+    InvalidInput,
+    /// The signature we just computed doesn't verify, so something went wrong inside the private
+    /// key operation and the result must not be released.
+    SigningFailure,
+    /// The unblinded signature doesn't verify: the signer signed something else, or used another key.
+    InvalidSignature,
+    /// Fewer than t of the threshold signature shares came from different parties and checked out.
+    NotEnoughShares,
+}
+
+impl fmt::Display for RsaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsaError::KeyTooSmall => write!(f, "modulus too small for the chosen padding"),
+            RsaError::InvalidKey(reason) => write!(f, "invalid key: {}", reason),
+            RsaError::InvalidInput => write!(f, "invalid input"),
+            RsaError::SigningFailure => write!(f, "signing failure"),
+            RsaError::InvalidSignature => write!(f, "invalid signature"),
+            RsaError::NotEnoughShares => write!(f, "not enough valid signature shares"),
+        }
+    }
+}
+
+impl std::error::Error for RsaError {}
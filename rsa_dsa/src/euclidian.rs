@@ -1,58 +1,75 @@
-use bigint::uint::U512;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
 
+/// gcd(a, b) by Euclid's algorithm. gcd(a, 0) = a, so a zero on either side is fine.
 #[allow(dead_code)]
-pub fn gcd(a: U512, b: U512) -> U512 {
-    let mut r0 = a;
-    let mut r1 = b;
-    if r1 > r0 {
-        std::mem::swap(&mut r0, &mut r1);
-    }
-    loop {
-        let r2 = r0 % r1;
-        if r2 == U512::zero() {
-            return r1;
-        }
+pub fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    while !r1.is_zero() {
+        let r2 = &r0 % &r1;
         r0 = r1;
         r1 = r2;
     }
+    r0
 }
 
-pub fn modinv(e: U512, p: U512) -> U512 {
-    if p == U512::one() { return U512::one(); }
-    let (mut a, mut m, mut x, mut inv) = (e.clone(), p.clone(), U512::zero(), U512::one());
-
-    while a > U512::one() {
-        let div = a / m;
-        let rem = a % m;
-        if (div * x) > inv {
-            inv = p - (((div * x) % p) - inv);
-        }
-        else {
-            inv = (inv - (div * x)) % p;
-        }
-        a = rem;
-        std::mem::swap(&mut a, &mut m);
-        std::mem::swap(&mut x, &mut inv);
+/// Stein's binary GCD. It only shifts and subtracts, which is cheaper than the long divisions
+/// Euclid needs when the numbers are about the same size.
+#[allow(dead_code)]
+pub fn binary_gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let (Some(za), Some(zb)) = (a.trailing_zeros(), b.trailing_zeros()) else {
+        // One of them is zero.
+        return a | b;
+    };
+    // gcd(2^k * a', 2^k * b') = 2^k * gcd(a', b'), and once one side is odd the other side's
+    // factors of two can be thrown away.
+    let shift = za.min(zb);
+    let mut a = a >> za;
+    let mut b = b.clone();
+    loop {
+        b >>= b.trailing_zeros().expect("b is nonzero");
+        if a > b { std::mem::swap(&mut a, &mut b); }
+        b -= &a;
+        if b.is_zero() { return a << shift; }
     }
-    inv
 }
 
-/*
-pub fn modinv(n: &BigInt, p: &BigInt) -> BigInt {
-    if p.is_one() { return BigInt::one() }
-
-    let (mut a, mut m, mut x, mut inv) = (n.clone(), p.clone(), BigInt::zero(), BigInt::one());
-
-    while a > BigInt::one() {
-        let (div, rem) = a.div_rem(&m);
-        inv -= div * &x;
-        a = rem;
-        std::mem::swap(&mut a, &mut m);
-        std::mem::swap(&mut x, &mut inv);
+/// The extended Euclidean algorithm. Along with g = gcd(a, b) it finds x and y such that
+/// a * x + b * y = g. The coefficients go negative along the way, so they are BigInts.
+///
+/// Input: first number (a) -> any BigUint.
+/// Input: second number (b) -> any BigUint.
+///
+/// Output: (g, x, y) -> gcd and Bezout coefficients.
+#[allow(dead_code)]
+pub fn xgcd(a: &BigUint, b: &BigUint) -> (BigUint, BigInt, BigInt) {
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut s0, mut s1) = (BigInt::one(), BigInt::zero());
+    let (mut t0, mut t1) = (BigInt::zero(), BigInt::one());
+    while !r1.is_zero() {
+        let (q, r2) = r0.div_rem(&r1);
+        let q = BigInt::from(q);
+        (r0, r1) = (r1, r2);
+        let s2 = &s0 - &q * &s1;
+        (s0, s1) = (s1, s2);
+        let t2 = &t0 - &q * &t1;
+        (t0, t1) = (t1, t2);
     }
- 
-    if inv < BigInt::zero() { inv += p }
+    (r0, s0, t0)
+}
 
-    inv
+/// a^-1 (mod m), or None if there isn't one (gcd(a, m) != 1, or m = 0). Dividing by something
+/// without an inverse is always a bug or an attack somewhere upstream, so callers have to decide
+/// what it means instead of getting garbage back.
+///
+/// Input: number to invert (a) -> any BigUint.
+/// Input: modulus (m) -> the modulus.
+///
+/// Output: Some(x) with a * x = 1 (mod m) and x < m, or None.
+pub fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    if m.is_zero() { return None; }
+    let (g, x, _) = xgcd(&(a % m), m);
+    if !g.is_one() { return None; }
+    x.mod_floor(&BigInt::from(m.clone())).to_biguint()
 }
-*/
@@ -1,12 +1,27 @@
-use crate::math::mod_exp;
-use crate::euclidian::{gcd, modinv};
-use crate::flt::flt;
+pub use num_bigint::BigUint;
 
-use bigint::uint::U512;
+use rand::{CryptoRng, RngCore};
+
+pub use crate::blind::{blind, blind_sign, finalize, prepare, verify_blind, BlindVariant};
+pub use crate::error::RsaError;
+pub use crate::hash::HashAlg;
+pub use crate::key::{OtherPrimeInfo, RsaPrivateKey, RsaPublicKey, DEFAULT_EXPONENT};
+pub use crate::pkcs1v15::{sign_pkcs1v15, verify_pkcs1v15};
+pub use crate::prime::{is_prime, Primality};
+pub use crate::pss::{sign_pss, sign_pss_with, verify_pss, verify_pss_with, Pss};
+pub use crate::threshold::{combine_shares, deal, deal_with_exponent, sign_share, verify_share, KeyShare, SignatureShare, ThresholdPublicKey};
 
-mod flt;
 mod math;
+mod blind;
+mod der;
 mod euclidian;
+mod error;
+mod hash;
+mod key;
+mod pkcs1v15;
+mod prime;
+mod pss;
+mod threshold;
 
 /// Deriving a rsa public key requires the user to have two large prime numbers and an exponent.
 /// Note that the exponent doesn't necessarily have to be large, but the larger it is, the more
@@ -16,28 +31,45 @@ mod euclidian;
 /// Input: secret prime #2 (p) -> second secret key for RSA encryption.
 /// Input: public exponent (e) -> exponent to use as the second part of the public key.
 ///
-/// Output: public keys (N, e) -> N, which is the finite field modulo, and e, the exponent used to
+/// Output: public key (N, e) -> N, which is the finite field modulo, and e, the exponent used to
 /// encrpt data to a user.
-pub fn rsa_derive_pubkey(q: U512, p: U512, e: U512) -> (U512, U512) {
-    assert!(gcd(e, (q - U512::one()) * (p - U512::one())) == U512::one(), "chosen exponent is not coprime with p-1 * q-1");
-    assert!(flt(q));
-    assert!(flt(p));
-    ((p * q), e)
+pub fn rsa_derive_pubkey(q: BigUint, p: BigUint, e: BigUint) -> RsaPublicKey {
+    match RsaPrivateKey::from_primes(p, q, e) {
+        Ok(key) => key.public_key(),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+/// Same as rsa_derive_pubkey, but for a multi-prime key: N is the product of every prime in the
+/// list instead of just two of them. Nothing changes for whoever encrypts (or verifies); they only
+/// ever see N and e.
+///
+/// Input: secret primes (primes) -> two or more distinct primes.
+/// Input: public exponent (e) -> exponent to use as the second part of the public key.
+///
+/// Output: public key (N, e).
+pub fn rsa_derive_multi_prime_pubkey(primes: Vec<BigUint>, e: BigUint) -> RsaPublicKey {
+    match RsaPrivateKey::from_prime_factors(primes, e) {
+        Ok(key) => key.public_key(),
+        Err(err) => panic!("{}", err),
+    }
 }
 
 /// We sign a message with our RSA private keys to verify the authenticity of a document. Because
 /// the DSA is built on RSA, it guarantees that the signer is probably legit (since if it wasn't
 /// then RSA would be broken or the keys of the owner were compromised).
 ///
+/// The private exponent and the CRT parameters live in the key, so signing is just the two
+/// half-size exponentiations mod p and mod q. The input is blinded with a random factor first
+/// (see RsaPrivateKey::private_exp), which is what the rng is for.
+///
 /// Input: document hash (d) -> hash of the document to sign.
-/// Input: private key #1 (p) -> first private key of the signer.
-/// Input: private key #2 (q) -> second private key of the signer.
-/// Input: public exponent (e) -> public key for the target signer.
+/// Input: private key (key) -> private key of the signer.
+/// Input: random number generator (rng) -> source of the blinding factor.
 ///
 /// Output: signature (S) -> signatures of the document for people to verify.
-pub fn sign(d: U512, p: U512, q: U512, e: U512) -> U512 {
-    let x = modinv(e, (p - U512::one()) * (q - U512::one()));
-    mod_exp(d, x, p * q)
+pub fn sign<R: CryptoRng + RngCore>(d: &BigUint, key: &RsaPrivateKey, rng: &mut R) -> BigUint {
+    key.private_exp(d, rng)
 }
 
 /// Verifying an RSA signature is pretty straitforward because we only have one signature to verify
@@ -46,15 +78,14 @@ pub fn sign(d: U512, p: U512, q: U512, e: U512) -> U512 {
 /// document is equal to S^e (mod pq)
 ///
 /// Input: signature (S) -> signature to use in verification.
-/// Input: public key (N) -> supposed signer's public key.
-/// Input: exponent (e) -> public exponent used for encryption.
+/// Input: public key (key) -> supposed signer's public key (N, e).
 /// Input: document_hash (d) -> hash of the document that the signer supposedly signed.
 ///
 /// Output: bool -> true means signature is valid. False means that it is invalid.
 #[allow(non_snake_case)]
-pub fn verify(S: U512, N: U512, e: U512, d: U512) -> bool {
-    let res = mod_exp(S, e, N);
-    if res == d { return true; }
+pub fn verify(S: &BigUint, key: &RsaPublicKey, d: &BigUint) -> bool {
+    let res = key.public_exp(S);
+    if res == *d { return true; }
     false
 }
 
@@ -66,10 +97,11 @@ pub fn verify(S: U512, N: U512, e: U512, d: U512) -> bool {
 /// Input: public exponent (e) -> public key exponent with trait that GCD(e, (p-1)(q-1)) = 1.
 /// Input: document hash (d) -> hash of the document to sign and verify.
 #[allow(non_snake_case)]
-pub fn rsa_test(p: U512, q: U512, e: U512, d: U512) -> bool {
-    let (N, e) = rsa_derive_pubkey(q, p, e);
-    let S = sign(d, p, q,e);
-    verify(S, N, e, d)
+pub fn rsa_test(p: BigUint, q: BigUint, e: BigUint, d: BigUint) -> bool {
+    let key = RsaPrivateKey::from_primes(p.clone(), q.clone(), e.clone()).unwrap();
+    let public = rsa_derive_pubkey(q, p, e);
+    let S = sign(&d, &key, &mut rand::thread_rng());
+    verify(&S, &public, &d)
 }
 
 /// RSA test fail is the exact same as RSA test, except the document hash is just one bit off of
@@ -82,10 +114,11 @@ pub fn rsa_test(p: U512, q: U512, e: U512, d: U512) -> bool {
 /// Input: public exponent (e) -> public key exponent with trait that GCD(e, (p-1)(q-1)) = 1.
 /// Input: document hash (d) -> hash of the document to sign and verify.
 #[allow(non_snake_case)]
-pub fn rsa_test_fail(p: U512, q: U512, e: U512, d: U512) -> bool {
-    let (N, e) = rsa_derive_pubkey(q, p, e);
-    let S = sign(d, p, q,e);
-    verify(S, N, e, d - U512::one())
+pub fn rsa_test_fail(p: BigUint, q: BigUint, e: BigUint, d: BigUint) -> bool {
+    let key = RsaPrivateKey::from_primes(p.clone(), q.clone(), e.clone()).unwrap();
+    let public = rsa_derive_pubkey(q, p, e);
+    let S = sign(&d, &key, &mut rand::thread_rng());
+    verify(&S, &public, &(d - 1u64))
 }
 
 
@@ -94,51 +127,567 @@ pub fn rsa_test_fail(p: U512, q: U512, e: U512, d: U512) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::euclidian::{binary_gcd, gcd, mod_inverse, xgcd};
+    use num_bigint::BigInt;
+    use crate::math::{mod_exp, mod_exp_ct, random_bits};
+    use crate::prime::baillie_psw;
+    use num_traits::{One, Zero};
     use rand::RngCore;
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
     #[test]
-    fn fermats_little_theorem() {
-        assert!(flt(U512::from(10093)));
-        assert!(flt(U512::from(10099)));
-        assert!(flt(U512::from(10103)));
-        assert!(flt(U512::from(10111)));
-        assert!(flt(U512::from(10133)));
-        assert!(flt(U512::from(10139)));
-        assert!(flt(U512::from(10141)));
-        assert!(flt(U512::from(10151)));
-        assert!(flt(U512::from(10159)));
-        assert!(flt(U512::from(10163)));
-        assert!(flt(U512::from(10169)));
-        assert!(!flt(U512::from(10102)));
-        assert!(!flt(U512::from(10143)));
-        assert!(!flt(U512::from(10165)));
-        assert!(!flt(U512::from(10137)));
-        assert!(!flt(U512::from(10199)));
+    fn primality() {
+        let mut rng = rand::thread_rng();
+        let mut check = |n: u64| {
+            let mr = is_prime(&BigUint::from(n), Primality::MillerRabin, &mut rng);
+            let bpsw = is_prime(&BigUint::from(n), Primality::BailliePsw, &mut rng);
+            assert_eq!(mr, bpsw, "tests disagree on {}", n);
+            mr
+        };
+        assert!(check(10093));
+        assert!(check(10099));
+        assert!(check(10103));
+        assert!(check(10111));
+        assert!(check(10133));
+        assert!(check(10139));
+        assert!(check(10141));
+        assert!(check(10151));
+        assert!(check(10159));
+        assert!(check(10163));
+        assert!(check(10169));
+        assert!(!check(10102));
+        assert!(!check(10143));
+        assert!(!check(10165));
+        assert!(!check(10137));
+        assert!(!check(10199));
+        assert!(check(2) && check(3) && check(5) && check(251) && check(257));
+        assert!(!check(0) && !check(1) && !check(4) && !check(9) && !check(65536));
+
+        // Mersenne primes 2^61 - 1, 2^89 - 1, 2^127 - 1, 2^521 - 1 and 2^1279 - 1, and the
+        // composites around them.
+        for e in [61, 89, 127, 521, 1279] {
+            let m = (BigUint::one() << e) - 1u64;
+            assert!(prime::baillie_psw(&m));
+            assert!(prime::is_probable_prime(&m, &mut rng));
+            assert!(!prime::baillie_psw(&(&m - 2u64)));
+            assert!(!prime::baillie_psw(&(&m * 1000003u64)));
+        }
+        // Product of two 128-bit primes.
+        let p = (BigUint::one() << 127) - 1u64;
+        let q = big("340282366920938463463374607431768211297");
+        assert!(prime::baillie_psw(&q));
+        assert!(!prime::baillie_psw(&(&p * &q)));
+        assert!(!prime::is_probable_prime(&(&p * &q), &mut rng));
+    }
+    #[test]
+    fn carmichael_numbers() {
+        // Carmichael numbers fool the Fermat test for every base coprime to them. The ones in
+        // the second list have no factor below 10, so they even passed the old flt check.
+        let carmichael: [u64; 19] = [
+            561, 1105, 1729, 2465, 2821, 6601, 8911, 10585, 15841, 29341, 41041, 46657, 52633,
+            62745, 63973, 75361, 101101, 115921, 126217,
+        ];
+        let fool_flt: [u64; 10] = [162401, 252601, 294409, 314821, 334153, 340561, 399001, 410041, 488881, 512461];
+        let mut rng = rand::thread_rng();
+        for &n in carmichael.iter().chain(fool_flt.iter()) {
+            let n = BigUint::from(n);
+            assert!(!is_prime(&n, Primality::MillerRabin, &mut rng), "{} passed Miller-Rabin", n);
+            assert!(!is_prime(&n, Primality::BailliePsw, &mut rng), "{} passed Baillie-PSW", n);
+        }
+        for &n in fool_flt.iter() {
+            for a in 2..10u64 {
+                assert!(mod_exp(&BigUint::from(a), &BigUint::from(n - 1), &BigUint::from(n)).is_one());
+            }
+        }
+    }
+    #[test]
+    fn strong_pseudoprimes() {
+        let mut rng = rand::thread_rng();
+        // Strong pseudoprimes to base 2: they pass a single Miller-Rabin round with a = 2, so
+        // Baillie-PSW has to catch them in the Lucas half.
+        let base_2: [u64; 16] = [
+            2047, 3277, 4033, 4681, 8321, 15841, 29341, 42799, 49141, 52633, 65281, 74665, 80581,
+            85489, 88357, 90751,
+        ];
+        for &n in base_2.iter() {
+            assert!(!prime::baillie_psw(&BigUint::from(n)), "{} passed Baillie-PSW", n);
+            assert!(!prime::is_probable_prime(&BigUint::from(n), &mut rng), "{} passed Miller-Rabin", n);
+        }
+        // 3215031751 is a strong pseudoprime to bases 2, 3, 5 and 7, and 3825123056546413051 to
+        // every prime base up to 23.
+        for n in ["3215031751", "3825123056546413051"] {
+            let n = big(n);
+            assert!(!prime::baillie_psw(&n));
+            assert!(!prime::is_probable_prime(&n, &mut rng));
+        }
+        // Strong Lucas pseudoprimes (Selfridge parameters) fool the Lucas half, so they have to
+        // fail the base 2 half instead.
+        let lucas: [u64; 10] = [5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519];
+        for &n in lucas.iter() {
+            assert!(prime::strong_lucas_probable_prime(&BigUint::from(n)));
+            assert!(!prime::baillie_psw(&BigUint::from(n)), "{} passed Baillie-PSW", n);
+            assert!(!prime::is_probable_prime(&BigUint::from(n), &mut rng), "{} passed Miller-Rabin", n);
+        }
+    }
+    #[test]
+    fn jacobi_symbols() {
+        // (a/p) for a prime p agrees with Euler's criterion a^((p-1)/2).
+        let p = BigUint::from(10169u64);
+        for a in 1..200u64 {
+            let euler = mod_exp(&BigUint::from(a), &((&p - 1u64) >> 1), &p);
+            let expected = if euler.is_one() { 1 } else { -1 };
+            assert_eq!(prime::jacobi(&BigUint::from(a), &p), expected);
+        }
+        assert_eq!(prime::jacobi(&BigUint::from(1001u64), &BigUint::from(9907u64)), -1);
+        assert_eq!(prime::jacobi(&BigUint::from(19u64), &BigUint::from(45u64)), 1);
+        assert_eq!(prime::jacobi(&BigUint::from(8u64), &BigUint::from(21u64)), -1);
+        assert_eq!(prime::jacobi(&BigUint::from(5u64), &BigUint::from(21u64)), 1);
+        assert_eq!(prime::jacobi(&BigUint::from(15u64), &BigUint::from(45u64)), 0);
+    }
+    #[test]
+    fn extended_gcds() {
+        let a = big("836924423851314686957903970262947033592661064025187511582267500056692617427");
+        let b = big("60772039568148124987496788008570400867100447637981291630550967949578841");
+        let pairs = [(2024u64, 748u64), (748, 2024), (7834, 48), (40183, 45791821), (12, 0), (0, 12), (0, 0)];
+        let pairs = pairs.iter().map(|&(a, b)| (BigUint::from(a), BigUint::from(b))).chain([(a.clone(), b.clone())]);
+        for (a, b) in pairs {
+            let (g, x, y) = xgcd(&a, &b);
+            assert_eq!(g, gcd(&a, &b));
+            assert_eq!(g, binary_gcd(&a, &b));
+            assert_eq!(BigInt::from(a) * x + BigInt::from(b) * y, BigInt::from(g));
+        }
+        assert_eq!(xgcd(&a, &b).0, BigUint::from(3u64));
+
+        let m = (BigUint::one() << 255) - 19u64;
+        let x = big("21582102299826525449504797940759260576379968499998982132066757867315193878103");
+        let inv = big("17072501176736913924375703095939437871033759977920439439095577500683350405846");
+        assert_eq!(mod_inverse(&x, &m), Some(inv.clone()));
+        assert_eq!(mod_inverse(&(&x + &m), &m), Some(inv));
+        assert_eq!(mod_inverse(&BigUint::from(3u64), &BigUint::from(7u64)), Some(BigUint::from(5u64)));
+        assert_eq!(mod_inverse(&BigUint::from(5u64), &BigUint::one()), Some(BigUint::zero()));
+        assert_eq!(mod_inverse(&BigUint::zero(), &m), None);
+        assert_eq!(mod_inverse(&m, &m), None);
+        assert_eq!(mod_inverse(&BigUint::from(6u64), &BigUint::from(15u64)), None);
+        assert_eq!(mod_inverse(&BigUint::from(6u64), &BigUint::zero()), None);
     }
     #[test]
     #[allow(non_snake_case)]
     fn rsa_digital_signatures() {
-        let (N, e) = rsa_derive_pubkey(U512::from(1223u64), U512::from(1987u64), U512::from(948047u64));
-        let S = sign(U512::from(1070777u64), U512::from(1223u64), U512::from(1987u64), U512::from(948047u64));
-        assert!(verify(S, N, e, U512::from(1070777u64)));
-        assert!(rsa_test(U512::from(1223u64), U512::from(1987u64), U512::from(948047u64), U512::from(1070777u64)));
-        assert!(rsa_test(U512::from(1223u64), U512::from(1987u64), U512::from(9129874561u64), U512::from(rand::thread_rng().next_u64() % 1000000)));
-        assert!(rsa_test(U512::from(10193u64), U512::from(1000199u64), U512::from(246258617u64), U512::from(rand::thread_rng().next_u64() % 100000000)));
-        assert!(rsa_test(U512::from(1120211u64), U512::from(1000213u64), U512::from(83731u64), U512::from(rand::thread_rng().next_u64() % 213546712)));
-        assert!(rsa_test(U512::from(2502317u64), U512::from(1000249u64), U512::from(45641737u64), U512::from(rand::thread_rng().next_u64() % 123645321)));
-        assert!(rsa_test(U512::from(33311u64), U512::from(542000867u64), U512::from(24727u64), U512::from(rand::thread_rng().next_u64() % 138765413)));
-        assert!(rsa_test(U512::from(367500929u64), U512::from(542000951u64), U512::from(948047u64), U512::from(rand::thread_rng().next_u64() % 1827635418)));
-        assert!(rsa_test(U512::from(37670153u64), U512::from(1987u64), U512::from(9480473u64), U512::from(rand::thread_rng().next_u64() % 27635418)));
+        let mut rng = rand::thread_rng();
+        let public = rsa_derive_pubkey(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64));
+        let key = RsaPrivateKey::from_primes(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64)).unwrap();
+        let S = sign(&BigUint::from(1070777u64), &key, &mut rng);
+        assert!(verify(&S, &public, &BigUint::from(1070777u64)));
+        assert!(rsa_test(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64), BigUint::from(1070777u64)));
+        assert!(rsa_test(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(9129874561u64), BigUint::from(rand::thread_rng().next_u64() % 1000000)));
+        assert!(rsa_test(BigUint::from(10193u64), BigUint::from(1000199u64), BigUint::from(246258617u64), BigUint::from(rand::thread_rng().next_u64() % 100000000)));
+        assert!(rsa_test(BigUint::from(1120211u64), BigUint::from(1000213u64), BigUint::from(83731u64), BigUint::from(rand::thread_rng().next_u64() % 213546712)));
+        assert!(rsa_test(BigUint::from(2502317u64), BigUint::from(1000249u64), BigUint::from(45641737u64), BigUint::from(rand::thread_rng().next_u64() % 123645321)));
+        assert!(rsa_test(BigUint::from(33311u64), BigUint::from(542000867u64), BigUint::from(24727u64), BigUint::from(rand::thread_rng().next_u64() % 138765413)));
+        assert!(rsa_test(BigUint::from(367500929u64), BigUint::from(542000951u64), BigUint::from(948047u64), BigUint::from(rand::thread_rng().next_u64() % 1827635418)));
+        assert!(rsa_test(BigUint::from(37670153u64), BigUint::from(1987u64), BigUint::from(9480473u64), BigUint::from(rand::thread_rng().next_u64() % 27635418)));
 
         // Same tests as above (kinda because thread_rng is always changing the document hash
         // value) except that the tests SHOULD fail.
-        assert!(!rsa_test_fail(U512::from(1223u64), U512::from(1987u64), U512::from(948047u64), U512::from(1070777u64)));
-        assert!(!rsa_test_fail(U512::from(1223u64), U512::from(1987u64), U512::from(9129874561u64), U512::from(rand::thread_rng().next_u64() % 1000000)));
-        assert!(!rsa_test_fail(U512::from(10193u64), U512::from(1000199u64), U512::from(246258617u64), U512::from(rand::thread_rng().next_u64() % 100000000)));
-        assert!(!rsa_test_fail(U512::from(1120211u64), U512::from(1000213u64), U512::from(83731u64), U512::from(rand::thread_rng().next_u64() % 213546712)));
-        assert!(!rsa_test_fail(U512::from(2502317u64), U512::from(1000249u64), U512::from(45641737u64), U512::from(rand::thread_rng().next_u64() % 123645321)));
-        assert!(!rsa_test_fail(U512::from(33311u64), U512::from(542000867u64), U512::from(24727u64), U512::from(rand::thread_rng().next_u64() % 138765413)));
-        assert!(!rsa_test_fail(U512::from(367500929u64), U512::from(542000951u64), U512::from(948047u64), U512::from(rand::thread_rng().next_u64() % 1827635418)));
-        assert!(!rsa_test_fail(U512::from(37670153u64), U512::from(1987u64), U512::from(9480473u64), U512::from(rand::thread_rng().next_u64() % 27635418)));
+        assert!(!rsa_test_fail(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(948047u64), BigUint::from(1070777u64)));
+        assert!(!rsa_test_fail(BigUint::from(1223u64), BigUint::from(1987u64), BigUint::from(9129874561u64), BigUint::from(rand::thread_rng().next_u64() % 1000000)));
+        assert!(!rsa_test_fail(BigUint::from(10193u64), BigUint::from(1000199u64), BigUint::from(246258617u64), BigUint::from(rand::thread_rng().next_u64() % 100000000)));
+        assert!(!rsa_test_fail(BigUint::from(1120211u64), BigUint::from(1000213u64), BigUint::from(83731u64), BigUint::from(rand::thread_rng().next_u64() % 213546712)));
+        assert!(!rsa_test_fail(BigUint::from(2502317u64), BigUint::from(1000249u64), BigUint::from(45641737u64), BigUint::from(rand::thread_rng().next_u64() % 123645321)));
+        assert!(!rsa_test_fail(BigUint::from(33311u64), BigUint::from(542000867u64), BigUint::from(24727u64), BigUint::from(rand::thread_rng().next_u64() % 138765413)));
+        assert!(!rsa_test_fail(BigUint::from(367500929u64), BigUint::from(542000951u64), BigUint::from(948047u64), BigUint::from(rand::thread_rng().next_u64() % 1827635418)));
+        assert!(!rsa_test_fail(BigUint::from(37670153u64), BigUint::from(1987u64), BigUint::from(9480473u64), BigUint::from(rand::thread_rng().next_u64() % 27635418)));
+
+    }
+    #[test]
+    fn constant_time_exponentiation() {
+        let mut rng = rand::thread_rng();
+        // One limb, exactly at a limb boundary, and a few limbs longer than that.
+        let moduli = [
+            BigUint::from(3u64),
+            BigUint::from(u64::MAX),
+            (BigUint::one() << 64) + 1u64,
+            (BigUint::one() << 128) - 1u64,
+            random_bits(&mut rng, 1000) | BigUint::one(),
+            random_bits(&mut rng, 2048) | (BigUint::one() << 2047) | BigUint::one(),
+        ];
+        for f in moduli.iter() {
+            for _ in 0..5 {
+                let g = random_bits(&mut rng, f.bits() as usize + 10);
+                let len = 1 + (rng.next_u32() % 600) as usize;
+                let a = random_bits(&mut rng, len);
+                assert_eq!(mod_exp_ct(&g, &a, f), mod_exp(&g, &a, f));
+            }
+            let g = f - 1u64;
+            assert_eq!(mod_exp_ct(&g, &BigUint::from(2u64), f), BigUint::one());
+            assert_eq!(mod_exp_ct(&g, &BigUint::one(), f), g);
+            assert_eq!(mod_exp_ct(&g, &BigUint::zero(), f), BigUint::one());
+            assert!(mod_exp_ct(&BigUint::zero(), &BigUint::from(17u64), f).is_zero());
+        }
+        assert!(mod_exp_ct(&BigUint::from(5u64), &BigUint::from(3u64), &BigUint::one()).is_zero());
+    }
+    /// Wraps thread_rng and counts how many bytes get pulled out of it.
+    struct CountingRng(rand::rngs::ThreadRng, usize);
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 { self.1 += 4; self.0.next_u32() }
+        fn next_u64(&mut self) -> u64 { self.1 += 8; self.0.next_u64() }
+        fn fill_bytes(&mut self, dest: &mut [u8]) { self.1 += dest.len(); self.0.fill_bytes(dest) }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> { self.1 += dest.len(); self.0.try_fill_bytes(dest) }
+    }
+    impl rand::CryptoRng for CountingRng {}
+    #[test]
+    #[allow(non_snake_case)]
+    fn blinding() {
+        let mut rng = CountingRng(rand::thread_rng(), 0);
+        let mut key = RsaPrivateKey::generate(&mut rng, 512);
+        let N = key.n().clone();
+        assert!(key.blinding());
+
+        let x = random_bits(&mut rng, 500);
+        let expected = mod_exp(&x, key.d(), &N);
+        rng.1 = 0;
+        assert_eq!(key.private_exp(&x, &mut rng), expected);
+        assert!(rng.1 > 0, "blinding is on by default and needs randomness");
+        // The blinding factor is fresh every time, and the answer never changes.
+        for _ in 0..10 {
+            assert_eq!(key.private_exp(&x, &mut rng), expected);
+        }
+
+        key.set_blinding(false);
+        rng.1 = 0;
+        assert_eq!(key.private_exp(&x, &mut rng), expected);
+        assert_eq!(rng.1, 0);
+        assert!(key.private_exp(&BigUint::zero(), &mut rng).is_zero());
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn key_generation() {
+        let mut rng = rand::thread_rng();
+        for bits in [32, 64, 128, 200, 256, 512, 1024] {
+            let key = RsaPrivateKey::generate(&mut rng, bits);
+            let public = key.public_key();
+            let (N, e) = (public.n(), public.e());
+            assert_eq!(public.bits(), bits);
+            assert_eq!(*e, BigUint::from(DEFAULT_EXPONENT));
+            assert!(!key::too_close(key.p(), key.q(), bits));
+            assert!(gcd(e, &key::carmichael_lambda(&key.primes())).is_one());
+
+            let d = BigUint::from(rng.next_u64()) % N;
+            let S = sign(&d, &key, &mut rng);
+            assert_eq!(S, mod_exp(&d, key.d(), N));
+            assert!(verify(&S, &public, &d));
+            assert!(!verify(&S, &public, &((&d + 1u64) % N)));
+        }
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn key_sizes() {
+        let mut rng = rand::thread_rng();
+        for bits in [2048, 3072, 4096] {
+            let key = RsaPrivateKey::generate(&mut rng, bits);
+            let public = key.public_key();
+            assert_eq!(public.bits(), bits);
+            assert!(!key::too_close(key.p(), key.q(), bits));
+
+            for pss in [Pss::default(), Pss::new(HashAlg::Sha384), Pss::new(HashAlg::Sha512)] {
+                let S = sign_pss_with(&key, b"hello", pss, &mut rng).unwrap();
+                assert_eq!(S.len(), bits / 8);
+                assert!(verify_pss_with(&S, &public, b"hello", pss));
+                assert!(!verify_pss_with(&S, &public, b"hellp", pss));
+            }
+            for hash in [HashAlg::Sha256, HashAlg::Sha384, HashAlg::Sha512] {
+                let S = sign_pkcs1v15(&key, b"hello", hash, &mut rng).unwrap();
+                assert!(verify_pkcs1v15(&S, &public, b"hello", hash));
+                assert!(!verify_pkcs1v15(&S, &public, b"hellp", hash));
+            }
+        }
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn openssl_interop() {
+        // A 2048-bit key with a PSS signature (SHA-256, 32 byte salt) and a PKCS#1 v1.5 signature
+        // (SHA-384) on "hello", both made with OpenSSL through python's cryptography package.
+        let key = openssl_key();
+        let public = key.public_key();
+        let mut rng = rand::thread_rng();
+        let S = hex("07aeca416f90a3718d101a9afd84d673974109645038cef7cf6977cfed192bef00e27410aadccbce2ebb6b21362b8e7355d11c050036a11e000ed5b034b0ce8f47b8ed400b89708b1698672e8bb67b70de6967aa2b4fdd914582e656d0a9cb9806312b4173c29ecd77ec8e8bc12e686d6446ac3fa2dc9a13f85fbc5d544e1c3006a5b6a1da81e8c409fd6d79f26575e62d87db9e539d079f0b0fd7121e34caf822065e26bf91be8799b71d73071bc9bb880c6ec51ac3f60d775f3a3485d5ff7e8aa7c0cd60595a1bdde7477c756f918d2afd4ccf4de12e814611e281cd06c838189c2e0c55917dcacf210ffa9b99debc31d8c1fe4a80e4e077b2a6dc5e9800f9");
+        assert!(verify_pss(&S, &public, b"hello"));
+        assert!(!verify_pss(&S, &public, b"hello!"));
+        let S = hex("4644c3e845009e56bfa4d5a7f4958a4bd1d217209e6c82b886b27698356f4d3a64dc6a5edfbf8eb4599aa8cbf69a7733090468493037f5c2d113b8ffc558f7a048a7d0f3a08ca7082706710c808c98154f1f1ceb40f81f17ef1cd170e9b8a94b513b75f7696e9de4d0bf29f020d9b7b6738d92197af2d27adf7ebfe5b0386ca099e768c62fa350430b3aa7e80006b872ce1522cad2702234e02370a06b9bc6662e598ada53b1d3a61db7913d8bd81bc0176838fa05039400c0897b5101c9f8b99825e0d7e889cfd815deffcddd0c458acab6e58a33fea0f1cc4bacfd57b64301d251d5589dd02c587103b3d282002a2e5f646fb3fc6ba5c871fdfec03105cb01");
+        assert!(verify_pkcs1v15(&S, &public, b"hello", HashAlg::Sha384));
+        assert!(!verify_pkcs1v15(&S, &public, b"hello", HashAlg::Sha256));
+        // PKCS#1 v1.5 signatures are deterministic, so ours has to come out byte for byte the same.
+        assert_eq!(sign_pkcs1v15(&key, b"hello", HashAlg::Sha384, &mut rng).unwrap(), S);
+    }
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+    /// The 2048-bit key behind the OpenSSL signatures.
+    fn openssl_key() -> RsaPrivateKey {
+        let p = BigUint::parse_bytes(b"dea9c99739f5c7adfa0ff25c0e92466f3300f33cba997c7b09a63295fdad867de666e594ae79516c10ff0df1933f0bf1152e6d8f1076e12ecf9bf0f3a14e5fdf350a34f3905038c0f33e50ca47c0049b3a804dbdf749026dfb841496086cae5c6a5095413425a70ae12016cdc0cb90917ecab1d11777ddce397a8ca9a861e4c9", 16).unwrap();
+        let q = BigUint::parse_bytes(b"d4ddae8b010d7aa7178e702214d0c424e203f89b83dd91184471a2f957afaf218d4b3454e968fff8ca64af5b5bf95edd542c9591d992965510a814902cbafd5989c152c234e91015a1feb7091cffc0994f18ebb0db565d92c436b0e93940158f1abae1e96a64e699cafc5493ec9dd0430ddfd6dae28309b4f8a8fddc59dfa421", 16).unwrap();
+        RsaPrivateKey::from_primes(p, q, BigUint::from(DEFAULT_EXPONENT)).unwrap()
+    }
+    /// The 4096-bit key from RFC 9474, appendix A.
+    fn rfc9474_key() -> RsaPrivateKey {
+        let p = BigUint::parse_bytes(b"e1f4d7a34802e27c7392a3cea32a262a34dc3691bd87f3f310dc75673488930559c120fd0410194fb8a0da55bd0b81227e843fdca6692ae80e5a5d414116d4803fca7d8c30eaaae57e44a1816ebb5c5b0606c536246c7f11985d731684150b63c9a3ad9e41b04c0b5b27cb188a692c84696b742a80d3cd00ab891f2457443dadfeba6d6daf108602be26d7071803c67105a5426838e6889d77e8474b29244cefaf418e381b312048b457d73419213063c60ee7b0d81820165864fef93523c9635c22210956e53a8d96322493ffc58d845368e2416e078e5bcb5d2fd68ae6acfa54f9627c42e84a9d3f2774017e32ebca06308a12ecc290c7cd1156dcccfb2311", 16).unwrap();
+        let q = BigUint::parse_bytes(b"c601a9caea66dc3835827b539db9df6f6f5ae77244692780cd334a006ab353c806426b60718c05245650821d39445d3ab591ed10a7339f15d83fe13f6a3dfb20b9452c6a9b42eaa62a68c970df3cadb2139f804ad8223d56108dfde30ba7d367e9b0a7a80c4fdba2fd9dde6661fc73fc2947569d2029f2870fc02d8325acf28c9afa19ecf962daa7916e21afad09eb62fe9f1cf91b77dc879b7974b490d3ebd2e95426057f35d0a3c9f45f79ac727ab81a519a8b9285932d9b2e5ccd347e59f3f32ad9ca359115e7da008ab7406707bd0e8e185a5ed8758b5ba266e8828f8d863ae133846304a2936ad7bc7c9803879d2fc4a28e69291d73dbd799f8bc238385", 16).unwrap();
+        RsaPrivateKey::from_primes(p, q, BigUint::from(DEFAULT_EXPONENT)).unwrap()
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn blind_signature_vectors() {
+        // RFC 9474, appendix A: the RSABSSA-SHA384-PSS-Deterministic vector.
+        let key = rfc9474_key();
+        let public = key.public_key();
+        assert_eq!(key.n(), &BigUint::from_bytes_be(&hex("aec4d69addc70b990ea66a5e70603b6fee27aafebd08f2d94cbe1250c556e047a928d635c3f45ee9b66d1bc628a03bac9b7c3f416fe20dabea8f3d7b4bbf7f963be335d2328d67e6c13ee4a8f955e05a3283720d3e1f139c38e43e0338ad058a9495c53377fc35be64d208f89b4aa721bf7f7d3fef837be2a80e0f8adf0bcd1eec5bb040443a2b2792fdca522a7472aed74f31a1ebe1eebc1f408660a0543dfe2a850f106a617ec6685573702eaaa21a5640a5dcaf9b74e397fa3af18a2f1b7c03ba91a6336158de420d63188ee143866ee415735d155b7c2d854d795b7bc236cffd71542df34234221a0413e142d8c61355cc44d45bda94204974557ac2704cd8b593f035a5724b1adf442e78c542cd4414fce6f1298182fb6d8e53cef1adfd2e90e1e4deec52999bdc6c29144e8d52a125232c8c6d75c706ea3cc06841c7bda33568c63a6c03817f722b50fcf898237d788a4400869e44d90a3020923dc646388abcc914315215fcd1bae11b1c751fd52443aac8f601087d8d42737c18a3fa11ecd4131ecae017ae0a14acfc4ef85b83c19fed33cfd1cd629da2c4c09e222b398e18d822f77bb378dea3cb360b605e5aa58b20edc29d000a66bd177c682a17e7eb12a63ef7c2e4183e0d898f3d6bf567ba8ae84f84f1d23bf8b8e261c3729e2fa6d07b832e07cddd1d14f55325c6f924267957121902dc19b3b32948bdead5")));
+        assert_eq!(key.d(), &BigUint::from_bytes_be(&hex("0d43242aefe1fb2c13fbc66e20b678c4336d20b1808c558b6e62ad16a287077180b177e1f01b12f9c6cd6c52630257ccef26a45135a990928773f3bd2fc01a313f1dac97a51cec71cb1fd7efc7adffdeb05f1fb04812c924ed7f4a8269925dad88bd7dcfbc4ef01020ebfc60cb3e04c54f981fdbd273e69a8a58b8ceb7c2d83fbcbd6f784d052201b88a9848186f2a45c0d2826870733e6fd9aa46983e0a6e82e35ca20a439c5ee7b502a9062e1066493bdadf8b49eb30d9558ed85abc7afb29b3c9bc644199654a4676681af4babcea4e6f71fe4565c9c1b85d9985b84ec1abf1a820a9bbebee0df1398aae2c85ab580a9f13e7743afd3108eb32100b870648fa6bc17e8abac4d3c99246b1f0ea9f7f93a5dd5458c56d9f3f81ff2216b3c3680a13591673c43194d8e6fc93fc1e37ce2986bd628ac48088bc723d8fbe293861ca7a9f4a73e9fa63b1b6d0074f5dea2a624c5249ff3ad811b6255b299d6bc5451ba7477f19c5a0db690c3e6476398b1483d10314afd38bbaf6e2fbdbcd62c3ca9797a420ca6034ec0a83360a3ee2adf4b9d4ba29731d131b099a38d6a23cc463db754603211260e99d19affc902c915d7854554aabf608e3ac52c19b8aa26ae042249b17b2d29669b5c859103ee53ef9bdc73ba3c6b537d5c34b6d8f034671d7f3a8a6966cc4543df223565343154140fd7391c7e7be03e241f4ecfeb877a051")));
+        let mut rng = rand::thread_rng();
+        let msg = hex("8f3dc6fb8c4a02f4d6352edf0907822c1210a9b32f9bdda4c45a698c80023aa6b59f8cfec5fdbb36331372ebefedae7d");
+        let salt = hex("051722b35f458781397c3a671a7d3bd3096503940e4c4f1aaa269d60300ce449555cd7340100df9d46944c5356825abf");
+        let inv = BigUint::from_bytes_be(&hex("80682c48982407b489d53d1261b19ec8627d02b8cda5336750b8cee332ae260de57b02d72609c1e0e9f28e2040fc65b6f02d56dbd6aa9af8fde656f70495dfb723ba01173d4707a12fddac628ca29f3e32340bd8f7ddb557cf819f6b01e445ad96f874ba235584ee71f6581f62d4f43bf03f910f6510deb85e8ef06c7f09d9794a008be7ff2529f0ebb69decef646387dc767b74939265fec0223aa6d84d2a8a1cc912d5ca25b4e144ab8f6ba054b54910176d5737a2cff011da431bd5f2a0d2d66b9e70b39f4b050e45c0d9c16f02deda9ddf2d00f3e4b01037d7029cd49c2d46a8e1fc2c0c17520af1f4b5e25ba396afc4cd60c494a4c426448b35b49635b337cfb08e7c22a39b256dd032c00adddafb51a627f99a0e1704170ac1f1912e49d9db10ec04c19c58f420212973e0cb329524223a6aa56c7937c5dffdb5d966b6cd4cbc26f3201dd25c80960a1a111b32947bb78973d269fac7f5186530930ed19f68507540eed9e1bab8b00f00d8ca09b3f099aae46180e04e3584bd7ca054df18a1504b89d1d1675d0966c4ae1407be325cdf623cf13ff13e4a28b594d59e3eadbadf6136eee7a59d6a444c9eb4e2198e8a974f27a39eb63af2c9af3870488b8adaad444674f512133ad80b9220e09158521614f1faadfe8505ef57b7df6813048603f0dd04f4280177a11380fbfc861dbcbd7418d62155248dad5fdec0991f"));
+        let encoded_msg = hex("6e0c464d9c2f9fbc147b43570fc4f238e0d0b38870b3addcf7a4217df912ccef17a7f629aa850f63a063925f312d61d6437be954b45025e8282f9c0b1131bc8ff19a8a928d859b37113db1064f92a27f64761c181c1e1f9b251ae5a2f8a4047573b67a270584e089beadcb13e7c82337797119712e9b849ff56e04385d144d3ca9d8d92bf78adb20b5bbeb3685f17038ec6afade3ef354429c51c687b45a7018ee3a6966b3af15c9ba8f40e6461ba0a17ef5a799672ad882bab02b518f9da7c1a962945c2e9b0f02f29b31b9cdf3e633f9d9d2a22e96e1de28e25241ca7dd04147112f578973403e0f4fd80865965475d22294f065e17a1c4a201de93bd14223e6b1b999fd548f2f759f52db71964528b6f15b9c2d7811f2a0a35d534b8216301c47f4f04f412cae142b48c4cdff78bc54df690fd43142d750c671dd8e2e938e6a440b2f825b6dbb3e19f1d7a3c0150428a47948037c322365b7fe6fe57ac88d8f80889e9ff38177bad8c8d8d98db42908b389cb59692a58ce275aa15acb032ca951b3e0a3404b7f33f655b7c7d83a2f8d1b6bbff49d5fcedf2e030e80881aa436db27a5c0dea13f32e7d460dbf01240c2320c2bb5b3225b17145c72d61d47c8f84d1e19417ebd8ce3638a82d395cc6f7050b6209d9283dc7b93fecc04f3f9e7f566829ac41568ef799480c733c09759aa9734e2013d7640dc6151018ea902bc");
+        let blinded_msg = hex("10c166c6a711e81c46f45b18e5873cc4f494f003180dd7f115585d871a28930259654fe28a54dab319cc5011204c8373b50a57b0fdc7a678bd74c523259dfe4fd5ea9f52f170e19dfa332930ad1609fc8a00902d725cfe50685c95e5b2968c9a2828a21207fcf393d15f849769e2af34ac4259d91dfd98c3a707c509e1af55647efaa31290ddf48e0133b798562af5eabd327270ac2fb6c594734ce339a14ea4fe1b9a2f81c0bc230ca523bda17ff42a377266bc2778a274c0ae5ec5a8cbbe364fcf0d2403f7ee178d77ff28b67a20c7ceec009182dbcaa9bc99b51ebbf13b7d542be337172c6474f2cd3561219fe0dfa3fb207cff89632091ab841cf38d8aa88af6891539f263adb8eac6402c41b6ebd72984e43666e537f5f5fe27b2b5aa114957e9a580730308a5f5a9c63a1eb599f093ab401d0c6003a451931b6d124180305705845060ebba6b0036154fcef3e5e9f9e4b87e8f084542fd1dd67e7782a5585150181c01eb6d90cb95883837384a5b91dbb606f266059ecc51b5acbaa280e45cfd2eec8cc1cdb1b7211c8e14805ba683f9b78824b2eb005bc8a7d7179a36c152cb87c8219e5569bba911bb32a1b923ca83de0e03fb10fba75d85c55907dda5a2606bf918b056c3808ba496a4d95532212040a5f44f37e1097f26dc27b98a51837daa78f23e532156296b64352669c94a8a855acf30533d8e0594ace7c442");
+        let blind_sig = hex("364f6a40dbfbc3bbb257943337eeff791a0f290898a6791283bba581d9eac90a6376a837241f5f73a78a5c6746e1306ba3adab6067c32ff69115734ce014d354e2f259d4cbfb890244fd451a497fe6ecf9aa90d19a2d441162f7eaa7ce3fc4e89fd4e76b7ae585be2a2c0fd6fb246b8ac8d58bcb585634e30c9168a434786fe5e0b74bfe8187b47ac091aa571ffea0a864cb906d0e28c77a00e8cd8f6aba4317a8cc7bf32ce566bd1ef80c64de041728abe087bee6cadd0b7062bde5ceef308a23bd1ccc154fd0c3a26110df6193464fc0d24ee189aea8979d722170ba945fdcce9b1b4b63349980f3a92dc2e5418c54d38a862916926b3f9ca270a8cf40dfb9772bfbdd9a3e0e0892369c18249211ba857f35963d0e05d8da98f1aa0c6bba58f47487b8f663e395091275f82941830b050b260e4767ce2fa903e75ff8970c98bfb3a08d6db91ab1746c86420ee2e909bf681cac173697135983c3594b2def673736220452fde4ddec867d40ff42dd3da36c84e3e52508b891a00f50b4f62d112edb3b6b6cc3dbd546ba10f36b03f06c0d82aeec3b25e127af545fac28e1613a0517a6095ad18a98ab79f68801e05c175e15bae21f821e80c80ab4fdec6fb34ca315e194502b8f3dcf7892b511aee45060e3994cd15e003861bc7220a2babd7b40eda03382548a34a7110f9b1779bf3ef6011361611e6bc5c0dc851e1509de1a");
+        let sig = hex("6fef8bf9bc182cd8cf7ce45c7dcf0e6f3e518ae48f06f3c670c649ac737a8b8119a34d51641785be151a697ed7825fdfece82865123445eab03eb4bb91cecf4d6951738495f8481151b62de869658573df4e50a95c17c31b52e154ae26a04067d5ecdc1592c287550bb982a5bb9c30fd53a768cee6baabb3d483e9f1e2da954c7f4cf492fe3944d2fe456c1ecaf0840369e33fb4010e6b44bb1d721840513524d8e9a3519f40d1b81ae34fb7a31ee6b7ed641cb16c2ac999004c2191de0201457523f5a4700dd649267d9286f5c1d193f1454c9f868a57816bf5ff76c838a2eeb616a3fc9976f65d4371deecfbab29362caebdff69c635fe5a2113da4d4d8c24f0b16a0584fa05e80e607c5d9a2f765f1f069f8d4da21f27c2a3b5c984b4ab24899bef46c6d9323df4862fe51ce300fca40fb539c3bb7fe2dcc9409e425f2d3b95e70e9c49c5feb6ecc9d43442c33d50003ee936845892fb8be475647da9a080f5bc7f8a716590b3745c2209fe05b17992830ce15f32c7b22cde755c8a2fe50bd814a0434130b807dc1b7218d4e85342d70695a5d7f29306f25623ad1e8aa08ef71b54b8ee447b5f64e73d09bdd6c3b7ca224058d7c67cc7551e9241688ada12d859cb7646fbd3ed8b34312f3b49d69802f0eaa11bc4211c2f7a29cd5c01ed01a39001c5856fab36228f5ee2f2e1110811872fe7c865c42ed59029c706195d52");
+
+        let variant = BlindVariant::SHA384_PSS_DETERMINISTIC;
+        assert_eq!(prepare(&msg, variant, &mut rng), msg);
+        let hash = variant.pss.hash;
+        assert_eq!(pss::pss_encode(&hash.digest(&[&msg]), public.bits() - 1, hash, &salt).unwrap(), encoded_msg);
+        // The RFC publishes inv rather than r, so r is inv^-1 (mod N).
+        let r = mod_inverse(&inv, key.n()).unwrap();
+        let (blinded, inv_out) = blind::blind_with(&public, &msg, variant, &salt, &r).unwrap();
+        assert_eq!(blinded, blinded_msg);
+        assert_eq!(inv_out, inv);
+        assert_eq!(blind_sign(&key, &blinded, &mut rng).unwrap(), blind_sig);
+        assert_eq!(finalize(&public, &msg, &blind_sig, &inv, variant).unwrap(), sig);
+        assert!(verify_blind(&sig, &public, &msg, variant));
+        assert!(!verify_blind(&sig, &public, &msg[1..], variant));
+
+        // The other variants over the same key, message, salt (none for PSSZERO) and r, with the
+        // prepared message going all the way through. The encoding is worked out on the side, so
+        // blinding has to land on EM * r^e and the finalized signature on EM^d, and a signature
+        // over a prefixed message is no good for the bare one.
+        for variant in [
+            BlindVariant::SHA384_PSS_RANDOMIZED,
+            BlindVariant::SHA384_PSSZERO_RANDOMIZED,
+            BlindVariant::SHA384_PSSZERO_DETERMINISTIC,
+        ] {
+            let prepared = prepare(&msg, variant, &mut rng);
+            assert_eq!(prepared.len(), if variant.randomized { blind::MSG_PREFIX_LEN } else { 0 } + msg.len());
+            assert!(prepared.ends_with(&msg));
+            let salt = &salt[..variant.pss.salt_len];
+            let em = BigUint::from_bytes_be(&pss::pss_encode(&hash.digest(&[&prepared]), public.bits() - 1, hash, salt).unwrap());
+            let (blinded, inv_out) = blind::blind_with(&public, &prepared, variant, salt, &r).unwrap();
+            assert_eq!(BigUint::from_bytes_be(&blinded), &em * r.modpow(&BigUint::from(DEFAULT_EXPONENT), key.n()) % key.n());
+            assert_eq!(inv_out, inv);
+            let blind_sig = blind_sign(&key, &blinded, &mut rng).unwrap();
+            let sig = finalize(&public, &prepared, &blind_sig, &inv, variant).unwrap();
+            assert_eq!(BigUint::from_bytes_be(&sig), em.modpow(key.d(), key.n()));
+            assert!(verify_blind(&sig, &public, &prepared, variant));
+            assert_eq!(verify_blind(&sig, &public, &msg, variant), !variant.randomized);
+        }
+
+        // PSSZERO-Deterministic is plain PSS with no salt, so the whole protocol has to land on
+        // exactly the signature OpenSSL makes with the same key.
+        let variant = BlindVariant::SHA384_PSSZERO_DETERMINISTIC;
+        let key = openssl_key();
+        let public = key.public_key();
+        let msg = prepare(b"anonymous token #1", variant, &mut rng);
+        assert_eq!(msg, b"anonymous token #1");
+        let (blinded, inv) = blind(&public, &msg, variant, &mut rng).unwrap();
+        let blind_sig = blind_sign(&key, &blinded, &mut rng).unwrap();
+        let S = finalize(&public, &msg, &blind_sig, &inv, variant).unwrap();
+        assert_eq!(S, hex("774019b4c9d3240d3420374610c906f17ca6bf7f84352d0e917539e4e49ccd6a0cbf89b729817d34bfd082276382a99726337838201bd6dc4d79003ee5097d551460eaf157222d4ab46400c4c4bf01f8b418846c775f5cba2901a5692c1801d4d34a3a4a5207d668c8829c7fb3b2abf460fc3cfe14f08b3c852426f5c46cf6caefb6f623e454267d03831d8eb614db031e4442a14d786e2994c781b8a9787e0aa2aecf73b745cbb20bb94a7d2e4b659b7a71c83fcbeabf32d996d794b260cb6b5951829d62588adb3df8318218b6d91593454dcad137935f54e9f27a691f9c4d5be0e3bd7cf7494af1ba263bead2e58293cf07f4122e20dfd5d6396d24aa3978"));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn blind_signatures() {
+        let key = openssl_key();
+        let public = key.public_key();
+        let mut rng = rand::thread_rng();
+        for variant in [
+            BlindVariant::SHA384_PSS_RANDOMIZED,
+            BlindVariant::SHA384_PSSZERO_RANDOMIZED,
+            BlindVariant::SHA384_PSS_DETERMINISTIC,
+            BlindVariant::SHA384_PSSZERO_DETERMINISTIC,
+        ] {
+            let msg = prepare(b"hello", variant, &mut rng);
+            assert_eq!(msg.len(), if variant.randomized { 32 + 5 } else { 5 });
+            assert!(msg.ends_with(b"hello"));
+            let (blinded, inv) = blind(&public, &msg, variant, &mut rng).unwrap();
+            // Blinding the same message twice gives the signer nothing to link the two by.
+            let (again, _) = blind(&public, &msg, variant, &mut rng).unwrap();
+            assert_ne!(blinded, again);
+            let blind_sig = blind_sign(&key, &blinded, &mut rng).unwrap();
+            let S = finalize(&public, &msg, &blind_sig, &inv, variant).unwrap();
+            assert!(verify_blind(&S, &public, &msg, variant));
+            assert!(verify_pss_with(&S, &public, &msg, variant.pss));
+            assert!(!verify_blind(&S, &public, b"hellp", variant));
+
+            // The signature is only good for the message that was blinded, and only unblinds with
+            // the right inverse.
+            assert_eq!(finalize(&public, b"hellp", &blind_sig, &inv, variant), Err(RsaError::InvalidSignature));
+            assert_eq!(finalize(&public, &msg, &blind_sig, &(&inv + 1u64), variant), Err(RsaError::InvalidSignature));
+            assert_eq!(finalize(&public, &msg, &blind_sig[1..], &inv, variant), Err(RsaError::InvalidInput));
+        }
+
+        assert_eq!(blind_sign(&key, &[0u8; 255], &mut rng), Err(RsaError::InvalidInput));
+        assert_eq!(blind_sign(&key, &[0xffu8; 256], &mut rng), Err(RsaError::InvalidInput));
+        // r has to be invertible mod N.
+        let variant = BlindVariant::default();
+        assert_eq!(blind::blind_with(&public, b"hello", variant, &[0; 48], key.p()), Err(RsaError::InvalidInput));
+        // SHA-384 with a 48 byte salt needs 98 bytes of EM, and a 512-bit key only has 64.
+        let small = RsaPrivateKey::generate(&mut rng, 512).public_key();
+        assert_eq!(blind(&small, b"hello", variant, &mut rng), Err(RsaError::KeyTooSmall));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn threshold_signatures() {
+        // Every party lives in this one process: the dealer hands out five shares, any three of
+        // them sign, and the combined signature is an ordinary RSA signature.
+        let mut rng = rand::thread_rng();
+        let (public, shares) = deal(&mut rng, 512, 3, 5);
+        assert_eq!((public.threshold(), public.parties(), shares.len()), (3, 5, 5));
+        let n = public.public_key().n();
+        assert!(public.verification_key(0).is_none() && public.verification_key(6).is_none());
+
+        let d = BigUint::from_bytes_be(&HashAlg::Sha256.digest(&[b"threshold"]));
+        let sig_shares: Vec<SignatureShare> = shares.iter().map(|share| sign_share(share, &public, &d, &mut rng).unwrap()).collect();
+        assert!(sig_shares.iter().all(|share| verify_share(share, &public, &d)));
+        assert!(!verify_share(&sig_shares[0], &public, &(&d + 1u64)));
+
+        let S = combine_shares(&public, &d, &[sig_shares[0].clone(), sig_shares[2].clone(), sig_shares[4].clone()]).unwrap();
+        assert!(verify(&S, public.public_key(), &d));
+        assert!(!verify(&S, public.public_key(), &(&d + 1u64)));
+        // RSA signatures are deterministic, so every set of three parties comes up with the same one.
+        for subset in [[0, 1, 2], [1, 3, 4], [4, 2, 0]] {
+            let chosen: Vec<SignatureShare> = subset.iter().map(|&i| sig_shares[i].clone()).collect();
+            assert_eq!(combine_shares(&public, &d, &chosen), Ok(S.clone()));
+        }
+
+        // A share made for another message fails its proof and is skipped; with a fourth honest
+        // share around the signature still comes out.
+        let bad = sign_share(&shares[1], &public, &(&d + 1u64), &mut rng).unwrap();
+        assert!(!verify_share(&bad, &public, &d));
+        let with_bad = [bad.clone(), sig_shares[0].clone(), sig_shares[3].clone(), sig_shares[4].clone()];
+        assert_eq!(combine_shares(&public, &d, &with_bad), Ok(S.clone()));
+        let with_bad = [bad, sig_shares[0].clone(), sig_shares[3].clone()];
+        assert_eq!(combine_shares(&public, &d, &with_bad), Err(RsaError::NotEnoughShares));
+        // The same party twice only counts once.
+        let twice = [sig_shares[0].clone(), sig_shares[0].clone(), sig_shares[3].clone()];
+        assert_eq!(combine_shares(&public, &d, &twice), Err(RsaError::NotEnoughShares));
+        assert_eq!(combine_shares(&public, n, &sig_shares), Err(RsaError::InvalidInput));
+        assert_eq!(sign_share(&shares[0], &public, n, &mut rng), Err(RsaError::InvalidInput));
+        assert_eq!(sign_share(&shares[0], &public, &(n + 1u64), &mut rng), Err(RsaError::InvalidInput));
+
+        // One party holding the whole key is just RSA with extra steps.
+        let (public, shares) = deal_with_exponent(&mut rng, 256, 1, 1, BigUint::from(3u64));
+        let S = combine_shares(&public, &d, &[sign_share(&shares[0], &public, &d, &mut rng).unwrap()]).unwrap();
+        assert!(verify(&S, public.public_key(), &d));
+        // Party 2 of a bigger deal has no verification key in this one-party key.
+        let (_, other) = deal_with_exponent(&mut rng, 256, 1, 2, BigUint::from(3u64));
+        assert!(matches!(sign_share(&other[1], &public, &d, &mut rng), Err(RsaError::InvalidKey(_))));
+
+        for _ in 0..5 {
+            let p = threshold::generate_safe_prime(&mut rng, 64);
+            assert_eq!(p.bits(), 64);
+            assert!(baillie_psw(&p) && baillie_psw(&(p >> 1)));
+        }
+    }
+    #[test]
+    fn pss_encoding() {
+        let salt: Vec<u8> = (0..32).collect();
+        let m_hash = HashAlg::Sha256.digest(&[b"hello"]);
+        let em = pss::pss_encode(&m_hash, 1027, HashAlg::Sha256, &salt).unwrap();
+        assert_eq!(em, hex("022c85ccb1e59bb7469927baaba23772d677e5d92d4c40c8082c00bced771775348846d18aa9776ed84ea49f0723d660c7a3861f246b3a79ec729f334f8a1002dc57374deb2603d0435bd4152a79a141c1cec766030d355cc07ec9dc224ad9ee0946ab314e5e81093b00dae0c435584eb8f6c7da8cf2bab0b104ff46bb5184a3bc"));
+        assert!(pss::pss_verify(&m_hash, &em, 1027, HashAlg::Sha256, 32));
+        assert!(!pss::pss_verify(&m_hash, &em, 1027, HashAlg::Sha256, 31));
+        assert!(!pss::pss_verify(&HashAlg::Sha256.digest(&[b"hellp"]), &em, 1027, HashAlg::Sha256, 32));
+        for i in [0, 1, 60, 94, 95, 110, 128] {
+            let mut bad = em.clone();
+            bad[i] ^= 0x01;
+            assert!(!pss::pss_verify(&m_hash, &bad, 1027, HashAlg::Sha256, 32));
+        }
+
+        let m_hash = HashAlg::Sha512.digest(&[b"attack at dawn"]);
+        let em = pss::pss_encode(&m_hash, 1535, HashAlg::Sha512, &salt[..20]).unwrap();
+        assert_eq!(em, hex("05e5ad4aad958ac3402a6c73b2321b0f23ceee0868d422a61075c16515a5d91a906772ca7b565aa0c55a5ef678b5738770c51dbbac24948d184d8f6b01b75e21fdb497a5cfa775a4cbe4bb7c7bc4803c55a20b039868f4df4f0a55a0db1c82ceb599877a6e01d4ce0b1345d997c7160a953cec00d4f5a6d731d0b474f0a65331905e0cec23f9b9166eb6cd99580c016fe6c2144a2c9ef83a3060c259d57638a4d093ac9f0b0a125bb1def357a39933a951887f1a5f7fec5ff0a6997c584332bc"));
+        assert!(pss::pss_verify(&m_hash, &em, 1535, HashAlg::Sha512, 20));
+
+        // Salt lengths from nothing up to the most that fits.
+        for salt_len in [0, 1, 32, 128 - 32 - 2] {
+            let salt = vec![0x5au8; salt_len];
+            let m_hash = HashAlg::Sha256.digest(&[b"salty"]);
+            let em = pss::pss_encode(&m_hash, 1024, HashAlg::Sha256, &salt).unwrap();
+            assert!(pss::pss_verify(&m_hash, &em, 1024, HashAlg::Sha256, salt_len));
+        }
+        assert_eq!(pss::pss_encode(&[0u8; 32], 1024, HashAlg::Sha256, &[0u8; 95]), Err(RsaError::KeyTooSmall));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn pss_signatures() {
+        // A 256-bit modulus only leaves 32 bytes for EM, which can't even hold a SHA-256 hash
+        // plus the two bytes of framing.
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 256);
+        let public = key.public_key();
+        assert_eq!(sign_pss(&key, b"hello", &mut rng), Err(RsaError::KeyTooSmall));
+        let pss = Pss { hash: HashAlg::Sha256, salt_len: 0 };
+        assert_eq!(sign_pss_with(&key, b"hello", pss, &mut rng), Err(RsaError::KeyTooSmall));
+        assert!(!verify_pss(&[0u8; 32], &public, b"hello"));
+        assert!(!verify_pss(&[0xffu8; 32], &public, b"hello"));
+        assert!(!verify_pss_with(&[0u8; 31], &public, b"hello", pss));
+    }
+    #[test]
+    fn pkcs1v15_encoding() {
+        // Encoded messages recovered from OpenSSL signatures on "hello" with a 1024-bit key.
+        let vectors = [
+            (HashAlg::Sha256, "0001ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff003031300d0609608648016503040201050004202cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"),
+            (HashAlg::Sha384, "0001ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff003041300d06096086480165030402020500043059e1748777448c69de6b800d7a33bbfb9ff1b463e44354c3553bcdb9c666fa90125a3c79f90397bdf5f6a13de828684f"),
+            (HashAlg::Sha512, "0001ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff003051300d0609608648016503040203050004409b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043"),
+        ];
+        for (hash, em) in vectors {
+            assert_eq!(pkcs1v15::pkcs1v15_encode(&hash.digest(&[b"hello"]), 128, hash).unwrap(), hex(em));
+        }
+        // Smallest encoding that still has eight bytes of 0xff.
+        let t_len = 19 + 64;
+        assert!(pkcs1v15::pkcs1v15_encode(&[0u8; 64], t_len + 11, HashAlg::Sha512).is_ok());
+        assert_eq!(pkcs1v15::pkcs1v15_encode(&[0u8; 64], t_len + 10, HashAlg::Sha512), Err(RsaError::KeyTooSmall));
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn pkcs1v15_signatures() {
+        // Even SHA-256 needs 62 bytes of modulus, almost twice what a 256-bit N has.
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate(&mut rng, 256);
+        let public = key.public_key();
+        for hash in [HashAlg::Sha256, HashAlg::Sha384, HashAlg::Sha512] {
+            assert_eq!(sign_pkcs1v15(&key, b"hello", hash, &mut rng), Err(RsaError::KeyTooSmall));
+            assert!(!verify_pkcs1v15(&[0u8; 32], &public, b"hello", hash));
+        }
+    }
+    #[test]
+    #[allow(non_snake_case)]
+    fn multi_prime_signatures() {
+        let mut rng = rand::thread_rng();
+        let key = RsaPrivateKey::generate_multi_prime(&mut rng, 1024, 3);
+        let public = key.public_key();
+        let S = sign_pss(&key, b"hello", &mut rng).unwrap();
+        assert!(verify_pss(&S, &public, b"hello"));
+        let d = BigUint::from(rng.next_u64());
+        assert!(verify(&sign(&d, &key, &mut rng), &public, &d));
 
+        // The three-prime key from OpenSSL and "multi-prime RSA" signed with
+        // "openssl dgst -sha256 -sign". PKCS#1 v1.5 is deterministic, so ours has to match exactly.
+        let der = hex("308204d80201010282010100a94ed22d7c6d1204137d08ae91c023c378181a23d340a2e0908b1d76338aaff71d8f39b09d85de02d4211ce357e2e0c98f13cf0e4a89483c5e7093ef4fc0436455ec46dd1df750527981623844828c96ecca1a957351a87538f596195cb121799242eddadc11f394da5cb492d9915b391b22dd8b9860a115b6835611b883faf4751218ff0d2f13643325486f672e98bfc9b918d5f2259f44b8e4abc8d5e418a823d6c61293c987084400e4a591788cd87a6baf40e46f05289fd4a2151656ae8352e30e3a07525be12d9501ed62c2642e7982945901aaf657ab1b8aff0deea0c33a3010f2ef5a7f0820a81def95457c8820437cffafe5923d7d809e025b57a6350203010001028201004581976597144bf6226e0d57657ca4f2056697aca7787ca58691a619b5953522551f4c86c65483cd537af89a18a58ab514a9043310c485444b5d1a4e7fc625a49665b6bde62476f434efb9fbf19c4acd570ead953727fbdf87fcb3c618aff5e3a940108de540111214577bd4b70bd61cbd977a43a808e6da2a065e3ff190cde0377410c8ec640b98ff4b35df31b1ad679c7668bb5813a02d65b58a52954b7075464e6bf4712022e850ac95d31d017f375bc080a58f8264216b1a25186b7c4fb40a655b45fd459f8fc3364b18e9cea8f6163801dd4b8d9bb459e0ba8ba0a47d678987a093dd62b2a7e1146c85478b163475db60f8f2088871b7b2bd6379ded0b1025607225798e9ac87d58c32f87856b9e7cf0afdbe2105021f80367c58a97513e73080c93b70efd26431315af6ff85959bc7f4e2cd2cb02529385afdf0f5fcf40cea328664f56af55cd0d19b3bf538af4c8478aacfb557ff0256068ff93670dfb3dadccdf8aae74e18777ba98a76e2f7c4323d93dd8b50c97d1328c6fe3b0f430d364c42869a08951d0304372759122de9933a4fa2d10e94c3e9cb9e6ee281f5b7d30265f416f01739eab30c5e573dbd0256040370ee487f544771c0f7019420afe54d54a492a4e3a5df1647ec0521255a49721a43436cd79aa006191b9d72845108339f2837fcbc5b2d261b78649ab29d555fa935c0cf344bcbfa2f6f92ac1a0a5cf819781297f70256053ca188c35c917f87934aecbf773fe921a3335f1a52d49baf2e2faebc827e306aeb19f8717e75adadffacc08136d59711a71ddc63b885dc3abd5055fa87d72bdc950b31f0b9f0bf0ffbe264e7a5f14f0d2142abe61502560433b9dca8897f2883f353aa3383141e225320aa2267401927512f476c890abbd21966a8b94ac27c458851daf9da44cd947067c6101707443743387b44e0db5ff2ef7fbb3ac910a684bf4864f75dd608225cee951d193082010b308201070256039dca437453ce7cc51037ba882e12b9c01dd8c74a5a502ad4ffa78a643ac1425fa146207c27e9c27d34a746d7e69a1862b39e7aa04e441354a1f56ac78754bb2646bd507a9102901eb47e6e5470be761b4a347842270255460d4b035f600d82dfd247b40c40707bc0066a360f1567d84c2c0245c9485c5c5e18af5695b2937be3e310723fc9c3349cc0ce2899237f8ef4de5c2112f56f5fdb14722584197c8c463e16dd1ed6da9b00076e5e1b025602167ad27b948b4b8443fb468aa947b5397a7b6b108afbd7136f45aeba15b65b5cf9ae14cf3561c4a56663fb207bb44d608a760d882ac7f747dea384b8853b25a08a7887b627f6ddc7d56d760cfff1730aba8f51bf36");
+        let key = RsaPrivateKey::from_pkcs1_der(&der).unwrap();
+        assert_eq!(key.primes().len(), 3);
+        assert_eq!(key.to_pkcs1_der(), der);
+        let S = hex("1c24073d1d3138c7b21979a1f6ff8687d7ad23b4761167400c102f1ceb9b53bbbc8d4d7fe84b270e1c175c74d837267dec72c213638df0951a89fb95ca726907161dc27104d08409c4146d6687fa59340b9bc5769b348bf6cd7a5d19f524929a13df543a11fb18ceb87ffda7e4fd71bb705e42c940057c235350050f11fa7f666960cb9328512452d1cfd22daa003e15bdc648d9fa954318ea2463974c090ab58e3b45e9c65e7c2b5f759ef7a81c0fd01cb16bad61ee362f6260d6dc0d383ed6a0de293118868b2c652f874564324f299dd3a86f02c18026534b5d530d077c0f31649fc7e893b993c8527b9bd9e532a583827482c01e492e182610c30c8a7483");
+        assert_eq!(sign_pkcs1v15(&key, b"multi-prime RSA", HashAlg::Sha256, &mut rng).unwrap(), S);
+        assert!(verify_pkcs1v15(&S, &key.public_key(), b"multi-prime RSA", HashAlg::Sha256));
     }
 }
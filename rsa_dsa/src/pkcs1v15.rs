@@ -0,0 +1,68 @@
+use crate::error::RsaError;
+use crate::hash::HashAlg;
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, os2ip};
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+/// Signs a message with RSASSA-PKCS1-v1_5 (RFC 8017, section 8.2). Unlike PSS there is no salt:
+/// the hash is wrapped in a DER DigestInfo that names the hash function and padded out with 0xff
+/// bytes, so the same message always gives the same signature. Mostly useful for talking to
+/// legacy systems that don't know about PSS yet.
+///
+/// Input: private key (key) -> signer's private key.
+/// Input: message (msg) -> bytes to sign. These are hashed for you.
+/// Input: hash function (hash) -> SHA-256, SHA-384 or SHA-512.
+/// Input: random number generator (rng) -> source of the blinding factor. The signature itself
+/// doesn't depend on it.
+///
+/// Output: signature -> exactly k bytes, where k is the size of N in bytes.
+#[allow(non_snake_case)]
+pub fn sign_pkcs1v15<R: CryptoRng + RngCore>(key: &RsaPrivateKey, msg: &[u8], hash: HashAlg, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let k = key.size();
+    let em = pkcs1v15_encode(&hash.digest(&[msg]), k, hash)?;
+    let S = key.private_exp(&os2ip(&em), rng);
+    Ok(i2osp(&S, k).expect("S is smaller than N"))
+}
+
+/// Verifies an RSASSA-PKCS1-v1_5 signature. Instead of parsing the DigestInfo back out (which is
+/// where a lot of historical signature forgeries came from), we build the encoding we expect and
+/// compare the two byte for byte.
+///
+/// Input: signature (S) -> signature to check.
+/// Input: public key (key) -> supposed signer's public key.
+/// Input: message (msg) -> the message that was supposedly signed.
+/// Input: hash function (hash) -> same hash the signer used.
+///
+/// Output: bool -> true means signature is valid. False means that it is invalid.
+#[allow(non_snake_case)]
+pub fn verify_pkcs1v15(S: &[u8], key: &RsaPublicKey, msg: &[u8], hash: HashAlg) -> bool {
+    let k = key.size();
+    if S.len() != k { return false; }
+    let S = os2ip(S);
+    if S >= *key.n() { return false; }
+    let em = match i2osp(&key.public_exp(&S), k) {
+        Some(em) => em,
+        None => return false,
+    };
+    match pkcs1v15_encode(&hash.digest(&[msg]), k, hash) {
+        Ok(expected) => expected.ct_eq(&em).into(),
+        Err(_) => false,
+    }
+}
+
+/// EMSA-PKCS1-v1_5 encoding: EM = 0x00 || 0x01 || 0xff...0xff || 0x00 || DigestInfo || H, with at
+/// least eight bytes of 0xff.
+pub fn pkcs1v15_encode(m_hash: &[u8], em_len: usize, hash: HashAlg) -> Result<Vec<u8>, RsaError> {
+    let prefix = hash.digest_info_prefix();
+    let t_len = prefix.len() + m_hash.len();
+    if em_len < t_len + 11 { return Err(RsaError::KeyTooSmall); }
+
+    let mut em = vec![0x00, 0x01];
+    em.resize(em_len - t_len - 1, 0xff);
+    em.push(0x00);
+    em.extend_from_slice(prefix);
+    em.extend_from_slice(m_hash);
+    Ok(em)
+}
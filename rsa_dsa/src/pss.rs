@@ -0,0 +1,125 @@
+use crate::error::RsaError;
+use crate::hash::{mgf1, HashAlg};
+use crate::key::{RsaPrivateKey, RsaPublicKey};
+use crate::math::{i2osp, os2ip};
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+/// Parameters for RSASSA-PSS. The signer and the verifier have to agree on both of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pss {
+    /// Hash used for the message, for H and for MGF1.
+    pub hash: HashAlg,
+    /// Number of random salt bytes mixed into every signature.
+    pub salt_len: usize,
+}
+
+impl Pss {
+
+    /// PSS with the given hash and a salt as long as the hash output, which is what most
+    /// implementations default to.
+    pub fn new(hash: HashAlg) -> Self {
+        Self { hash, salt_len: hash.size() }
+    }
+}
+
+impl Default for Pss {
+    fn default() -> Self {
+        Self::new(HashAlg::Sha256)
+    }
+}
+
+/// Signs a message with RSASSA-PSS (RFC 8017, section 8.1) using SHA-256 and a 32 byte salt. The
+/// textbook sign raises whatever it is handed straight to d, so S1 * S2 is a valid signature on
+/// d1 * d2. PSS hashes the message and pads it with a random salt before the exponentiation, so
+/// the numbers being signed have no structure an attacker can multiply together.
+///
+/// Input: private key (key) -> signer's private key.
+/// Input: message (msg) -> bytes to sign. These are hashed for you.
+/// Input: random number generator (rng) -> source of the salt and of the blinding factor.
+///
+/// Output: signature -> exactly k bytes, where k is the size of N in bytes.
+pub fn sign_pss<R: CryptoRng + RngCore>(key: &RsaPrivateKey, msg: &[u8], rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    sign_pss_with(key, msg, Pss::default(), rng)
+}
+
+/// Same as sign_pss, but with the hash and the salt length chosen by the caller.
+#[allow(non_snake_case)]
+pub fn sign_pss_with<R: CryptoRng + RngCore>(key: &RsaPrivateKey, msg: &[u8], pss: Pss, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+    let mut salt = vec![0u8; pss.salt_len];
+    rng.fill_bytes(&mut salt);
+    let em = pss_encode(&pss.hash.digest(&[msg]), key.bits() - 1, pss.hash, &salt)?;
+    let S = key.private_exp(&os2ip(&em), rng);
+    Ok(i2osp(&S, key.size()).expect("S is smaller than N"))
+}
+
+/// Verifies an RSASSA-PSS signature made with sign_pss.
+///
+/// Input: signature (S) -> signature to check.
+/// Input: public key (key) -> supposed signer's public key.
+/// Input: message (msg) -> the message that was supposedly signed.
+///
+/// Output: bool -> true means signature is valid. False means that it is invalid.
+#[allow(non_snake_case)]
+pub fn verify_pss(S: &[u8], key: &RsaPublicKey, msg: &[u8]) -> bool {
+    verify_pss_with(S, key, msg, Pss::default())
+}
+
+/// Same as verify_pss, but with the hash and the salt length chosen by the caller.
+#[allow(non_snake_case)]
+pub fn verify_pss_with(S: &[u8], key: &RsaPublicKey, msg: &[u8], pss: Pss) -> bool {
+    if S.len() != key.size() { return false; }
+    let S = os2ip(S);
+    if S >= *key.n() { return false; }
+    let em_bits = key.bits() - 1;
+    let em = match i2osp(&key.public_exp(&S), em_bits.div_ceil(8)) {
+        Some(em) => em,
+        None => return false,
+    };
+    pss_verify(&pss.hash.digest(&[msg]), &em, em_bits, pss.hash, pss.salt_len)
+}
+
+/// EMSA-PSS encoding: EM = maskedDB || H || 0xbc, where H = Hash(0x00 * 8 || mHash || salt) and
+/// DB = PS || 0x01 || salt is masked with MGF1(H). The top bits are cleared so EM fits in
+/// em_bits bits.
+pub fn pss_encode(m_hash: &[u8], em_bits: usize, hash: HashAlg, salt: &[u8]) -> Result<Vec<u8>, RsaError> {
+    let h_len = hash.size();
+    let em_len = em_bits.div_ceil(8);
+    if em_len < h_len + salt.len() + 2 { return Err(RsaError::KeyTooSmall); }
+
+    let h = hash.digest(&[&[0u8; 8], m_hash, salt]);
+    let mut db = vec![0u8; em_len - salt.len() - h_len - 2];
+    db.push(0x01);
+    db.extend_from_slice(salt);
+    let db_mask = mgf1(hash, &h, em_len - h_len - 1);
+    db.iter_mut().zip(db_mask).for_each(|(b, mask)| *b ^= mask);
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    let mut em = db;
+    em.extend(h);
+    em.push(0xbc);
+    Ok(em)
+}
+
+/// EMSA-PSS verification: unmasks DB, checks the padding and recomputes H from the salt.
+pub fn pss_verify(m_hash: &[u8], em: &[u8], em_bits: usize, hash: HashAlg, salt_len: usize) -> bool {
+    let h_len = hash.size();
+    let em_len = em_bits.div_ceil(8);
+    if em.len() != em_len || em_len < h_len + salt_len + 2 { return false; }
+    if em[em_len - 1] != 0xbc { return false; }
+
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - h_len - 1);
+    let top_mask = 0xffu8 >> (8 * em_len - em_bits);
+    if masked_db[0] & !top_mask != 0 { return false; }
+
+    let db_mask = mgf1(hash, h, masked_db.len());
+    let mut db: Vec<u8> = masked_db.iter().zip(db_mask).map(|(b, mask)| b ^ mask).collect();
+    db[0] &= top_mask;
+
+    let ps_len = em_len - h_len - salt_len - 2;
+    if db[..ps_len].iter().any(|&b| b != 0) || db[ps_len] != 0x01 { return false; }
+    let salt = &db[db.len() - salt_len..];
+
+    hash.digest(&[&[0u8; 8], m_hash, salt]).ct_eq(h).into()
+}
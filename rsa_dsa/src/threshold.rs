@@ -0,0 +1,277 @@
+use crate::error::RsaError;
+use crate::euclidian::{gcd, mod_inverse, xgcd};
+use crate::hash::HashAlg;
+use crate::key::{too_close, RsaPublicKey, DEFAULT_EXPONENT};
+use crate::math::{i2osp, mod_exp, mod_exp_ct, os2ip, random_bits, random_range};
+use crate::prime::{baillie_psw, is_probable_prime, SMALL_PRIMES};
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Signed, Zero};
+use rand::{CryptoRng, RngCore};
+
+/// Size of the proof challenges in bits (L1 in Shoup's paper). The random r in a proof gets this
+/// many bits twice over on top of the size of N, so s_i * c + r says nothing about s_i.
+const CHALLENGE_BITS: usize = 256;
+
+/// Everything that is public about a threshold key: the ordinary RSA public key that signatures
+/// verify under, how many parties there are and how many it takes to sign, and the verification
+/// keys v and v_i = v^(s_i) that let anybody check a signature share.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThresholdPublicKey {
+    public: RsaPublicKey,
+    threshold: usize,
+    v: BigUint,
+    verification_keys: Vec<BigUint>,
+}
+
+/// One party's share s_i = f(i) of the signing exponent, where f is the dealer's random polynomial
+/// of degree t - 1 with f(0) = d. Parties are numbered from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyShare {
+    index: usize,
+    secret: BigUint,
+}
+
+/// One party's contribution to a signature: x_i = x^(2Δ s_i) (mod N) along with a proof (c, z)
+/// that x_i was computed with the same s_i that is behind the party's verification key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureShare {
+    index: usize,
+    value: BigUint,
+    c: BigUint,
+    z: BigUint,
+}
+
+impl ThresholdPublicKey {
+
+    /// The RSA public key (N, e) that combined signatures verify under with the ordinary verify.
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public
+    }
+
+    /// How many shares it takes to make a signature (t).
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// How many parties hold a share (l).
+    pub fn parties(&self) -> usize {
+        self.verification_keys.len()
+    }
+
+    /// The base v of the verification keys, a random square mod N.
+    pub fn v(&self) -> &BigUint {
+        &self.v
+    }
+
+    /// v_i = v^(s_i) (mod N) for party i (counting from 1), or None if there is no such party.
+    pub fn verification_key(&self, index: usize) -> Option<&BigUint> {
+        self.verification_keys.get(index.checked_sub(1)?)
+    }
+
+    /// Δ = l!. Every Lagrange coefficient times Δ is an integer, which is what lets us interpolate
+    /// in the exponent without knowing the order of the group.
+    fn delta(&self) -> BigUint {
+        (1..=self.parties() as u64).product()
+    }
+}
+
+impl KeyShare {
+
+    /// Which party this share belongs to (counting from 1).
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl SignatureShare {
+
+    /// Which party made this share (counting from 1).
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// x_i = x^(2Δ s_i) (mod N).
+    pub fn value(&self) -> &BigUint {
+        &self.value
+    }
+}
+
+/// The trusted dealer of Shoup's "Practical Threshold Signatures": makes an RSA key out of two safe
+/// primes, splits d between l parties with Shamir secret sharing so that any t of them can sign,
+/// and then forgets everything. e is the default 65537.
+///
+/// Input: random number generator (rng) -> source of the primes, the polynomial and v.
+/// Input: modulus size (bits) -> size of N in bits.
+/// Input: threshold (t) -> how many parties it takes to sign, at least 1.
+/// Input: parties (l) -> how many shares to hand out, at least t.
+///
+/// Output: (ThresholdPublicKey, shares) -> the public key and one KeyShare per party, in order.
+pub fn deal<R: CryptoRng + RngCore>(rng: &mut R, bits: usize, t: usize, l: usize) -> (ThresholdPublicKey, Vec<KeyShare>) {
+    deal_with_exponent(rng, bits, t, l, BigUint::from(DEFAULT_EXPONENT))
+}
+
+/// Same as deal, but with the public exponent chosen by the caller. Shoup needs e to be a prime
+/// bigger than l, so that it is coprime with Δ = l!.
+pub fn deal_with_exponent<R: CryptoRng + RngCore>(rng: &mut R, bits: usize, t: usize, l: usize, e: BigUint) -> (ThresholdPublicKey, Vec<KeyShare>) {
+    assert!(1 <= t && t <= l, "need 1 <= t <= l");
+    assert!(e > BigUint::from(l) && baillie_psw(&e), "e must be a prime bigger than l");
+    // p = 2p' + 1 and q = 2q' + 1. The squares mod N form a cyclic group of order m = p'q', and
+    // that is where all of the secret sharing happens.
+    let (n, m) = loop {
+        let p = generate_safe_prime(rng, bits / 2);
+        let q = generate_safe_prime(rng, bits - bits / 2);
+        if too_close(&p, &q, bits) { continue; }
+        let m = (&p >> 1) * (&q >> 1);
+        if gcd(&e, &m).is_one() { break (p * q, m); }
+    };
+    let d = mod_inverse(&e, &m).expect("e is coprime with m");
+
+    // f(X) = d + a_1 X + ... + a_(t-1) X^(t-1) over Z_m.
+    let coefficients: Vec<BigUint> = std::iter::once(d).chain((1..t).map(|_| random_range(rng, &BigUint::ZERO, &(&m - 1u64)))).collect();
+    let shares: Vec<KeyShare> = (1..=l).map(|index| {
+        let i = BigUint::from(index);
+        let secret = coefficients.iter().rev().fold(BigUint::ZERO, |acc, a| (acc * &i + a) % &m);
+        KeyShare { index, secret }
+    }).collect();
+
+    let r = loop {
+        let r = random_range(rng, &BigUint::from(2u64), &(&n - 1u64));
+        if gcd(&r, &n).is_one() { break r; }
+    };
+    let v = (&r * &r) % &n;
+    let verification_keys = shares.iter().map(|share| mod_exp_ct(&v, &share.secret, &n)).collect();
+    let public = RsaPublicKey::new(n, e).expect("dealer built a valid modulus");
+    (ThresholdPublicKey { public, threshold: t, v, verification_keys }, shares)
+}
+
+/// One party's half of signing: x_i = x^(2Δ s_i) (mod N), plus a non-interactive proof that
+/// log_v(v_i) = log_(x^(4Δ))(x_i^2). Without the proof, a single dishonest party could hand in
+/// garbage and the combined signature would come out wrong with nobody to blame.
+///
+/// Input: key share (share) -> this party's share of d.
+/// Input: public key (public) -> the threshold public key.
+/// Input: document hash (d) -> the same number that sign would be handed, smaller than N.
+/// Input: random number generator (rng) -> source of the proof's randomness.
+///
+/// Output: SignatureShare -> to be sent to whoever combines the shares, or
+/// RsaError::InvalidKey if the share's index doesn't belong to the public key and
+/// RsaError::InvalidInput if d isn't smaller than N or shares a factor with it.
+pub fn sign_share<R: CryptoRng + RngCore>(share: &KeyShare, public: &ThresholdPublicKey, d: &BigUint, rng: &mut R) -> Result<SignatureShare, RsaError> {
+    let n = public.public.n();
+    let v_i = public.verification_key(share.index).ok_or(RsaError::InvalidKey("key share index is out of range for this public key"))?;
+    if d >= n || !gcd(d, n).is_one() { return Err(RsaError::InvalidInput); }
+    let delta = public.delta();
+    let value = mod_exp_ct(d, &((&delta * &share.secret) << 1), n);
+
+    let x_tilde = mod_exp(d, &(&delta << 2), n);
+    let r = random_bits(rng, public.public.bits() + 2 * CHALLENGE_BITS);
+    let v_r = mod_exp_ct(&public.v, &r, n);
+    let x_r = mod_exp_ct(&x_tilde, &r, n);
+    let c = challenge(public, &x_tilde, v_i, &(&value * &value % n), &v_r, &x_r);
+    let z = &share.secret * &c + r;
+    Ok(SignatureShare { index: share.index, value, c, z })
+}
+
+/// Checks the proof attached to a signature share: recomputes v' = v^z v_i^(-c) and
+/// x' = x~^z (x_i^2)^(-c) and makes sure they hash back to c.
+///
+/// Input: signature share (share) -> share to check.
+/// Input: public key (public) -> the threshold public key.
+/// Input: document hash (d) -> the number being signed.
+///
+/// Output: bool -> true if the share is correct.
+pub fn verify_share(share: &SignatureShare, public: &ThresholdPublicKey, d: &BigUint) -> bool {
+    let n = public.public.n();
+    let v_i = match public.verification_key(share.index) {
+        Some(v_i) => v_i,
+        None => return false,
+    };
+    if share.value >= *n || share.c.bits() as usize > CHALLENGE_BITS { return false; }
+    let x_tilde = mod_exp(d, &(public.delta() << 2), n);
+    let x_i2 = &share.value * &share.value % n;
+    let (v_i_inv, x_i2_inv) = match (mod_inverse(v_i, n), mod_inverse(&x_i2, n)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+    let v_r = mod_exp(&public.v, &share.z, n) * mod_exp(&v_i_inv, &share.c, n) % n;
+    let x_r = mod_exp(&x_tilde, &share.z, n) * mod_exp(&x_i2_inv, &share.c, n) % n;
+    challenge(public, &x_tilde, v_i, &x_i2, &v_r, &x_r) == share.c
+}
+
+/// Combines t signature shares into an ordinary RSA signature y with y^e = x (mod N). Shares that
+/// fail their proof are thrown out, so as long as t honest parties took part the result is right.
+///
+/// Interpolating at 0 in the exponent gives w = x^(4Δ^2 d) (every Lagrange coefficient is scaled
+/// by Δ to keep it an integer), so w^e = x^(4Δ^2). Since e is a prime bigger than l, it is coprime
+/// with 4Δ^2, and with a 4Δ^2 + b e = 1 we get y = w^a x^b.
+///
+/// Input: public key (public) -> the threshold public key.
+/// Input: document hash (d) -> the number being signed.
+/// Input: signature shares (shares) -> shares from at least t different parties.
+///
+/// Output: signature (S) -> accepted by verify with public.public_key(), or
+/// RsaError::NotEnoughShares if fewer than t distinct shares check out.
+#[allow(non_snake_case)]
+pub fn combine_shares(public: &ThresholdPublicKey, d: &BigUint, shares: &[SignatureShare]) -> Result<BigUint, RsaError> {
+    let n = public.public.n();
+    if d >= n || !gcd(d, n).is_one() { return Err(RsaError::InvalidInput); }
+    let mut chosen: Vec<&SignatureShare> = Vec::with_capacity(public.threshold);
+    for share in shares {
+        if chosen.len() == public.threshold { break; }
+        if chosen.iter().any(|s| s.index == share.index) || !verify_share(share, public, d) { continue; }
+        chosen.push(share);
+    }
+    if chosen.len() < public.threshold { return Err(RsaError::NotEnoughShares); }
+
+    let delta = BigInt::from(public.delta());
+    let mut w = BigUint::one();
+    for share in chosen.iter() {
+        let i = BigInt::from(share.index);
+        // λ_i = Δ * prod_(j != i) j / (j - i), which divides exactly.
+        let (num, den) = chosen.iter().filter(|s| s.index != share.index).fold((delta.clone(), BigInt::one()), |(num, den), s| {
+            let j = BigInt::from(s.index);
+            (num * &j, den * (j - &i))
+        });
+        let lambda = (num / den) << 1;
+        w = w * pow_signed(&share.value, &lambda, n).ok_or(RsaError::InvalidInput)? % n;
+    }
+
+    let e_prime = public.delta().pow(2) << 2;
+    let (_, a, b) = xgcd(&e_prime, public.public.e());
+    let S = pow_signed(&w, &a, n).ok_or(RsaError::InvalidInput)? * pow_signed(d, &b, n).ok_or(RsaError::InvalidInput)? % n;
+    if public.public.public_exp(&S) != *d { return Err(RsaError::SigningFailure); }
+    Ok(S)
+}
+
+/// Draws random safe primes p = 2p' + 1 (with p' prime too) of exactly "bits" bits. Candidates
+/// where either p' or p has a small factor are thrown out before any real test runs, since that
+/// is nearly all of them.
+pub fn generate_safe_prime<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> BigUint {
+    assert!(bits >= 16, "primes must be at least 16 bits");
+    loop {
+        let mut half = random_bits(rng, bits - 1);
+        half.set_bit(bits as u64 - 2, true);
+        half.set_bit(bits as u64 - 3, true);
+        half.set_bit(0, true);
+        let p: BigUint = (&half << 1) + 1u64;
+        // Both are far bigger than any of the small primes, so dividing by one means composite.
+        if SMALL_PRIMES.iter().any(|&s| (&half % s).is_zero() || (&p % s).is_zero()) { continue; }
+        if is_probable_prime(&half, rng) && is_probable_prime(&p, rng) { return p; }
+    }
+}
+
+/// g^x (mod n) for a signed x, inverting g for negative exponents. None if g isn't invertible.
+fn pow_signed(g: &BigUint, x: &BigInt, n: &BigUint) -> Option<BigUint> {
+    let g = if x.sign() == Sign::Minus { mod_inverse(g, n)? } else { g.clone() };
+    Some(mod_exp(&g, x.abs().magnitude(), n))
+}
+
+/// The Fiat-Shamir challenge for a share proof: SHA-256 of v, x~, v_i, x_i^2, v' and x', each
+/// written out as k bytes.
+fn challenge(public: &ThresholdPublicKey, x_tilde: &BigUint, v_i: &BigUint, x_i2: &BigUint, v_r: &BigUint, x_r: &BigUint) -> BigUint {
+    let k = public.public.size();
+    let parts: Vec<Vec<u8>> = [&public.v, x_tilde, v_i, x_i2, v_r, x_r].iter().map(|x| i2osp(x, k).expect("reduced mod N")).collect();
+    let parts: Vec<&[u8]> = parts.iter().map(|p| p.as_slice()).collect();
+    os2ip(&HashAlg::Sha256.digest(&parts))
+}
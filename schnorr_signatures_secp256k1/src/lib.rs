@@ -4,6 +4,7 @@ use bigint::uint::U512;
 use rand::RngCore;
 
 mod ecmult;
+mod math;
 mod constants;
 mod euclidian;
 
@@ -50,7 +51,7 @@ pub fn sign(s: U512, m: U512) -> (AffinePoint, U512) {
     h.update(format!("{}", m).as_bytes());
     h.update(format!("{}", s1.x).as_bytes());
     let hn = U512::from_little_endian(h.finalize().as_bytes()) % order;
-    let s2 = (k + ((s * hn) % order)) % order;
+    let s2 = math::mod_add(k, math::mod_mul(s, hn, order), order);
     (s1, s2)
 }
 
@@ -117,6 +118,7 @@ mod tests {
 
     use super::*;
     #[test]
+    #[allow(clippy::useless_format)]
     fn schnorr_runthrough() {
         let q = U512::from_big_endian(&CURVE_ORDER);
         let secret_key = U512::from(rand::thread_rng().next_u64());
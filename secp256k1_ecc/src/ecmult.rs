@@ -1,4 +1,5 @@
-use crate::euclidian::modinv;
+use crate::euclidian::mod_inverse;
+use crate::math::{mod_add, mod_mul, mod_sub};
 use bigint::uint::U512;
 use crate::constants::*;
 
@@ -9,6 +10,7 @@ pub struct AffinePoint {
     pub infinity: bool,
 }
 
+#[allow(clippy::clone_on_copy)]
 impl AffinePoint {
 
     /// The ecadd function is the backbone of multiplying two elliptic curves. We add two points on an
@@ -29,39 +31,20 @@ impl AffinePoint {
         let f = U512::from_big_endian(&FIELD_SIZE);
         if self.infinity { return other.clone(); }
         if other.infinity { return self.clone(); }
-        if self.x == other.x {
-            if self.y == (f - other.y) { return AffinePoint::default(); }
-        }
-
-        let (dy, dx) = self.slope(&other, f);
-        let dx = modinv(dx, f);
-        let lambda = (dy * dx) % f;
-        
-        let x3 = {
-            if (lambda * lambda) % f < ((self.x + other.x) % f) {
-                f - (((self.x + other.x) % f) - ((lambda * lambda) % f))
-            }
-            else {
-                ((lambda * lambda) % f) - ((self.x + other.x) % f)
-            }
+        if self.x == other.x && self.y == (f - other.y) { return AffinePoint::default(); }
+
+        let (dy, dx) = self.slope(other, f);
+        // No inverse means a vertical line (doubling a point with y = 0), which only meets the
+        // curve again at infinity.
+        let dx = match mod_inverse(dx, f) {
+            Some(inv) => inv,
+            None => return AffinePoint::default(),
         };
+        let lambda = mod_mul(dy, dx, f);
 
-        let y3 = {
-            let t;
-            if self.x > x3 {
-                t = self.x - x3;
-            }
-            else {
-                t = f - (x3 - self.x);
-            }
-
-            if lambda * t > self.y {
-                (lambda * t - self.y) % f
-            }
-            else {
-                f - ((self.y - (lambda * t) % f))
-            }
-        };
+        // x3 = lambda^2 - x1 - x2 and y3 = lambda * (x1 - x3) - y1, all mod f.
+        let x3 = mod_sub(mod_mul(lambda, lambda, f), mod_add(self.x, other.x, f), f);
+        let y3 = mod_sub(mod_mul(lambda, mod_sub(self.x, x3, f), f), self.y, f);
 
         AffinePoint::new(x3, y3)
     }
@@ -132,23 +115,10 @@ impl AffinePoint {
     pub fn slope(&self, other: &AffinePoint, field: U512) -> (U512, U512) {
 
         if self.eq(other) {
-            return ((((U512::from(3u32) * self.x) % field) * self.x) % field, (U512::from(2u32) * self.y) % field);
-        }
-        let y;
-        if self.y > other.y {
-            y = field - (self.y - other.y);
-        }
-        else {
-            y = other.y - self.y;
-        }
-        let x;
-        if self.x > other.x {
-            x = field - (self.x - other.x);
-        }
-        else {
-            x = other.x - self.x;
+            let x2 = mod_mul(self.x, self.x, field);
+            return (mod_mul(U512::from(3u32), x2, field), mod_add(self.y, self.y, field));
         }
-        (y, x)
+        (mod_sub(other.y, self.y, field), mod_sub(other.x, self.x, field))
     }
 
 }
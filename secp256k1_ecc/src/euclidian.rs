@@ -1,22 +1,86 @@
 use bigint::uint::U512;
 
+/// U512 has no sign bit, so the Bezout coefficients coming out of xgcd are a magnitude plus a flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signed {
+    pub magnitude: U512,
+    pub negative: bool,
+}
+
+/// gcd(a, b) by Euclid's algorithm. gcd(a, 0) = a, so a zero on either side is fine.
+#[allow(dead_code)]
+pub fn gcd(a: U512, b: U512) -> U512 {
+    let (mut r0, mut r1) = (a, b);
+    while r1 != U512::zero() {
+        let r2 = r0 % r1;
+        r0 = r1;
+        r1 = r2;
+    }
+    r0
+}
+
+/// Stein's binary GCD. It only shifts and subtracts, and U512 division is slow enough that this
+/// comes out ahead of Euclid once the numbers get big.
 #[allow(dead_code)]
-pub fn modinv(e: U512, p: U512) -> U512 {
-    if p == U512::one() { return U512::one(); }
-    let (mut a, mut m, mut x, mut inv) = (e.clone(), p.clone(), U512::zero(), U512::one());
+pub fn binary_gcd(a: U512, b: U512) -> U512 {
+    if a == U512::zero() { return b; }
+    if b == U512::zero() { return a; }
+    // gcd(2^k * a', 2^k * b') = 2^k * gcd(a', b'), and once one side is odd the other side's
+    // factors of two can be thrown away.
+    let shift = (a | b).trailing_zeros() as usize;
+    let mut a = a >> a.trailing_zeros() as usize;
+    let mut b = b;
+    loop {
+        b = b >> b.trailing_zeros() as usize;
+        if a > b { std::mem::swap(&mut a, &mut b); }
+        b = b - a;
+        if b == U512::zero() { return a << shift; }
+    }
+}
 
-    while a > U512::one() {
-        let div = a / m;
-        let rem = a % m;
-        if (div * x) > inv {
-            inv = p - (((div * x) % p) - inv);
-        }
-        else {
-            inv = (inv - (div * x)) % p;
-        }
-        a = rem;
-        std::mem::swap(&mut a, &mut m);
-        std::mem::swap(&mut x, &mut inv);
+/// The extended Euclidean algorithm. Along with g = gcd(a, b) it finds x and y such that
+/// a * x + b * y = g.
+///
+/// Every remainder r_i is a combination s_i * a + t_i * b, with s_{i+1} = s_{i-1} - q_i * s_i.
+/// The signs of s_i (and of t_i) flip every step, so the subtraction is really an addition of
+/// magnitudes, and we only need to remember how many steps we took to get the signs back. The
+/// magnitudes never get bigger than b / g and a / g, so nothing overflows.
+///
+/// Input: first number (a) -> any U512.
+/// Input: second number (b) -> any U512.
+///
+/// Output: (g, x, y) -> gcd and Bezout coefficients.
+#[allow(dead_code)]
+pub fn xgcd(a: U512, b: U512) -> (U512, Signed, Signed) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut s0, mut s1) = (U512::one(), U512::zero());
+    let (mut t0, mut t1) = (U512::zero(), U512::one());
+    let mut odd = false;
+    while r1 != U512::zero() {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 + q * s1);
+        (t0, t1) = (t1, t0 + q * t1);
+        odd = !odd;
     }
-    inv
+    let x = Signed { magnitude: s0, negative: odd && s0 != U512::zero() };
+    let y = Signed { magnitude: t0, negative: !odd && t0 != U512::zero() };
+    (r0, x, y)
+}
+
+/// a^-1 (mod m), or None if there isn't one (gcd(a, m) != 1, or m = 0). Dividing by something
+/// without an inverse is always a bug or an attack somewhere upstream, so callers have to decide
+/// what it means instead of getting garbage back.
+///
+/// Input: number to invert (a) -> any U512.
+/// Input: modulus (m) -> the modulus.
+///
+/// Output: Some(x) with a * x = 1 (mod m) and x < m, or None.
+#[allow(dead_code)]
+pub fn mod_inverse(a: U512, m: U512) -> Option<U512> {
+    if m == U512::zero() { return None; }
+    let (g, x, _) = xgcd(a % m, m);
+    if g != U512::one() { return None; }
+    let magnitude = x.magnitude % m;
+    if x.negative && magnitude != U512::zero() { Some(m - magnitude) } else { Some(magnitude) }
 }
@@ -4,6 +4,7 @@ use bigint::uint::U512;
 use rand::RngCore;
 
 mod ecmult;
+mod math;
 mod euclidian;
 mod constants;
 
@@ -116,7 +117,8 @@ pub fn secp256k1_ct() -> bool {
 ///
 /// Output: bool -> true if the point is on the curve, false otherwise.
 pub fn verify(a: AffinePoint, f: U512) -> bool {
-    ((a.y * a.y) % f) == ((((a.x * a.x) % f) * a.x) + U512::from(7u32)) % f
+    let x3 = math::mod_mul(math::mod_mul(a.x, a.x, f), a.x, f);
+    math::mod_mul(a.y, a.y, f) == math::mod_add(x3, U512::from(7u32), f)
 }
 
 /// As the name suggests, it produces a random point on secp256k1 by taking a random value and
@@ -188,4 +190,21 @@ mod tests {
         }
         v.into_iter().for_each(|x| { x.join().unwrap_or(()); });
     }
+
+    #[test]
+    fn field_arithmetic() {
+        let f = U512::from_big_endian(&FIELD_SIZE);
+        let a = U512::from_dec_str("5069199420743173515454659526953258370306277859387667389342862441580786705715").unwrap();
+        let b = U512::from_dec_str("46399075254699458125328640885121446688154974959808760309684141998719049315233").unwrap();
+        let ab = U512::from_dec_str("13712413852936706497166750477476048257778910093173278471329531952397703967170").unwrap();
+        assert_eq!(math::mod_mul(a, b, f), ab);
+        assert_eq!(math::mod_mul(f - U512::one(), f - U512::one(), f), U512::one());
+        assert_eq!(math::mod_add(f - U512::one(), U512::one(), f), U512::zero());
+        assert_eq!(math::mod_sub(U512::zero(), U512::one(), f), f - U512::one());
+        assert_eq!(math::mod_sub(a, a, f), U512::zero());
+        let inv = U512::from_dec_str("15515627914071999978080022206909131485396609542878889692978844188267657345602").unwrap();
+        assert_eq!(euclidian::mod_inverse(a, f), Some(inv));
+        assert_eq!(euclidian::mod_inverse(U512::zero(), f), None);
+        assert_eq!(euclidian::mod_inverse(f, f), None);
+    }
 }
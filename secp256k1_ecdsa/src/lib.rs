@@ -1,10 +1,11 @@
 use crate::ecmult::*;
 use crate::constants::*;
 use bigint::uint::U512;
-use euclidian::modinv;
+use euclidian::mod_inverse;
 use rand::RngCore;
 
 mod ecmult;
+mod math;
 mod euclidian;
 mod constants;
 
@@ -46,11 +47,14 @@ pub fn sign(s: U512, d: U512) -> (U512, U512) {
         U512::from_big_endian(&GENERATOR_Y)
     );
 
-    let e = U512::from(rand::thread_rng().next_u64());
+    // e = 0 is the only draw without an inverse, since the order is prime.
+    let (e, inv_e) = loop {
+        let e = U512::from(rand::thread_rng().next_u64());
+        if let Some(inv_e) = mod_inverse(e, order) { break (e, inv_e); }
+    };
     let p1 = generator.naf_ecmult(&e);
     let s1 = p1.x % order;
-    let inv_e = modinv(e, order);
-    let s2 = (((d + ((s * s1) % order)) % order) * inv_e) % order;
+    let s2 = math::mod_mul(math::mod_add(d, math::mod_mul(s, s1, order), order), inv_e, order);
     (s1, s2)
 }
 
@@ -73,11 +77,15 @@ pub fn verify_signature(v: AffinePoint, s1: U512, s2: U512, d: U512) -> bool {
         U512::from_big_endian(&GENERATOR_Y)
     );
 
-    let inv_s2 = modinv(s2, order);
+    // A zero (mod n) s2 can't come out of sign, and without an inverse there's nothing to check.
+    let inv_s2 = match mod_inverse(s2, order) {
+        Some(inv) => inv,
+        None => return false,
+    };
 
-    let v1 = (inv_s2 * d) % order;
+    let v1 = math::mod_mul(inv_s2, d, order);
 
-    let v2 = (s1 * inv_s2) % order;
+    let v2 = math::mod_mul(s1, inv_s2, order);
     let r1 = generator.naf_ecmult(&v1);
     let r2 = v.naf_ecmult(&v2);
 
@@ -117,7 +125,8 @@ pub fn secp256k1_ecdsa_expected_fail() -> bool {
 }
 
 pub fn verify(a: AffinePoint, f: U512) -> bool {
-    ((a.y * a.y) % f) == ((((a.x * a.x) % f) * a.x) + U512::from(7u32)) % f
+    let x3 = math::mod_mul(math::mod_mul(a.x, a.x, f), a.x, f);
+    math::mod_mul(a.y, a.y, f) == math::mod_add(x3, U512::from(7u32), f)
 }
 
 #[cfg(test)]
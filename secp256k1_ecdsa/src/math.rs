@@ -0,0 +1,61 @@
+use bigint::uint::U512;
+
+/// a * b (mod m) for any m < 2^512. The product is computed at full width (1024 bits) and then
+/// reduced, so unlike (a * b) % m it can't wrap around no matter how big the operands are.
+///
+/// Input: first factor (a) -> any U512.
+/// Input: second factor (b) -> any U512.
+/// Input: modulus (m) -> nonzero modulus.
+///
+/// Output: product -> a * b reduced into [0, m).
+#[allow(dead_code)]
+pub fn mod_mul(a: U512, b: U512, m: U512) -> U512 {
+    let (hi, lo) = wide_mul(a % m, b % m);
+    // Both factors are below m, so if m is at most 256 bits the product always fits in the low
+    // half and a single division does the job.
+    if hi == U512::zero() { return lo % m; }
+
+    // Otherwise reduce hi * 2^512 + lo one bit at a time: start from hi (mod m) and shift the
+    // bits of lo in from the top, doubling and reducing as we go.
+    let mut r = hi % m;
+    for i in (0..512).rev() {
+        r = mod_add(r, r, m);
+        if lo.bit(i) { r = mod_add(r, U512::one(), m); }
+    }
+    r
+}
+
+/// a + b (mod m) for a, b < m. The sum can spill past 2^512 when m is close to it, in which case
+/// the wrapped result plus the lost 2^512 is still less than 2m, so one subtraction fixes it.
+#[allow(dead_code)]
+pub fn mod_add(a: U512, b: U512, m: U512) -> U512 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= m { sum.overflowing_sub(m).0 } else { sum }
+}
+
+/// a - b (mod m) for a, b < m.
+#[allow(dead_code)]
+pub fn mod_sub(a: U512, b: U512, m: U512) -> U512 {
+    if a >= b { a - b } else { m - (b - a) }
+}
+
+/// The full 1024-bit product of a and b as (high half, low half), by schoolbook multiplication on
+/// the 64-bit limbs.
+#[allow(dead_code)]
+pub fn wide_mul(a: U512, b: U512) -> (U512, U512) {
+    let (U512(x), U512(y)) = (a, b);
+    let mut limbs = [0u64; 16];
+    for i in 0..8 {
+        let mut carry = 0u128;
+        for j in 0..8 {
+            let t = x[i] as u128 * y[j] as u128 + limbs[i + j] as u128 + carry;
+            limbs[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        limbs[i + 8] = carry as u64;
+    }
+    let (mut hi, mut lo) = ([0u64; 8], [0u64; 8]);
+    lo.copy_from_slice(&limbs[..8]);
+    hi.copy_from_slice(&limbs[8..]);
+    (U512(hi), U512(lo))
+}